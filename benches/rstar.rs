@@ -49,6 +49,31 @@ fn insert_rng_bench_3d_10000(b: &mut Bencher) {
     });
 }
 
+fn bulk_load_tree_with_size(count: usize) -> RStarTree<f64, 3, Point<f64, 3>, usize> {
+    let mut tree_map = RStar::new_with_max(32);
+    tree_map.bulk_load((0..count).map(|i| {
+        (
+            Point::new([random::<f64>(), random::<f64>(), random::<f64>()]),
+            i,
+        )
+    }));
+    tree_map
+}
+
+#[bench]
+fn bulk_load_rng_bench_3d_1000(b: &mut Bencher) {
+    b.iter(|| {
+        bulk_load_tree_with_size(1000);
+    });
+}
+
+#[bench]
+fn bulk_load_rng_bench_3d_10000(b: &mut Bencher) {
+    b.iter(|| {
+        bulk_load_tree_with_size(10000);
+    });
+}
+
 fn search_rng_bench_3d(b: &mut Bencher, size: usize) {
     let tree_map = generate_tree_with_size(size);
     b.iter(|| {
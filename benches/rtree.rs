@@ -4,7 +4,6 @@ extern crate rand;
 extern crate spatial;
 extern crate test;
 
-use rand::Rng;
 use spatial::geometry::{Point, Rect};
 use spatial::tree::mbr::MbrRectQuery;
 use spatial::{RLinearTree, RQuadraticTree, RTree};
@@ -8,32 +8,99 @@
 //! Various geometric shapes to insert into spatial trees
 
 use num::{Bounded, Float};
+use ordered_float::OrderedFloat;
 use std::convert::{AsMut, AsRef, TryInto};
-use std::fmt::Debug;
+use std::error::Error;
+use std::fmt::{self, Debug};
 use std::ops::{Deref, DerefMut};
-use crate::FP;
+use crate::tree::mbr::DynLeafGeometry;
+use crate::{Scalar, FP};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Errors returned by the fallible geometry constructors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryError {
+    /// A coordinate was NaN or infinite
+    NonFiniteCoordinate,
+    /// A direction had no length
+    ZeroDirection,
+    /// A line string had fewer than 2 vertices, or a polygon ring fewer than 3
+    TooFewVertices,
+    /// A collection of geometries was empty
+    Empty,
+    /// A radius was negative
+    NegativeRadius,
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GeometryError::NonFiniteCoordinate => write!(f, "coordinates must be finite"),
+            GeometryError::ZeroDirection => write!(f, "direction must have a non-zero length"),
+            GeometryError::TooFewVertices => write!(f, "too few vertices"),
+            GeometryError::Empty => write!(f, "geometry must not be empty"),
+            GeometryError::NegativeRadius => write!(f, "radius must not be negative"),
+        }
+    }
+}
+
+impl Error for GeometryError {}
+
+fn check_finite<P: Scalar>(coord: P) -> Result<P, GeometryError> {
+    if coord.is_finite() {
+        Ok(coord)
+    } else {
+        Err(GeometryError::NonFiniteCoordinate)
+    }
+}
 
 /// An n-dimensional point
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound(
+            serialize = "P: Serialize",
+            deserialize = "P: Scalar + Deserialize<'de>"
+        ),
+        try_from = "crate::serde_geometry::RawPoint<P, DIM>"
+    )
+)]
 pub struct Point<P, const DIM: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
     pub coords: [P; DIM],
 }
 
-impl<P: FP, const DIM: usize> Point<P, DIM> {
+impl<P: Scalar, const DIM: usize> Point<P, DIM> {
     /// New Point from a `GenericArray`
     pub fn new(coords: [P; DIM]) -> Point<P, DIM> {
         for coord in &coords {
-            assert!(Float::is_finite(*coord), "{:?} should be finite", coord);
+            assert!(coord.is_finite(), "{:?} should be finite", coord);
         }
         Point { coords }
     }
+    /// New Point from a `GenericArray`, failing if any coordinate isn't finite
+    pub fn try_new(coords: [P; DIM]) -> Result<Point<P, DIM>, GeometryError> {
+        for &coord in &coords {
+            check_finite(coord)?;
+        }
+        Ok(Point { coords })
+    }
     /// New Point from a slice
     pub fn from_slice(slice: &[P]) -> Point<P, DIM> {
         Point::new(slice.try_into().unwrap())
     }
+    /// The point converted to its measure type, for computing distances
+    pub fn to_measure(&self) -> Point<P::Measure, DIM> {
+        Point {
+            coords: self.coords.map(Scalar::to_measure),
+        }
+    }
 }
 
-impl<P: FP, const DIM: usize> Deref for Point<P, DIM> {
+impl<P: Scalar, const DIM: usize> Deref for Point<P, DIM> {
     type Target = [P];
 
     fn deref(&self) -> &[P] {
@@ -41,26 +108,27 @@ impl<P: FP, const DIM: usize> Deref for Point<P, DIM> {
     }
 }
 
-impl<P: FP, const DIM: usize> DerefMut for Point<P, DIM> {
+impl<P: Scalar, const DIM: usize> DerefMut for Point<P, DIM> {
     fn deref_mut(&mut self) -> &mut [P] {
         &mut self.coords
     }
 }
 
-impl<P: FP, const DIM: usize> AsRef<[P]> for Point<P, DIM> {
+impl<P: Scalar, const DIM: usize> AsRef<[P]> for Point<P, DIM> {
     fn as_ref(&self) -> &[P] {
         &self.coords
     }
 }
 
-impl<P: FP, const DIM: usize> AsMut<[P]> for Point<P, DIM> {
+impl<P: Scalar, const DIM: usize> AsMut<[P]> for Point<P, DIM> {
     fn as_mut(&mut self) -> &mut [P] {
         &mut self.coords
     }
 }
 
 /// An n-dimensional line segment
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LineSegment<P: FP, const DIM: usize> {
     // TODO: Would this be better as [(P,P)]?
     pub x: Point<P, DIM>,
@@ -75,6 +143,13 @@ impl<P: FP, const DIM: usize> LineSegment<P, DIM> {
             y: Point::new(y),
         }
     }
+    /// New LineSegment from two GenericArrays, failing if any coordinate isn't finite
+    pub fn try_new(x: [P; DIM], y: [P; DIM]) -> Result<LineSegment<P, DIM>, GeometryError> {
+        Ok(LineSegment {
+            x: Point::try_new(x)?,
+            y: Point::try_new(y)?,
+        })
+    }
     /// New LineSegment from two slices representing either end
     pub fn from_slices(x: &[P], y: &[P]) -> LineSegment<P, DIM> {
         LineSegment {
@@ -84,25 +159,105 @@ impl<P: FP, const DIM: usize> LineSegment<P, DIM> {
     }
 }
 
+/// An n-dimensional polyline, connecting each vertex to the next
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "crate::serde_geometry::RawLineString<P, DIM>")
+)]
+pub struct LineString<P: FP, const DIM: usize> {
+    vertices: Vec<Point<P, DIM>>,
+    mbr: Rect<P, DIM>,
+}
+
+impl<P: FP, const DIM: usize> LineString<P, DIM> {
+    /// New LineString from its vertices
+    pub fn new(vertices: Vec<[P; DIM]>) -> LineString<P, DIM> {
+        match LineString::try_new(vertices) {
+            Ok(line) => line,
+            Err(err) => panic!("not a valid line string: {}", err),
+        }
+    }
+
+    /// New LineString from its vertices, failing if a coordinate isn't finite or there are fewer than 2 vertices
+    pub fn try_new(vertices: Vec<[P; DIM]>) -> Result<LineString<P, DIM>, GeometryError> {
+        use crate::tree::mbr::MbrLeafGeometry;
+        if vertices.len() < 2 {
+            return Err(GeometryError::TooFewVertices);
+        }
+        let vertices = vertices
+            .into_iter()
+            .map(Point::try_new)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut mbr = Rect::max_inverted();
+        for vertex in &vertices {
+            vertex.expand_mbr_to_fit(&mut mbr);
+        }
+        Ok(LineString { vertices, mbr })
+    }
+
+    /// The vertices
+    pub fn vertices(&self) -> &[Point<P, DIM>] {
+        &self.vertices
+    }
+
+    /// The line string's minimum bounding rectangle
+    pub fn mbr(&self) -> &Rect<P, DIM> {
+        &self.mbr
+    }
+
+    /// Every segment, in order
+    pub fn segments(&self) -> impl Iterator<Item = LineSegment<P, DIM>> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().skip(1))
+            .map(|(x, y)| LineSegment {
+                x: x.clone(),
+                y: y.clone(),
+            })
+    }
+}
+
 /// An n-dimensional rectangle
-#[derive(Debug, Clone)]
-pub struct Rect<P: FP, const DIM: usize> {
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound(serialize = "P: Serialize", deserialize = "P: Deserialize<'de>"),
+        try_from = "crate::serde_geometry::RawRect<P, DIM>"
+    )
+)]
+pub struct Rect<P: Scalar, const DIM: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
     pub edges: [(P, P); DIM],
 }
 
-impl<P: FP, const DIM: usize> Rect<P, DIM> {
+impl<P: Scalar, const DIM: usize> Rect<P, DIM> {
     /// New Rect from a `GenericArray`
     pub fn new(mut edges: [(P, P); DIM]) -> Rect<P, DIM> {
         // ensure that the edge coordinates are valid and ordered correctly
         for &mut (ref mut x, ref mut y) in &mut edges {
-            assert!(Float::is_finite(*x), "{:?} should be finite", x);
-            assert!(Float::is_finite(*y), "{:?} should be finite", y);
-            *x = Float::min(*x, *y);
-            *y = Float::max(*x, *y);
+            assert!(x.is_finite(), "{:?} should be finite", x);
+            assert!(y.is_finite(), "{:?} should be finite", y);
+            let (a, b) = (*x, *y);
+            *x = a.partial_min(b);
+            *y = a.partial_max(b);
         }
         Rect { edges }
     }
 
+    /// New Rect from a `GenericArray`, failing if any coordinate isn't finite
+    pub fn try_new(mut edges: [(P, P); DIM]) -> Result<Rect<P, DIM>, GeometryError> {
+        for &mut (ref mut x, ref mut y) in &mut edges {
+            let (a, b) = (check_finite(*x)?, check_finite(*y)?);
+            *x = a.partial_min(b);
+            *y = a.partial_max(b);
+        }
+        Ok(Rect { edges })
+    }
+
     /// New Rect from corners
     pub fn from_corners(x: [P; DIM], y: [P; DIM]) -> Rect<P, DIM> {
         use crate::tree::mbr::MbrLeafGeometry;
@@ -112,6 +267,15 @@ impl<P: FP, const DIM: usize> Rect<P, DIM> {
         edges
     }
 
+    /// New Rect from corners, failing if any coordinate isn't finite
+    pub fn try_from_corners(x: [P; DIM], y: [P; DIM]) -> Result<Rect<P, DIM>, GeometryError> {
+        use crate::tree::mbr::MbrLeafGeometry;
+        let mut edges = Rect::max_inverted();
+        Point::try_new(x)?.expand_mbr_to_fit(&mut edges);
+        Point::try_new(y)?.expand_mbr_to_fit(&mut edges);
+        Ok(edges)
+    }
+
     /// An inverted Rect where ever dimension's (x, y) coordinates are (MAX, MIN). Simplifies finding boundaries.
     pub fn max_inverted() -> Rect<P, DIM> {
         let edges = [(Bounded::max_value(), Bounded::min_value()); DIM];
@@ -123,9 +287,16 @@ impl<P: FP, const DIM: usize> Rect<P, DIM> {
         let edges = [(Bounded::min_value(), Bounded::max_value()); DIM];
         Rect { edges }
     }
+
+    /// The rect converted to its measure type, for computing areas and distances
+    pub fn to_measure(&self) -> Rect<P::Measure, DIM> {
+        Rect {
+            edges: self.edges.map(|(x, y)| (x.to_measure(), y.to_measure())),
+        }
+    }
 }
 
-impl<P: FP, const DIM: usize> Deref for Rect<P, DIM> {
+impl<P: Scalar, const DIM: usize> Deref for Rect<P, DIM> {
     type Target = [(P, P)];
 
     fn deref(&self) -> &[(P, P)] {
@@ -133,33 +304,625 @@ impl<P: FP, const DIM: usize> Deref for Rect<P, DIM> {
     }
 }
 
-impl<P: FP, const DIM: usize> DerefMut for Rect<P, DIM> {
+impl<P: Scalar, const DIM: usize> DerefMut for Rect<P, DIM> {
     fn deref_mut(&mut self) -> &mut [(P, P)] {
         &mut self.edges
     }
 }
 
-impl<P: FP, const DIM: usize> AsRef<[(P, P)]> for Rect<P, DIM> {
+impl<P: Scalar, const DIM: usize> AsRef<[(P, P)]> for Rect<P, DIM> {
     fn as_ref(&self) -> &[(P, P)] {
         self.deref()
     }
 }
 
-impl<P: FP, const DIM: usize> AsMut<[(P, P)]> for Rect<P, DIM> {
+impl<P: Scalar, const DIM: usize> AsMut<[(P, P)]> for Rect<P, DIM> {
     fn as_mut(&mut self) -> &mut [(P, P)] {
         self.deref_mut()
     }
 }
 
-// When trying to use Other(Box<Shape<P>>)
-// the trait bound `shapes::Shape<P>: std::marker::Sized` is not satisfied [E0277]
-// the trait bound `shapes::Shape<P>: std::clone::Clone` is not satisfied [E0277]
-// the trait bound `shapes::Shape<P> + 'static: std::fmt::Debug` is not satisfied [E0277]
-//
-/// A convenience enum that contains `Point`, `LineSegment`, and `Rect`
-#[derive(Debug, Clone)]
+fn check_radius<P: FP>(radius: P) -> Result<P, GeometryError> {
+    if check_finite(radius)? < P::zero() {
+        Err(GeometryError::NegativeRadius)
+    } else {
+        Ok(radius)
+    }
+}
+
+/// An n-dimensional sphere, boundary inclusive.
+/// `area_overlapped_with_mbr` is approximate when the sphere straddles the mbr's boundary
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "crate::serde_geometry::RawSphere<P, DIM>")
+)]
+pub struct Sphere<P: FP, const DIM: usize> {
+    pub center: Point<P, DIM>,
+    pub radius: P,
+}
+
+impl<P: FP, const DIM: usize> Sphere<P, DIM> {
+    /// New Sphere from its center and radius
+    pub fn new(center: [P; DIM], radius: P) -> Sphere<P, DIM> {
+        match Sphere::try_new(center, radius) {
+            Ok(sphere) => sphere,
+            Err(err) => panic!("{:?}, {:?} is not a valid sphere: {}", center, radius, err),
+        }
+    }
+
+    /// New Sphere from its center and radius, failing if a coordinate isn't finite or the radius is negative
+    pub fn try_new(center: [P; DIM], radius: P) -> Result<Sphere<P, DIM>, GeometryError> {
+        Ok(Sphere {
+            center: Point::try_new(center)?,
+            radius: check_radius(radius)?,
+        })
+    }
+}
+
+/// An n-dimensional capsule, the volume swept by a sphere moving along a line segment.
+/// `area_overlapped_with_mbr` is approximate when the capsule straddles the mbr's boundary
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "crate::serde_geometry::RawCapsule<P, DIM>")
+)]
+pub struct Capsule<P: FP, const DIM: usize> {
+    pub segment: LineSegment<P, DIM>,
+    pub radius: P,
+}
+
+impl<P: FP, const DIM: usize> Capsule<P, DIM> {
+    /// New Capsule from either end of its segment and its radius
+    pub fn new(x: [P; DIM], y: [P; DIM], radius: P) -> Capsule<P, DIM> {
+        match Capsule::try_new(x, y, radius) {
+            Ok(capsule) => capsule,
+            Err(err) => panic!(
+                "{:?}, {:?}, {:?} is not a valid capsule: {}",
+                x, y, radius, err
+            ),
+        }
+    }
+
+    /// New Capsule from either end of its segment and its radius,
+    /// failing if a coordinate isn't finite or the radius is negative
+    pub fn try_new(x: [P; DIM], y: [P; DIM], radius: P) -> Result<Capsule<P, DIM>, GeometryError> {
+        Ok(Capsule {
+            segment: LineSegment::try_new(x, y)?,
+            radius: check_radius(radius)?,
+        })
+    }
+}
+
+/// An n-dimensional triangle, including its interior
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Triangle<P: FP, const DIM: usize> {
+    pub a: Point<P, DIM>,
+    pub b: Point<P, DIM>,
+    pub c: Point<P, DIM>,
+}
+
+impl<P: FP, const DIM: usize> Triangle<P, DIM> {
+    /// New Triangle from its corners
+    pub fn new(a: [P; DIM], b: [P; DIM], c: [P; DIM]) -> Triangle<P, DIM> {
+        Triangle {
+            a: Point::new(a),
+            b: Point::new(b),
+            c: Point::new(c),
+        }
+    }
+
+    /// New Triangle from its corners, failing if any coordinate isn't finite
+    pub fn try_new(
+        a: [P; DIM],
+        b: [P; DIM],
+        c: [P; DIM],
+    ) -> Result<Triangle<P, DIM>, GeometryError> {
+        Ok(Triangle {
+            a: Point::try_new(a)?,
+            b: Point::try_new(b)?,
+            c: Point::try_new(c)?,
+        })
+    }
+
+    /// The triangle's edges, as segments
+    pub fn edges(&self) -> [LineSegment<P, DIM>; 3] {
+        [
+            LineSegment {
+                x: self.a.clone(),
+                y: self.b.clone(),
+            },
+            LineSegment {
+                x: self.b.clone(),
+                y: self.c.clone(),
+            },
+            LineSegment {
+                x: self.c.clone(),
+                y: self.a.clone(),
+            },
+        ]
+    }
+}
+
+/// An n-dimensional ray, starting at the origin and extending along the direction.
+/// Points on the ray are `origin + t * direction` for `t >= 0`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound(serialize = "P: Serialize", deserialize = "P: Deserialize<'de>"),
+        try_from = "crate::serde_geometry::RawRay<P, DIM>"
+    )
+)]
+pub struct Ray<P: FP, const DIM: usize> {
+    pub origin: Point<P, DIM>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    pub direction: [P; DIM],
+}
+
+impl<P: FP, const DIM: usize> Ray<P, DIM> {
+    /// New Ray from an origin and a direction. The direction doesn't need to be normalized
+    pub fn new(origin: [P; DIM], direction: [P; DIM]) -> Ray<P, DIM> {
+        match Ray::try_new(origin, direction) {
+            Ok(ray) => ray,
+            Err(err) => panic!("{:?}, {:?} is not a valid ray: {}", origin, direction, err),
+        }
+    }
+
+    /// New Ray from an origin and a direction, failing if a coordinate isn't finite or the direction has no length
+    pub fn try_new(origin: [P; DIM], direction: [P; DIM]) -> Result<Ray<P, DIM>, GeometryError> {
+        let origin = Point::try_new(origin)?;
+        let mut zero = true;
+        for &d in &direction {
+            zero &= check_finite(d)? == P::zero();
+        }
+        if zero {
+            return Err(GeometryError::ZeroDirection);
+        }
+        Ok(Ray { origin, direction })
+    }
+
+    /// The point at parameter t
+    pub fn point_at(&self, t: P) -> Point<P, DIM> {
+        let mut coords = self.origin.coords;
+        for (coord, &d) in coords.iter_mut().zip(self.direction.iter()) {
+            *coord += t * d;
+        }
+        Point { coords }
+    }
+}
+
+/// A 2-dimensional polygon with holes.
+/// Rings are closed implicitly, connecting the last vertex back to the first. Holes are expected to lie within the exterior
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "crate::serde_geometry::RawPolygon<P>")
+)]
+pub struct Polygon<P: FP> {
+    exterior: Vec<Point<P, 2>>,
+    holes: Vec<Vec<Point<P, 2>>>,
+    mbr: Rect<P, 2>,
+}
+
+impl<P: FP> Polygon<P> {
+    /// New Polygon from its exterior ring and holes. A repeated closing vertex is dropped
+    pub fn new(exterior: Vec<[P; 2]>, holes: Vec<Vec<[P; 2]>>) -> Polygon<P> {
+        match Polygon::try_new(exterior, holes) {
+            Ok(polygon) => polygon,
+            Err(err) => panic!("not a valid polygon: {}", err),
+        }
+    }
+
+    /// New Polygon from its exterior ring and holes, failing if a coordinate isn't finite or a ring has too few vertices
+    pub fn try_new(
+        exterior: Vec<[P; 2]>,
+        holes: Vec<Vec<[P; 2]>>,
+    ) -> Result<Polygon<P>, GeometryError> {
+        use crate::tree::mbr::MbrLeafGeometry;
+        let exterior = ring(exterior)?;
+        let holes = holes.into_iter().map(ring).collect::<Result<Vec<_>, _>>()?;
+        let mut mbr = Rect::max_inverted();
+        for vertex in &exterior {
+            vertex.expand_mbr_to_fit(&mut mbr);
+        }
+        Ok(Polygon {
+            exterior,
+            holes,
+            mbr,
+        })
+    }
+
+    /// The exterior ring
+    pub fn exterior(&self) -> &[Point<P, 2>] {
+        &self.exterior
+    }
+
+    /// The holes' rings
+    pub fn holes(&self) -> &[Vec<Point<P, 2>>] {
+        &self.holes
+    }
+
+    /// The polygon's minimum bounding rectangle
+    pub fn mbr(&self) -> &Rect<P, 2> {
+        &self.mbr
+    }
+
+    /// Every ring, starting with the exterior
+    pub fn rings(&self) -> impl Iterator<Item = &[Point<P, 2>]> {
+        Some(self.exterior.as_slice())
+            .into_iter()
+            .chain(self.holes.iter().map(|hole| hole.as_slice()))
+    }
+
+    /// Every edge of every ring
+    pub fn edges(&self) -> impl Iterator<Item = LineSegment<P, 2>> + '_ {
+        self.rings().flat_map(|ring| {
+            ring.iter()
+                .zip(ring.iter().cycle().skip(1))
+                .map(|(x, y)| LineSegment {
+                    x: x.clone(),
+                    y: y.clone(),
+                })
+        })
+    }
+
+    /// Determine if the point is inside the polygon, boundary inclusive
+    pub fn contains_point(&self, point: &Point<P, 2>) -> bool {
+        if !contains(&self.mbr, point.coords) {
+            return false;
+        }
+        if self.edges().any(|edge| on_segment(&edge, point.coords)) {
+            return true;
+        }
+        crosses_odd(&self.exterior, point.coords)
+            && !self
+                .holes
+                .iter()
+                .any(|hole| crosses_odd(hole, point.coords))
+    }
+
+    /// Determine if the rect and the polygon share any point, boundary inclusive
+    pub fn touches_mbr(&self, mbr: &Rect<P, 2>) -> bool {
+        use crate::tree::mbr::MbrLeafGeometry;
+        self.edges().any(|edge| edge.overlapped_by_mbr(mbr))
+            || self.contains_point(&Point {
+                coords: [mbr.edges[0].0, mbr.edges[1].0],
+            })
+    }
+
+    /// Determine if the rect shares any point with the polygon's interior, so touching its boundary isn't enough.
+    /// Either an edge passes through the rect's interior, or the rect's center is inside the polygon
+    pub fn interior_overlaps_mbr(&self, mbr: &Rect<P, 2>) -> bool {
+        let [(x1, x2), (y1, y2)] = mbr.edges;
+        if x1 == x2 || y1 == y2 {
+            // without an interior, the rect is a segment that has to pass through the polygon's interior
+            return self.interior_overlaps_between([x1, y1], [x2, y2]);
+        }
+        let two = P::one() + P::one();
+        self.edges().any(|edge| crosses_interior(&edge, mbr))
+            || self.contains_point(&Point {
+                coords: [(x1 + x2) / two, (y1 + y2) / two],
+            })
+    }
+
+    /// Determine if the rect is inside the polygon, boundary inclusive.
+    /// Its corners must be inside and no edge may pass through its interior
+    pub fn contains_mbr(&self, mbr: &Rect<P, 2>) -> bool {
+        let [(x1, x2), (y1, y2)] = mbr.edges;
+        if x1 == x2 || y1 == y2 {
+            // without an interior, edges can only cross it at single points
+            return self.contains_between([x1, y1], [x2, y2]);
+        }
+        [[x1, y1], [x1, y2], [x2, y1], [x2, y2]]
+            .iter()
+            .all(|&corner| self.contains_point(&Point { coords: corner }))
+            && !self.edges().any(|edge| crosses_interior(&edge, mbr))
+    }
+
+    /// Determine if the segment and the polygon share any point, boundary inclusive.
+    /// Either its start is inside, or it meets an edge
+    pub fn touches_segment(&self, segment: &LineSegment<P, 2>) -> bool {
+        self.contains_point(&segment.x) || self.edges().any(|edge| segments_touch(&edge, segment))
+    }
+
+    /// Determine if any part of the segment is inside the polygon's interior, so touching its boundary isn't enough
+    pub fn interior_overlaps_segment(&self, segment: &LineSegment<P, 2>) -> bool {
+        self.interior_overlaps_between(segment.x.coords, segment.y.coords)
+    }
+
+    /// Determine if the segment is inside the polygon, boundary inclusive
+    pub fn contains_segment(&self, segment: &LineSegment<P, 2>) -> bool {
+        self.contains_between(segment.x.coords, segment.y.coords)
+    }
+
+    /// Determine if the polygons' interiors share any point, so touching boundaries aren't enough.
+    /// Either an edge of one passes through the other's interior, or neither boundary enters the other and
+    /// they share the same interior
+    pub fn interior_overlaps_polygon(&self, other: &Polygon<P>) -> bool {
+        use crate::tree::mbr::MbrLeafGeometry;
+        if !self.mbr.overlapped_by_mbr(&other.mbr) {
+            return false;
+        }
+        other
+            .edges()
+            .any(|edge| self.interior_overlaps_segment(&edge))
+            || self
+                .edges()
+                .any(|edge| other.interior_overlaps_segment(&edge))
+            || interior_point(&other.rings().collect::<Vec<_>>())
+                .map_or(false, |point| self.interior_contains_point(&point))
+    }
+
+    /// Determine if the other polygon is inside this one, boundary inclusive.
+    /// Its edges must be inside, which leaves each of this polygon's holes either inside or outside it,
+    /// and none of the holes may be inside it
+    pub fn contains_polygon(&self, other: &Polygon<P>) -> bool {
+        other.edges().all(|edge| self.contains_segment(&edge))
+            && self.holes.iter().all(|hole| {
+                interior_point(&[hole.as_slice()])
+                    .map_or(true, |point| !other.contains_point(&point))
+            })
+    }
+
+    /// Determine if the segment from a to b is inside the polygon, boundary inclusive
+    fn contains_between(&self, a: [P; 2], b: [P; 2]) -> bool {
+        let splits = self.split_between(a, b);
+        splits.iter().all(|split| self.contains_point(split))
+            && splits
+                .iter()
+                .zip(splits.iter().skip(1))
+                .all(|(x, y)| self.contains_point(&midpoint(x, y)))
+    }
+
+    /// Determine if any part of the segment from a to b is inside the polygon's interior.
+    /// Pieces between splits are either inside or not, so only their middles are tested
+    fn interior_overlaps_between(&self, a: [P; 2], b: [P; 2]) -> bool {
+        let splits = self.split_between(a, b);
+        splits
+            .iter()
+            .zip(splits.iter().skip(1))
+            .any(|(x, y)| self.interior_contains_point(&midpoint(x, y)))
+    }
+
+    /// Determine if the point is inside the polygon, but not on its boundary
+    fn interior_contains_point(&self, point: &Point<P, 2>) -> bool {
+        self.contains_point(point) && !self.edges().any(|edge| on_segment(&edge, point.coords))
+    }
+
+    /// The segment from a to b, split wherever it meets an edge, which leaves every piece either inside,
+    /// outside or on the boundary of the polygon. Returns the points from a to b, including both
+    fn split_between(&self, a: [P; 2], b: [P; 2]) -> Vec<Point<P, 2>> {
+        let zero = P::zero();
+        let one = P::one();
+        let ab = [b[0] - a[0], b[1] - a[1]];
+        let len_sq = ab[0] * ab[0] + ab[1] * ab[1];
+        if len_sq == zero {
+            return vec![Point { coords: a }, Point { coords: b }];
+        }
+        let mut splits = vec![zero, one];
+        for edge in self.edges() {
+            let (c, d) = (edge.x.coords, edge.y.coords);
+            let cd = [d[0] - c[0], d[1] - c[1]];
+            let ac = [c[0] - a[0], c[1] - a[1]];
+            let denom = ab[0] * cd[1] - ab[1] * cd[0];
+            if denom == zero {
+                // parallel, so split where the edge's ends project onto the segment
+                for end in [c, d] {
+                    splits.push(((end[0] - a[0]) * ab[0] + (end[1] - a[1]) * ab[1]) / len_sq);
+                }
+                continue;
+            }
+            let along_edge = (ac[0] * ab[1] - ac[1] * ab[0]) / denom;
+            if zero <= along_edge && along_edge <= one {
+                splits.push((ac[0] * cd[1] - ac[1] * cd[0]) / denom);
+            }
+        }
+        splits.retain(|&t| zero <= t && t <= one);
+        splits.sort_by_key(|&t| OrderedFloat(t));
+        splits
+            .into_iter()
+            .map(|t| Point {
+                coords: [a[0] + t * ab[0], a[1] + t * ab[1]],
+            })
+            .collect()
+    }
+}
+
+/// A collection of 2-dimensional polygons, expected not to overlap
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "crate::serde_geometry::RawMultiPolygon<P>")
+)]
+pub struct MultiPolygon<P: FP> {
+    polygons: Vec<Polygon<P>>,
+    mbr: Rect<P, 2>,
+}
+
+impl<P: FP> MultiPolygon<P> {
+    /// New MultiPolygon from its polygons
+    pub fn new(polygons: Vec<Polygon<P>>) -> MultiPolygon<P> {
+        match MultiPolygon::try_new(polygons) {
+            Ok(multi) => multi,
+            Err(err) => panic!("not a valid multipolygon: {}", err),
+        }
+    }
+
+    /// New MultiPolygon from its polygons, failing if there are none
+    pub fn try_new(polygons: Vec<Polygon<P>>) -> Result<MultiPolygon<P>, GeometryError> {
+        use crate::tree::mbr::MbrLeafGeometry;
+        if polygons.is_empty() {
+            return Err(GeometryError::Empty);
+        }
+        let mut mbr = Rect::max_inverted();
+        for polygon in &polygons {
+            polygon.mbr.expand_mbr_to_fit(&mut mbr);
+        }
+        Ok(MultiPolygon { polygons, mbr })
+    }
+
+    /// The polygons
+    pub fn polygons(&self) -> &[Polygon<P>] {
+        &self.polygons
+    }
+
+    /// The minimum bounding rectangle of every polygon
+    pub fn mbr(&self) -> &Rect<P, 2> {
+        &self.mbr
+    }
+
+    /// Determine if the point is inside any of the polygons, boundary inclusive
+    pub fn contains_point(&self, point: &Point<P, 2>) -> bool {
+        self.polygons
+            .iter()
+            .any(|polygon| polygon.contains_point(point))
+    }
+}
+
+/// Check a ring's coordinates, dropping a repeated closing vertex
+fn ring<P: FP>(mut coords: Vec<[P; 2]>) -> Result<Vec<Point<P, 2>>, GeometryError> {
+    if coords.len() > 1 && coords.first() == coords.last() {
+        coords.pop();
+    }
+    if coords.len() < 3 {
+        return Err(GeometryError::TooFewVertices);
+    }
+    coords.into_iter().map(Point::try_new).collect()
+}
+
+fn contains<P: FP>(mbr: &Rect<P, 2>, coords: [P; 2]) -> bool {
+    izip!(mbr.edges.iter(), coords.iter()).all(|(&(lo, hi), &z)| lo <= z && z <= hi)
+}
+
+/// Twice the signed area of the triangle a, b, c. Positive when counter-clockwise
+fn cross<P: FP>(a: [P; 2], b: [P; 2], c: [P; 2]) -> P {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn on_segment<P: FP>(edge: &LineSegment<P, 2>, coords: [P; 2]) -> bool {
+    cross(edge.x.coords, edge.y.coords, coords) == P::zero()
+        && izip!(edge.x.coords.iter(), edge.y.coords.iter(), coords.iter())
+            .all(|(&x, &y, &z)| Float::min(x, y) <= z && z <= Float::max(x, y))
+}
+
+/// Even-odd crossing test of a horizontal ray cast from the point
+fn crosses_odd<P: FP>(ring: &[Point<P, 2>], coords: [P; 2]) -> bool {
+    let [x, y] = coords;
+    let mut inside = false;
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        let ([ax, ay], [bx, by]) = (a.coords, b.coords);
+        if (ay > y) != (by > y) && x < ax + (y - ay) * (bx - ax) / (by - ay) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Whether two segments share any point, ends included
+fn segments_touch<P: FP>(a: &LineSegment<P, 2>, b: &LineSegment<P, 2>) -> bool {
+    let zero = P::zero();
+    let straddles = |x: P, y: P| (x > zero && y < zero) || (x < zero && y > zero);
+    let (ax, ay, bx, by) = (a.x.coords, a.y.coords, b.x.coords, b.y.coords);
+    if straddles(cross(ax, ay, bx), cross(ax, ay, by))
+        && straddles(cross(bx, by, ax), cross(bx, by, ay))
+    {
+        return true;
+    }
+    on_segment(a, bx) || on_segment(a, by) || on_segment(b, ax) || on_segment(b, ay)
+}
+
+fn midpoint<P: FP>(a: &Point<P, 2>, b: &Point<P, 2>) -> Point<P, 2> {
+    let two = P::one() + P::one();
+    Point {
+        coords: [
+            (a.coords[0] + b.coords[0]) / two,
+            (a.coords[1] + b.coords[1]) / two,
+        ],
+    }
+}
+
+/// A point inside the region bounded by the rings, by the even-odd rule.
+/// A horizontal line just above the lowest vertices meets no vertex, and between its first two crossings is inside
+fn interior_point<P: FP>(rings: &[&[Point<P, 2>]]) -> Option<Point<P, 2>> {
+    let two = P::one() + P::one();
+    let mut ys: Vec<P> = rings
+        .iter()
+        .flat_map(|ring| ring.iter().map(|vertex| vertex.coords[1]))
+        .collect();
+    ys.sort_by_key(|&y| OrderedFloat(y));
+    ys.dedup();
+    if ys.len() < 2 {
+        return None;
+    }
+    let y = (ys[0] + ys[1]) / two;
+    let mut crossings: Vec<P> = rings
+        .iter()
+        .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+        .filter_map(|(a, b)| {
+            let ([ax, ay], [bx, by]) = (a.coords, b.coords);
+            if (ay > y) != (by > y) {
+                Some(ax + (y - ay) * (bx - ax) / (by - ay))
+            } else {
+                None
+            }
+        })
+        .collect();
+    crossings.sort_by_key(|&x| OrderedFloat(x));
+    match crossings[..] {
+        [x1, x2, ..] => Some(Point {
+            coords: [(x1 + x2) / two, y],
+        }),
+        _ => None,
+    }
+}
+
+/// Whether any part of the edge is strictly inside the rect.
+/// The edge is clipped to the rect, and a clipped segment with any interior point has an interior midpoint
+fn crosses_interior<P: FP>(edge: &LineSegment<P, 2>, mbr: &Rect<P, 2>) -> bool {
+    let two: P = P::one() + P::one();
+    let mut t0 = P::zero();
+    let mut t1 = P::one();
+    for (&(lo, hi), &x, &y) in izip!(mbr.edges.iter(), edge.x.coords.iter(), edge.y.coords.iter()) {
+        let d = y - x;
+        if d == P::zero() {
+            if x <= lo || hi <= x {
+                return false;
+            }
+            continue;
+        }
+        let (a, b) = ((lo - x) / d, (hi - x) / d);
+        t0 = Float::max(t0, Float::min(a, b));
+        t1 = Float::min(t1, Float::max(a, b));
+        if t1 <= t0 {
+            return false;
+        }
+    }
+    let t = (t0 + t1) / two;
+    izip!(mbr.edges.iter(), edge.x.coords.iter(), edge.y.coords.iter()).all(
+        |(&(lo, hi), &x, &y)| {
+            let z = x + t * (y - x);
+            lo < z && z < hi
+        },
+    )
+}
+
+/// A convenience enum that contains `Point`, `LineSegment`, `Rect` and `LineString`,
+/// plus any application geometry boxed as a `Custom` shape
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Shapes<P: FP, const DIM: usize> {
     Point(Point<P, DIM>),
     LineSegment(LineSegment<P, DIM>),
-    Rect(Rect<P, DIM>), // Other(Box<Shape<P>>)
+    Rect(Rect<P, DIM>),
+    LineString(LineString<P, DIM>),
+    /// Custom shapes aren't serializable. Serializing one is an error
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Box<dyn DynLeafGeometry<P, DIM>>),
 }
@@ -12,13 +12,98 @@ extern crate itertools;
 
 extern crate num;
 extern crate ordered_float;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 #[cfg(test)]
 #[macro_use]
 extern crate approx;
 
+/// A coordinate type. Implemented for `f32`, `f64` and every primitive integer, so grid and voxel indexes
+/// can store integer coordinates directly.
+///
+/// Areas, margins and distances are computed in `Measure`, a float type. Integer coordinates are converted before
+/// any arithmetic, so area and margin can't overflow. Integer coordinates beyond 2^53 lose precision
+/// in measures, but never in the tree's mbrs.
+///
+/// Integer trees index points and rects, and support the rect, sphere and point queries, nearest neighbours,
+/// ray casting and the frozen format. Geometry that needs division or square roots (line segments, line strings,
+/// polygons, spheres, capsules and triangles), `Shapes`, polygon queries and the joins still require an `FP`
+/// coordinate. Fixed-point coordinates aren't supported
+pub trait Scalar:
+    Num + Bounded + PartialOrd + ToPrimitive + FromPrimitive + Copy + Debug + Default
+{
+    /// The float type measures are computed in
+    type Measure: FP;
+
+    /// Convert into the measure type
+    fn to_measure(self) -> Self::Measure;
+
+    /// Determine if the coordinate can be indexed. Integers always can
+    fn is_finite(self) -> bool;
+
+    /// The lesser of the two, which only needs `PartialOrd` since coordinates are never NaN
+    fn partial_min(self, other: Self) -> Self {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// The greater of the two, which only needs `PartialOrd` since coordinates are never NaN
+    fn partial_max(self, other: Self) -> Self {
+        if self < other {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+macro_rules! float_scalar {
+    ($($t:ty)*) => {
+        $(
+            impl Scalar for $t {
+                type Measure = $t;
+
+                fn to_measure(self) -> $t {
+                    self
+                }
+
+                fn is_finite(self) -> bool {
+                    Float::is_finite(self)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! integer_scalar {
+    ($($t:ty)*) => {
+        $(
+            impl Scalar for $t {
+                type Measure = f64;
+
+                fn to_measure(self) -> f64 {
+                    self as f64
+                }
+
+                fn is_finite(self) -> bool {
+                    true
+                }
+            }
+        )*
+    };
+}
+
+float_scalar!(f32 f64);
+integer_scalar!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+/// A float coordinate type. Geometry that needs division or square roots, like line segments and polygons, requires it
 pub trait FP:
-    Float
+    Scalar<Measure = Self>
+    + Float
     + Signed
     + Bounded
     + MulAssign
@@ -29,7 +114,6 @@ pub trait FP:
     + Debug
     + Default
     + FloatCore
-    + TryInto<NotNan<Self>>
 {
 }
 
@@ -37,12 +121,18 @@ impl FP for f32 {}
 impl FP for f64 {}
 
 pub mod geometry;
+#[cfg(feature = "serde")]
+mod serde_array;
+#[cfg(feature = "serde")]
+mod serde_geometry;
+#[cfg(feature = "serde")]
+mod serde_tree;
 pub mod tree;
 mod vecext;
 
-use num::{Bounded, Float, FromPrimitive, Signed, ToPrimitive};
-use ordered_float::{FloatCore, NotNan};
-use std::convert::TryInto;
+use num::{Bounded, Float, FromPrimitive, Num, Signed, ToPrimitive};
+use ordered_float::FloatCore;
 use std::fmt::Debug;
 use std::ops::{AddAssign, MulAssign};
 pub use tree::mbr::{RLinearTree, RQuadraticTree, RStar, RStarTree, RTree};
+pub use tree::xmbr::{XTree, XTreeMap};
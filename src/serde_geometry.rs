@@ -0,0 +1,171 @@
+// Copyright 2016 spatial-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Deserialized forms of the geometries, validated by the fallible constructors on the way in.
+//! Each has the same fields as the geometry it's converted to, so the serialized format is unchanged.
+//! Cached mbrs are read to keep the format, then recomputed from the vertices.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+use crate::geometry::{
+    Capsule, GeometryError, LineSegment, LineString, MultiPolygon, Point, Polygon, Ray, Rect,
+    Sphere,
+};
+use crate::{Scalar, FP};
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "P: Deserialize<'de>"))]
+pub struct RawPoint<P, const DIM: usize> {
+    #[serde(deserialize_with = "crate::serde_array::deserialize")]
+    coords: [P; DIM],
+}
+
+impl<P: Scalar, const DIM: usize> TryFrom<RawPoint<P, DIM>> for Point<P, DIM> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawPoint<P, DIM>) -> Result<Point<P, DIM>, GeometryError> {
+        Point::try_new(raw.coords)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "P: Deserialize<'de>"))]
+pub struct RawRect<P, const DIM: usize> {
+    #[serde(deserialize_with = "crate::serde_array::deserialize")]
+    edges: [(P, P); DIM],
+}
+
+impl<P: Scalar, const DIM: usize> TryFrom<RawRect<P, DIM>> for Rect<P, DIM> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawRect<P, DIM>) -> Result<Rect<P, DIM>, GeometryError> {
+        Rect::try_new(raw.edges)
+    }
+}
+
+/// Deserialize a level's mbr. Its edges keep their order rather than going through `Rect::try_new`,
+/// since empty levels are bounded by an inverted rect
+pub fn deserialize_mbr<'de, D, P, const DIM: usize>(
+    deserializer: D,
+) -> Result<Rect<P, DIM>, D::Error>
+where
+    D: Deserializer<'de>,
+    P: Scalar + Deserialize<'de>,
+{
+    let raw = RawRect::<P, DIM>::deserialize(deserializer)?;
+    if raw
+        .edges
+        .iter()
+        .any(|&(x, y)| !x.is_finite() || !y.is_finite())
+    {
+        return Err(D::Error::custom(GeometryError::NonFiniteCoordinate));
+    }
+    Ok(Rect { edges: raw.edges })
+}
+
+#[derive(Deserialize)]
+pub struct RawLineString<P: FP, const DIM: usize> {
+    vertices: Vec<Point<P, DIM>>,
+    #[allow(dead_code)]
+    mbr: Rect<P, DIM>,
+}
+
+impl<P: FP, const DIM: usize> TryFrom<RawLineString<P, DIM>> for LineString<P, DIM> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawLineString<P, DIM>) -> Result<LineString<P, DIM>, GeometryError> {
+        LineString::try_new(
+            raw.vertices
+                .into_iter()
+                .map(|vertex| vertex.coords)
+                .collect(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawSphere<P: FP, const DIM: usize> {
+    center: Point<P, DIM>,
+    radius: P,
+}
+
+impl<P: FP, const DIM: usize> TryFrom<RawSphere<P, DIM>> for Sphere<P, DIM> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawSphere<P, DIM>) -> Result<Sphere<P, DIM>, GeometryError> {
+        Sphere::try_new(raw.center.coords, raw.radius)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawCapsule<P: FP, const DIM: usize> {
+    segment: LineSegment<P, DIM>,
+    radius: P,
+}
+
+impl<P: FP, const DIM: usize> TryFrom<RawCapsule<P, DIM>> for Capsule<P, DIM> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawCapsule<P, DIM>) -> Result<Capsule<P, DIM>, GeometryError> {
+        Capsule::try_new(raw.segment.x.coords, raw.segment.y.coords, raw.radius)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "P: Deserialize<'de>"))]
+pub struct RawRay<P: FP, const DIM: usize> {
+    origin: Point<P, DIM>,
+    #[serde(deserialize_with = "crate::serde_array::deserialize")]
+    direction: [P; DIM],
+}
+
+impl<P: FP, const DIM: usize> TryFrom<RawRay<P, DIM>> for Ray<P, DIM> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawRay<P, DIM>) -> Result<Ray<P, DIM>, GeometryError> {
+        Ray::try_new(raw.origin.coords, raw.direction)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawPolygon<P: FP> {
+    exterior: Vec<Point<P, 2>>,
+    holes: Vec<Vec<Point<P, 2>>>,
+    #[allow(dead_code)]
+    mbr: Rect<P, 2>,
+}
+
+fn coords<P: FP>(ring: Vec<Point<P, 2>>) -> Vec<[P; 2]> {
+    ring.into_iter().map(|vertex| vertex.coords).collect()
+}
+
+impl<P: FP> TryFrom<RawPolygon<P>> for Polygon<P> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawPolygon<P>) -> Result<Polygon<P>, GeometryError> {
+        Polygon::try_new(
+            coords(raw.exterior),
+            raw.holes.into_iter().map(coords).collect(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawMultiPolygon<P: FP> {
+    polygons: Vec<Polygon<P>>,
+    #[allow(dead_code)]
+    mbr: Rect<P, 2>,
+}
+
+impl<P: FP> TryFrom<RawMultiPolygon<P>> for MultiPolygon<P> {
+    type Error = GeometryError;
+
+    fn try_from(raw: RawMultiPolygon<P>) -> Result<MultiPolygon<P>, GeometryError> {
+        MultiPolygon::try_new(raw.polygons)
+    }
+}
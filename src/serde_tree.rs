@@ -0,0 +1,221 @@
+// Copyright 2016 spatial-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Deserialized forms of the maps and their indexes, validated on the way in.
+//! Index configs are checked against what their constructors assert, and a map's length is recounted from its leaves.
+
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::tree::mbr::index::r::{RInsert, RRemove};
+use crate::tree::mbr::index::rstar::RStarInsert;
+use crate::tree::mbr::{MbrMap, MbrNodeChildren, NodeChildren};
+use crate::tree::xmbr::index::x::XInsert;
+use crate::Scalar;
+
+/// Reasons a deserialized map is rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeError {
+    /// An index config its constructor would never have built
+    InvalidConfig(&'static str),
+    /// Leaf levels at different depths
+    UnevenDepth,
+    /// A level with no children, other than an empty leaf level root
+    EmptyLevel,
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TreeError::InvalidConfig(reason) => write!(f, "invalid index config: {}", reason),
+            TreeError::UnevenDepth => write!(f, "leaf levels must all be at the same depth"),
+            TreeError::EmptyLevel => write!(f, "only a leaf level root may be empty"),
+        }
+    }
+}
+
+impl Error for TreeError {}
+
+fn check_config(valid: bool, reason: &'static str) -> Result<(), TreeError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(TreeError::InvalidConfig(reason))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawMbrMap<NODE, I, R> {
+    insert_index: I,
+    remove_index: R,
+    root: NODE,
+    #[allow(dead_code)]
+    len: usize,
+}
+
+/// Count the leaves under the level, checking every leaf level is at the same depth
+fn count_leaves<NODE: MbrNodeChildren>(
+    level: &NODE,
+    depth: usize,
+    leaf_depth: &mut Option<usize>,
+) -> Result<usize, TreeError> {
+    match level.children() {
+        NodeChildren::Leaves(children) => {
+            if children.is_empty() && depth > 0 {
+                return Err(TreeError::EmptyLevel);
+            }
+            if *leaf_depth.get_or_insert(depth) != depth {
+                return Err(TreeError::UnevenDepth);
+            }
+            Ok(children.len())
+        }
+        NodeChildren::Levels(children) => {
+            if children.is_empty() {
+                return Err(TreeError::EmptyLevel);
+            }
+            children.iter().try_fold(0, |len, child| {
+                Ok(len + count_leaves(child, depth + 1, leaf_depth)?)
+            })
+        }
+    }
+}
+
+impl<NODE: MbrNodeChildren, I, R> TryFrom<RawMbrMap<NODE, I, R>> for MbrMap<NODE, I, R> {
+    type Error = TreeError;
+
+    fn try_from(raw: RawMbrMap<NODE, I, R>) -> Result<MbrMap<NODE, I, R>, TreeError> {
+        let len = count_leaves(&raw.root, 0, &mut None)?;
+        Ok(MbrMap {
+            insert_index: raw.insert_index,
+            remove_index: raw.remove_index,
+            root: raw.root,
+            len,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawRInsert<NS> {
+    preferred_min: usize,
+    max: usize,
+    splitter: NS,
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NS> TryFrom<RawRInsert<NS>>
+    for RInsert<P, DIM, LG, T, NS>
+{
+    type Error = TreeError;
+
+    fn try_from(raw: RawRInsert<NS>) -> Result<RInsert<P, DIM, LG, T, NS>, TreeError> {
+        check_config(raw.max > 0, "max must be greater than 0")?;
+        check_config(
+            2 * raw.preferred_min <= raw.max,
+            "max must be at least twice the min",
+        )?;
+        Ok(RInsert {
+            preferred_min: raw.preferred_min,
+            max: raw.max,
+            splitter: raw.splitter,
+            _p: PhantomData,
+            _lg: PhantomData,
+            _t: PhantomData,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawRStarInsert {
+    max: usize,
+    preferred_min: usize,
+    reinsert_m: usize,
+    choose_subtree_p: usize,
+    min_k: usize,
+    max_k: usize,
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> TryFrom<RawRStarInsert> for RStarInsert<P, DIM, LG, T> {
+    type Error = TreeError;
+
+    fn try_from(raw: RawRStarInsert) -> Result<RStarInsert<P, DIM, LG, T>, TreeError> {
+        check_config(
+            raw.preferred_min > 0 && raw.preferred_min <= raw.max,
+            "min must be greater than 0 and at most max",
+        )?;
+        check_config(raw.min_k > 0, "min_k must be greater than 0")?;
+        check_config(raw.max_k > raw.min_k, "max_k must be greater than min_k")?;
+        check_config(raw.max > raw.max_k, "max must be greater than max_k")?;
+        check_config(raw.reinsert_m < raw.max, "reinsert_m must be less than max")?;
+        check_config(
+            raw.choose_subtree_p > 0,
+            "choose_subtree_p must be greater than 0",
+        )?;
+        Ok(RStarInsert {
+            max: raw.max,
+            preferred_min: raw.preferred_min,
+            reinsert_m: raw.reinsert_m,
+            choose_subtree_p: raw.choose_subtree_p,
+            min_k: raw.min_k,
+            max_k: raw.max_k,
+            _p: PhantomData,
+            _lg: PhantomData,
+            _t: PhantomData,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawXInsert {
+    max: usize,
+    preferred_min: usize,
+    min_fanout: usize,
+    max_overlap: f32,
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> TryFrom<RawXInsert> for XInsert<P, DIM, LG, T> {
+    type Error = TreeError;
+
+    fn try_from(raw: RawXInsert) -> Result<XInsert<P, DIM, LG, T>, TreeError> {
+        check_config(raw.preferred_min > 0, "min must be greater than 0")?;
+        check_config(
+            raw.max >= 2 * raw.preferred_min,
+            "max must be at least twice the min",
+        )?;
+        check_config(raw.min_fanout > 0, "min_fanout must be greater than 0")?;
+        check_config(raw.max_overlap.is_finite(), "max_overlap must be finite")?;
+        Ok(XInsert {
+            max: raw.max,
+            preferred_min: raw.preferred_min,
+            min_fanout: raw.min_fanout,
+            max_overlap: raw.max_overlap,
+            _p: PhantomData,
+            _lg: PhantomData,
+            _t: PhantomData,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawRRemove {
+    min: usize,
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> TryFrom<RawRRemove> for RRemove<P, DIM, LG, T> {
+    type Error = TreeError;
+
+    fn try_from(raw: RawRRemove) -> Result<RRemove<P, DIM, LG, T>, TreeError> {
+        check_config(raw.min > 0, "min must be greater than 0")?;
+        Ok(RRemove {
+            min: raw.min,
+            _p: PhantomData,
+            _lg: PhantomData,
+            _t: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright 2016 spatial-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Bulk loading of leaves into a packed tree
+//!
+//! Algorithm described by Leutenegger, S.; Lopez, M.; Edgington, J. (1997). "STR: A Simple and Efficient Algorithm for R-Tree Packing"
+
+use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode, MbrNodeChildren, NodeChildren};
+use crate::Scalar;
+use ordered_float::OrderedFloat;
+use std::cmp;
+
+/// Pack the leaves into a new root with Sort-Tile-Recursive.
+///
+/// Every level is filled evenly, so no node below the root has more than max or fewer than min children.
+/// No packing can keep every node at min when min is above (max + 1) / 2 (max + 1 leaves can't fill two nodes),
+/// so those nodes are instead held to (max + 1) / 2.
+pub fn str_pack<NODE, P: Scalar, const DIM: usize, LG, T>(
+    leaves: Vec<MbrLeaf<P, DIM, LG, T>>,
+    min: usize,
+    max: usize,
+) -> NODE
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    assert!(max > 1, "max({:?}) must be greater than 1.", max);
+    let min = cmp::min(min, (max + 1) / 2);
+    if leaves.is_empty() {
+        return NODE::new_leaves();
+    }
+    let mut nodes: Vec<NODE> = tile(leaves, min, max)
+        .into_iter()
+        .map(|(children, cut_axes)| with_splits(NODE::from_leaves(children), cut_axes))
+        .collect();
+    while nodes.len() > 1 {
+        nodes = tile(nodes, min, max)
+            .into_iter()
+            .map(|(children, cut_axes)| with_splits(NODE::from_levels(children), cut_axes))
+            .collect();
+    }
+    nodes.pop().unwrap()
+}
+
+/// Record every axis below cut_axes as one the node was split from its siblings along
+fn with_splits<NODE: MbrNodeChildren>(mut node: NODE, cut_axes: usize) -> NODE {
+    for axis in 0..cut_axes {
+        node.record_split(axis);
+    }
+    node
+}
+
+/// Move every leaf under the node into leaves
+pub fn collect_leaves<NODE, P: Scalar, const DIM: usize, LG, T>(
+    node: NODE,
+    leaves: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
+) where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    match node.into_children() {
+        NodeChildren::Leaves(mut children) => leaves.append(&mut children),
+        NodeChildren::Levels(children) => {
+            for child in children {
+                collect_leaves(child, leaves);
+            }
+        }
+    }
+}
+
+/// Group the children into the fewest possible nodes.
+/// Each group is returned with the number of leading axes it was cut from the other groups along
+fn tile<P: Scalar, const DIM: usize, V>(
+    children: Vec<V>,
+    min: usize,
+    max: usize,
+) -> Vec<(Vec<V>, usize)>
+where
+    V: MbrLeafGeometry<P, DIM>,
+{
+    let node_count = (children.len() + max - 1) / max;
+    let sizes = node_sizes(children.len(), node_count, min, max);
+    let mut nodes = Vec::with_capacity(node_count);
+    slice_into(children, &sizes, 0, &mut nodes);
+    nodes
+}
+
+/// Sort the children along the axis and cut them into vertical slabs, recursing into the next axis for each slab.
+/// Along the last axis, the slabs become nodes with the given sizes
+fn slice_into<P: Scalar, const DIM: usize, V>(
+    mut children: Vec<V>,
+    sizes: &[usize],
+    axis: usize,
+    nodes: &mut Vec<(Vec<V>, usize)>,
+) where
+    V: MbrLeafGeometry<P, DIM>,
+{
+    children.sort_by_key(|child| {
+        OrderedFloat(child.min_for_axis(axis).to_measure() + child.max_for_axis(axis).to_measure())
+    });
+    let mut iter = children.into_iter();
+    if axis + 1 >= DIM || sizes.len() == 1 {
+        // a lone node isn't cut along this axis, only along the ones before it
+        let cut_axes = if sizes.len() > 1 { axis + 1 } else { axis };
+        for &size in sizes {
+            nodes.push((iter.by_ref().take(size).collect(), cut_axes));
+        }
+        return;
+    }
+    let slab_count = slab_count(sizes.len(), (DIM - axis) as u32);
+    let mut remaining_sizes = sizes;
+    for nodes_in_slab in even_split(sizes.len(), slab_count) {
+        let (slab_sizes, rest) = remaining_sizes.split_at(nodes_in_slab);
+        remaining_sizes = rest;
+        let slab = iter.by_ref().take(slab_sizes.iter().sum()).collect();
+        slice_into(slab, slab_sizes, axis + 1, nodes);
+    }
+}
+
+/// The smallest number of slabs s where s^dims nodes can hold node_count
+fn slab_count(node_count: usize, dims: u32) -> usize {
+    let mut slabs = 1usize;
+    while slabs
+        .checked_pow(dims)
+        .map_or(false, |capacity| capacity < node_count)
+    {
+        slabs += 1;
+    }
+    slabs
+}
+
+/// Split len children evenly into node_count nodes.
+/// With node_count the fewest nodes that fit, the smallest node holds more than (node_count - 1) * max / node_count children,
+/// which is at least (max + 1) / 2 whenever there's more than one node, so the sizes always fall between min and max
+fn node_sizes(len: usize, node_count: usize, min: usize, max: usize) -> Vec<usize> {
+    let sizes = even_split(len, node_count);
+    assert!(
+        node_count == 1 || sizes.iter().all(|&size| min <= size && size <= max),
+        "node sizes {:?} must be between min({:?}) and max({:?})",
+        sizes,
+        min,
+        max
+    );
+    sizes
+}
+
+/// Split len into count parts whose sizes differ by at most one
+fn even_split(len: usize, count: usize) -> Vec<usize> {
+    let base = len / count;
+    let extra = len % count;
+    (0..count)
+        .map(|i| if i < extra { base + 1 } else { base })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+    use crate::tree::mbr::RTreeNode;
+
+    fn check_level<P: Scalar, const DIM: usize, LG, T>(
+        node: &RTreeNode<P, DIM, LG, T>,
+        min: usize,
+        max: usize,
+        depth: usize,
+        leaf_depths: &mut Vec<usize>,
+    ) -> usize
+    where
+        LG: MbrLeafGeometry<P, DIM>,
+    {
+        if depth > 0 {
+            assert!(min <= node.len() && node.len() <= max);
+        }
+        match *node {
+            RTreeNode::Leaves {
+                ref mbr,
+                ref children,
+            } => {
+                leaf_depths.push(depth);
+                for child in children {
+                    assert!(child.contained_by_mbr(mbr));
+                }
+                children.len()
+            }
+            RTreeNode::Level {
+                ref mbr,
+                ref children,
+            } => children
+                .iter()
+                .map(|child| {
+                    assert!(child.contained_by_mbr(mbr));
+                    check_level(child, min, max, depth + 1, leaf_depths)
+                })
+                .sum(),
+        }
+    }
+
+    #[test]
+    fn str_pack_sizes() {
+        for &count in &[0usize, 1, 15, 16, 17, 100, 257, 1000] {
+            let leaves = (0..count)
+                .map(|i| {
+                    let i_f64 = i as f64;
+                    MbrLeaf::new(Point::new([i_f64 % 13.0, i_f64 / 13.0, i_f64 % 7.0]), i)
+                })
+                .collect();
+            let root: RTreeNode<f64, 3, Point<f64, 3>, usize> = str_pack(leaves, 6, 16);
+            let mut leaf_depths = Vec::new();
+            assert_eq!(count, check_level(&root, 6, 16, 0, &mut leaf_depths));
+            assert!(leaf_depths.iter().all(|&depth| depth == leaf_depths[0]));
+        }
+    }
+
+    #[test]
+    fn str_pack_just_over_max() {
+        // one leaf past a multiple of max is the tightest fit for the smallest node
+        for &(min, max) in &[(8usize, 16usize), (8, 15), (2, 3), (12, 16)] {
+            let floor = cmp::min(min, (max + 1) / 2);
+            for k in 1..=(max + 1) {
+                let count = k * max + 1;
+                let leaves = (0..count)
+                    .map(|i| {
+                        let i_f64 = i as f64;
+                        MbrLeaf::new(Point::new([i_f64 % 13.0, i_f64 / 13.0, i_f64 % 7.0]), i)
+                    })
+                    .collect();
+                let root: RTreeNode<f64, 3, Point<f64, 3>, usize> = str_pack(leaves, min, max);
+                let mut leaf_depths = Vec::new();
+                assert_eq!(count, check_level(&root, floor, max, 0, &mut leaf_depths));
+                assert!(leaf_depths.iter().all(|&depth| depth == leaf_depths[0]));
+            }
+        }
+    }
+
+    #[test]
+    fn node_sizes_hold_min() {
+        assert_eq!(vec![9, 8], node_sizes(17, 2, 8, 16));
+        assert_eq!(vec![9, 8, 8], node_sizes(25, 3, 8, 12));
+        assert_eq!(vec![2, 2], node_sizes(4, 2, 2, 3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn node_sizes_reject_under_min() {
+        node_sizes(17, 2, 12, 16);
+    }
+
+    #[test]
+    fn slab_count_is_ceil_root() {
+        assert_eq!(1, slab_count(1, 2));
+        assert_eq!(2, slab_count(4, 2));
+        assert_eq!(3, slab_count(5, 2));
+        assert_eq!(2, slab_count(8, 3));
+        assert_eq!(7, slab_count(7, 1));
+    }
+}
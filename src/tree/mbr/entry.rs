@@ -0,0 +1,260 @@
+// Copyright 2016 spatial-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Entry API for `MbrMap`, modelled on `std::collections::hash_map::Entry`
+
+use std::mem;
+
+use crate::tree::mbr::index::{IndexInsert, IndexRemove};
+use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrMap, MbrNode, MbrNodeChildren, NodeChildren};
+use crate::Scalar;
+
+/// A view into a single entry of a map, keyed on exactly matching geometry
+pub enum Entry<'a, LG, NODE, I, R> {
+    /// An entry with matching geometry is in the map
+    Occupied(OccupiedEntry<'a, LG, NODE, I, R>),
+    /// No entry with matching geometry is in the map
+    Vacant(VacantEntry<'a, LG, NODE, I, R>),
+}
+
+/// A view into an occupied entry. Remembers the path to its leaf so it's only searched for once
+pub struct OccupiedEntry<'a, LG, NODE, I, R> {
+    map: &'a mut MbrMap<NODE, I, R>,
+    key: LG,
+    path: Vec<usize>,
+}
+
+/// A view into a vacant entry
+pub struct VacantEntry<'a, LG, NODE, I, R> {
+    map: &'a mut MbrMap<NODE, I, R>,
+    key: LG,
+}
+
+impl<P: Scalar, const DIM: usize, LG, I, R, T, NODE> MbrMap<NODE, I, R>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + PartialEq,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    /// Get the entry for the geometry. Only levels whose mbr contains the geometry are searched.
+    /// If several entries share the geometry, the first one found is used
+    pub fn entry(&mut self, geometry: LG) -> Entry<'_, LG, NODE, I, R> {
+        let mut path = Vec::new();
+        if find_path(&self.root, &geometry, &mut |_| true, &mut path) {
+            Entry::Occupied(OccupiedEntry {
+                map: self,
+                key: geometry,
+                path,
+            })
+        } else {
+            Entry::Vacant(VacantEntry {
+                map: self,
+                key: geometry,
+            })
+        }
+    }
+}
+
+impl<'a, P: Scalar, const DIM: usize, LG, I, R, T, NODE> Entry<'a, LG, NODE, I, R>
+where
+    P: 'a,
+    LG: 'a,
+    T: 'a,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + PartialEq,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    /// The geometry used to find this entry
+    pub fn key(&self) -> &LG {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Modify the item in place if the entry is occupied
+    pub fn and_modify<F: FnOnce(&mut T)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(ref mut entry) = self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    /// Insert the default if the entry is vacant. Returns the entry's item
+    pub fn or_insert(self, default: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Insert the result of `default` if the entry is vacant. Returns the entry's item
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Insert `T::default()` if the entry is vacant. Returns the entry's item
+    pub fn or_default(self) -> &'a mut T
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+}
+
+impl<'a, P: Scalar, const DIM: usize, LG, I, R, T, NODE> OccupiedEntry<'a, LG, NODE, I, R>
+where
+    P: 'a,
+    LG: 'a,
+    T: 'a,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + PartialEq,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    /// The geometry used to find this entry
+    pub fn key(&self) -> &LG {
+        &self.key
+    }
+
+    /// The entry's item
+    pub fn get(&self) -> &T {
+        &leaf_at(&self.map.root, &self.path).item
+    }
+
+    /// The entry's item, mutably
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut leaf_at_mut(&mut self.map.root, &self.path).item
+    }
+
+    /// Convert into a mutable reference to the item bound to the map's lifetime
+    pub fn into_mut(self) -> &'a mut T {
+        &mut leaf_at_mut(&mut self.map.root, &self.path).item
+    }
+
+    /// Replace the entry's item, returning the old one
+    pub fn insert(&mut self, item: T) -> T {
+        mem::replace(self.get_mut(), item)
+    }
+
+    /// Remove the entry from the map, condensing the tree as `MbrMap::remove` would
+    pub fn remove_entry(self) -> (LG, T) {
+        self.map.remove_at(&self.path)
+    }
+
+    /// Remove the entry from the map, returning its item
+    pub fn remove(self) -> T {
+        self.remove_entry().1
+    }
+}
+
+impl<'a, P: Scalar, const DIM: usize, LG, I, R, T, NODE> VacantEntry<'a, LG, NODE, I, R>
+where
+    P: 'a,
+    LG: 'a,
+    T: 'a,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + PartialEq,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    /// The geometry used to find this entry
+    pub fn key(&self) -> &LG {
+        &self.key
+    }
+
+    /// Take ownership of the geometry
+    pub fn into_key(self) -> LG {
+        self.key
+    }
+
+    /// Insert the item with the entry's geometry, without returning a reference to it
+    pub fn insert_entry(self, item: T) {
+        self.map.insert(self.key, item);
+    }
+
+    /// Insert the item with the entry's geometry. Returns the inserted item.
+    /// The insert reports where the new leaf ends up, so it isn't searched for again
+    pub fn insert(self, item: T) -> &'a mut T {
+        let VacantEntry { map, key } = self;
+        let path = map.insert_at(key, item);
+        &mut leaf_at_mut(&mut map.root, &path).item
+    }
+}
+
+/// Record the child indexes leading to the first leaf whose geometry equals the key and whose item is accepted by `matcher`.
+/// Only levels whose mbr contains the key are searched
+pub(crate) fn find_path<P: Scalar, const DIM: usize, LG, T, NODE, F>(
+    node: &NODE,
+    key: &LG,
+    matcher: &mut F,
+    path: &mut Vec<usize>,
+) -> bool
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + PartialEq,
+    F: FnMut(&T) -> bool,
+{
+    match node.children() {
+        NodeChildren::Leaves(children) => {
+            match children
+                .iter()
+                .position(|leaf| leaf.geometry == *key && matcher(&leaf.item))
+            {
+                Some(index) => {
+                    path.push(index);
+                    true
+                }
+                None => false,
+            }
+        }
+        NodeChildren::Levels(children) => {
+            for (index, child) in children.iter().enumerate() {
+                if !key.contained_by_mbr(child.mbr()) {
+                    continue;
+                }
+                path.push(index);
+                if find_path(child, key, matcher, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+    }
+}
+
+fn leaf_at<'n, P: Scalar, const DIM: usize, LG, T, NODE>(
+    node: &'n NODE,
+    path: &[usize],
+) -> &'n MbrLeaf<P, DIM, LG, T>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    match node.children() {
+        NodeChildren::Leaves(children) => &children[path[0]],
+        NodeChildren::Levels(children) => leaf_at(&children[path[0]], &path[1..]),
+    }
+}
+
+fn leaf_at_mut<'n, P: Scalar, const DIM: usize, LG, T, NODE>(
+    node: &'n mut NODE,
+    path: &[usize],
+) -> &'n mut MbrLeaf<P, DIM, LG, T>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    match node.children_mut() {
+        NodeChildren::Leaves(children) => &mut children[path[0]],
+        NodeChildren::Levels(children) => leaf_at_mut(&mut children[path[0]], &path[1..]),
+    }
+}
@@ -0,0 +1,732 @@
+// Copyright 2016 spatial-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A compact, read-only binary format for frozen trees
+//!
+//! Everything is little-endian. The layout is
+//!
+//! * header, 32 bytes: magic `b"SPTR"`, version `u16`, scalar size in bytes `u8`, scalar kind `u8`,
+//!   dimension count `u32`, reserved `u32`, node count `u64`, leaf count `u64`.
+//!   The scalar kind is 0 for IEEE 754 floats, 1 for signed and 2 for unsigned integers
+//! * node table, one record per node in breadth first order with the root first:
+//!   the mbr as `DIM` (min, max) scalar pairs, first child index `u64`, child count `u32`, and
+//!   a kind `u32` that is 0 when the children are nodes and 1 when they are leaves
+//! * leaf table, one 16 byte record per leaf: data offset `u64`, geometry length `u32`, payload length `u32`
+//! * data, holding each leaf's encoded geometry immediately followed by its payload bytes.
+//!   Offsets are relative to the start of the data
+//!
+//! `FrozenMap` queries the bytes in place, decoding only the nodes and leaves a query visits.
+
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+
+use crate::geometry::{LineSegment, LineString, Point, Rect, Shapes};
+use crate::tree::mbr::index::{IndexInsert, IndexRemove};
+use crate::tree::mbr::{
+    MbrLeaf, MbrLeafGeometry, MbrMap, MbrNode, MbrNodeChildren, MbrQuery, MbrRectQuery,
+    NodeChildren,
+};
+use crate::{Scalar, FP};
+
+/// Identifies the frozen format
+pub const FROZEN_MAGIC: [u8; 4] = *b"SPTR";
+/// The frozen format version written by `MbrMap::freeze`
+pub const FROZEN_VERSION: u16 = 1;
+
+const HEADER_LEN: usize = 32;
+const LEAF_RECORD_LEN: usize = 16;
+const KIND_LEVELS: u32 = 0;
+const KIND_LEAVES: u32 = 1;
+const SCALAR_FLOAT: u8 = 0;
+const SCALAR_SIGNED: u8 = 1;
+const SCALAR_UNSIGNED: u8 = 2;
+
+/// Errors from writing or reading a frozen tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrozenError {
+    /// A node has more children, or a leaf more geometry or payload bytes, than the format's `u32` fields hold
+    TooLarge,
+    /// A leaf's geometry has no frozen encoding
+    UnsupportedGeometry,
+    /// The bytes don't start with `FROZEN_MAGIC`
+    BadMagic,
+    /// The format version isn't supported by this reader
+    UnsupportedVersion(u16),
+    /// The tree was written with a different scalar size, in bytes
+    ScalarMismatch(u8),
+    /// The tree was written with a different scalar kind
+    ScalarKindMismatch(u8),
+    /// The tree was written with a different dimension count
+    DimensionMismatch(u32),
+    /// A table, record or leaf points past the end of the bytes
+    Truncated,
+    /// A node record is inconsistent with the tree structure
+    InvalidNode(u64),
+    /// A leaf's geometry couldn't be decoded
+    InvalidGeometry(u64),
+}
+
+impl fmt::Display for FrozenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrozenError::TooLarge => write!(f, "tree is too large to freeze"),
+            FrozenError::UnsupportedGeometry => write!(f, "geometry can't be frozen"),
+            FrozenError::BadMagic => write!(f, "not a frozen tree"),
+            FrozenError::UnsupportedVersion(version) => {
+                write!(f, "unsupported frozen tree version {}", version)
+            }
+            FrozenError::ScalarMismatch(size) => {
+                write!(f, "frozen tree has {} byte scalars", size)
+            }
+            FrozenError::ScalarKindMismatch(kind) => {
+                write!(f, "frozen tree has scalars of kind {}", kind)
+            }
+            FrozenError::DimensionMismatch(dim) => write!(f, "frozen tree has {} dimensions", dim),
+            FrozenError::Truncated => write!(f, "frozen tree is truncated"),
+            FrozenError::InvalidNode(node) => write!(f, "frozen tree node {} is invalid", node),
+            FrozenError::InvalidGeometry(leaf) => {
+                write!(f, "frozen tree leaf {} has invalid geometry", leaf)
+            }
+        }
+    }
+}
+
+impl Error for FrozenError {}
+
+/// A scalar with a fixed size little-endian encoding.
+/// `isize` and `usize` aren't encoded, since their size depends on the platform that wrote them
+pub trait FrozenScalar: Sized {
+    /// Encoded size in bytes
+    const BYTES: usize;
+
+    /// Encoded kind: 0 for IEEE 754 floats, 1 for signed and 2 for unsigned integers
+    const KIND: u8;
+
+    /// Append the little-endian encoding
+    fn write_le(self, bytes: &mut Vec<u8>);
+
+    /// Decode from exactly `BYTES` little-endian bytes
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! frozen_scalar {
+    ($kind:expr; $($t:ty)*) => {
+        $(
+            impl FrozenScalar for $t {
+                const BYTES: usize = mem::size_of::<$t>();
+                const KIND: u8 = $kind;
+
+                fn write_le(self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_le(bytes: &[u8]) -> $t {
+                    let mut le = [0u8; mem::size_of::<$t>()];
+                    le.copy_from_slice(bytes);
+                    <$t>::from_le_bytes(le)
+                }
+            }
+        )*
+    };
+}
+
+frozen_scalar!(SCALAR_FLOAT; f32 f64);
+frozen_scalar!(SCALAR_SIGNED; i8 i16 i32 i64 i128);
+frozen_scalar!(SCALAR_UNSIGNED; u8 u16 u32 u64 u128);
+
+/// Leaf geometry that can be written to and read from the frozen format
+pub trait FrozenGeometry<P: Scalar, const DIM: usize>: Sized {
+    /// Append the encoded geometry. Fails with `FrozenError::UnsupportedGeometry` if it has no encoding
+    fn write_frozen(&self, bytes: &mut Vec<u8>) -> Result<(), FrozenError>;
+
+    /// Decode the geometry from all of the bytes. Returns `None` if they don't hold valid geometry
+    fn read_frozen(bytes: &[u8]) -> Option<Self>;
+}
+
+/// Bounds checked little-endian reads
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let mut le = [0u8; 2];
+        le.copy_from_slice(self.take(2)?);
+        Some(u16::from_le_bytes(le))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let mut le = [0u8; 4];
+        le.copy_from_slice(self.take(4)?);
+        Some(u32::from_le_bytes(le))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let mut le = [0u8; 8];
+        le.copy_from_slice(self.take(8)?);
+        Some(u64::from_le_bytes(le))
+    }
+
+    fn scalar<P: Scalar + FrozenScalar>(&mut self) -> Option<P> {
+        let scalar = P::read_le(self.take(P::BYTES)?);
+        if scalar.is_finite() {
+            Some(scalar)
+        } else {
+            None
+        }
+    }
+
+    fn coords<P: Scalar + FrozenScalar, const DIM: usize>(&mut self) -> Option<[P; DIM]> {
+        let mut coords = [P::default(); DIM];
+        for coord in coords.iter_mut() {
+            *coord = self.scalar()?;
+        }
+        Some(coords)
+    }
+
+    fn edges<P: Scalar + FrozenScalar, const DIM: usize>(&mut self) -> Option<Rect<P, DIM>> {
+        let mut edges = [(P::default(), P::default()); DIM];
+        for edge in edges.iter_mut() {
+            *edge = (self.scalar()?, self.scalar()?);
+        }
+        Some(Rect { edges })
+    }
+}
+
+fn write_edges<P: Scalar + FrozenScalar, const DIM: usize>(
+    rect: &Rect<P, DIM>,
+    bytes: &mut Vec<u8>,
+) {
+    for &(min, max) in rect.iter() {
+        min.write_le(bytes);
+        max.write_le(bytes);
+    }
+}
+
+impl<P: Scalar + FrozenScalar, const DIM: usize> FrozenGeometry<P, DIM> for Point<P, DIM> {
+    fn write_frozen(&self, bytes: &mut Vec<u8>) -> Result<(), FrozenError> {
+        for &coord in self.iter() {
+            coord.write_le(bytes);
+        }
+        Ok(())
+    }
+
+    fn read_frozen(bytes: &[u8]) -> Option<Point<P, DIM>> {
+        let mut cursor = Cursor { bytes };
+        let coords = cursor.coords()?;
+        if !cursor.bytes.is_empty() {
+            return None;
+        }
+        Some(Point { coords })
+    }
+}
+
+impl<P: FP + FrozenScalar, const DIM: usize> FrozenGeometry<P, DIM> for LineSegment<P, DIM> {
+    fn write_frozen(&self, bytes: &mut Vec<u8>) -> Result<(), FrozenError> {
+        self.x.write_frozen(bytes)?;
+        self.y.write_frozen(bytes)
+    }
+
+    fn read_frozen(bytes: &[u8]) -> Option<LineSegment<P, DIM>> {
+        let mut cursor = Cursor { bytes };
+        let x = Point {
+            coords: cursor.coords()?,
+        };
+        let y = Point {
+            coords: cursor.coords()?,
+        };
+        if !cursor.bytes.is_empty() {
+            return None;
+        }
+        Some(LineSegment { x, y })
+    }
+}
+
+impl<P: Scalar + FrozenScalar, const DIM: usize> FrozenGeometry<P, DIM> for Rect<P, DIM> {
+    fn write_frozen(&self, bytes: &mut Vec<u8>) -> Result<(), FrozenError> {
+        write_edges(self, bytes);
+        Ok(())
+    }
+
+    fn read_frozen(bytes: &[u8]) -> Option<Rect<P, DIM>> {
+        let mut cursor = Cursor { bytes };
+        let rect = cursor.edges()?;
+        if !cursor.bytes.is_empty() || rect.iter().any(|&(min, max)| min > max) {
+            return None;
+        }
+        Some(rect)
+    }
+}
+
+/// Line strings are written as their vertices, so the vertex count follows from the length
+impl<P: FP + FrozenScalar, const DIM: usize> FrozenGeometry<P, DIM> for LineString<P, DIM> {
+    fn write_frozen(&self, bytes: &mut Vec<u8>) -> Result<(), FrozenError> {
+        for vertex in self.vertices() {
+            vertex.write_frozen(bytes)?;
+        }
+        Ok(())
+    }
+
+    fn read_frozen(bytes: &[u8]) -> Option<LineString<P, DIM>> {
+        let vertex_len = DIM * P::BYTES;
+        if vertex_len == 0 || bytes.len() % vertex_len != 0 {
+            return None;
+        }
+        let mut cursor = Cursor { bytes };
+        let mut vertices = Vec::with_capacity(bytes.len() / vertex_len);
+        while !cursor.bytes.is_empty() {
+            vertices.push(cursor.coords()?);
+        }
+        LineString::try_new(vertices).ok()
+    }
+}
+
+/// Shapes are written as a tag byte (0 for `Point`, 1 for `LineSegment`, 2 for `Rect` and 3 for `LineString`)
+/// followed by the shape. `Custom` shapes can't be frozen and fail with `FrozenError::UnsupportedGeometry`
+impl<P: FP + FrozenScalar, const DIM: usize> FrozenGeometry<P, DIM> for Shapes<P, DIM> {
+    fn write_frozen(&self, bytes: &mut Vec<u8>) -> Result<(), FrozenError> {
+        match *self {
+            Shapes::Point(ref point) => {
+                bytes.push(0);
+                point.write_frozen(bytes)
+            }
+            Shapes::LineSegment(ref line) => {
+                bytes.push(1);
+                line.write_frozen(bytes)
+            }
+            Shapes::Rect(ref rect) => {
+                bytes.push(2);
+                rect.write_frozen(bytes)
+            }
+            Shapes::LineString(ref line) => {
+                bytes.push(3);
+                line.write_frozen(bytes)
+            }
+            // there's no encoding for an application's shape
+            Shapes::Custom(_) => Err(FrozenError::UnsupportedGeometry),
+        }
+    }
+
+    fn read_frozen(bytes: &[u8]) -> Option<Shapes<P, DIM>> {
+        let (&tag, shape) = bytes.split_first()?;
+        match tag {
+            0 => Point::read_frozen(shape).map(Shapes::Point),
+            1 => LineSegment::read_frozen(shape).map(Shapes::LineSegment),
+            2 => Rect::read_frozen(shape).map(Shapes::Rect),
+            3 => LineString::read_frozen(shape).map(Shapes::LineString),
+            _ => None,
+        }
+    }
+}
+
+fn node_record_len<P: FrozenScalar, const DIM: usize>() -> usize {
+    2 * DIM * P::BYTES + 16
+}
+
+impl<P: Scalar + FrozenScalar, const DIM: usize, LG, I, R, T, NODE> MbrMap<NODE, I, R>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + FrozenGeometry<P, DIM>,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    /// Write the tree in the frozen binary format. `payload` appends each item's bytes.
+    /// The frozen tree keeps the map's exact structure and can be queried in place with `FrozenMap`.
+    /// Fails with `FrozenError::TooLarge` if a node or leaf doesn't fit the format's fields,
+    /// or with `FrozenError::UnsupportedGeometry` if a leaf's geometry can't be frozen
+    pub fn freeze<F: FnMut(&T, &mut Vec<u8>)>(
+        &self,
+        mut payload: F,
+    ) -> Result<Vec<u8>, FrozenError> {
+        // flatten breadth first so every node's children are contiguous
+        let mut nodes = vec![&self.root];
+        let mut node_table = Vec::new();
+        let mut leaf_table = Vec::new();
+        let mut data = Vec::new();
+        let mut leaf_count = 0u64;
+        let mut i = 0;
+        while i < nodes.len() {
+            let node = nodes[i];
+            write_edges(node.mbr(), &mut node_table);
+            match node.children() {
+                NodeChildren::Levels(children) => {
+                    (nodes.len() as u64).write_le(&mut node_table);
+                    table_u32(children.len())?.write_le(&mut node_table);
+                    KIND_LEVELS.write_le(&mut node_table);
+                    nodes.extend(children);
+                }
+                NodeChildren::Leaves(children) => {
+                    leaf_count.write_le(&mut node_table);
+                    table_u32(children.len())?.write_le(&mut node_table);
+                    KIND_LEAVES.write_le(&mut node_table);
+                    for leaf in children {
+                        let offset = data.len();
+                        leaf.geometry.write_frozen(&mut data)?;
+                        let geometry_len = data.len() - offset;
+                        payload(&leaf.item, &mut data);
+                        let payload_len = data.len() - offset - geometry_len;
+                        (offset as u64).write_le(&mut leaf_table);
+                        table_u32(geometry_len)?.write_le(&mut leaf_table);
+                        table_u32(payload_len)?.write_le(&mut leaf_table);
+                    }
+                    leaf_count += children.len() as u64;
+                }
+            }
+            i += 1;
+        }
+        let mut bytes =
+            Vec::with_capacity(HEADER_LEN + node_table.len() + leaf_table.len() + data.len());
+        bytes.extend_from_slice(&FROZEN_MAGIC);
+        FROZEN_VERSION.write_le(&mut bytes);
+        bytes.push(P::BYTES as u8);
+        bytes.push(P::KIND);
+        (DIM as u32).write_le(&mut bytes);
+        0u32.write_le(&mut bytes);
+        (nodes.len() as u64).write_le(&mut bytes);
+        leaf_count.write_le(&mut bytes);
+        bytes.append(&mut node_table);
+        bytes.append(&mut leaf_table);
+        bytes.append(&mut data);
+        Ok(bytes)
+    }
+}
+
+/// A length for one of the tables' `u32` fields
+fn table_u32(len: usize) -> Result<u32, FrozenError> {
+    u32::try_from(len).map_err(|_| FrozenError::TooLarge)
+}
+
+/// A node of a frozen tree, decoded on demand. Lets the regular `MbrQuery` implementations navigate a `FrozenMap`
+#[derive(Debug, Clone)]
+pub struct FrozenNode<P: Scalar, const DIM: usize> {
+    mbr: Rect<P, DIM>,
+    has_leaves: bool,
+    children: Range<u64>,
+}
+
+impl<P: Scalar, const DIM: usize> MbrNode<P, DIM> for FrozenNode<P, DIM> {
+    fn new_leaves() -> FrozenNode<P, DIM> {
+        FrozenNode {
+            mbr: Rect::max_inverted(),
+            has_leaves: true,
+            children: 0..0,
+        }
+    }
+
+    fn new_no_alloc() -> FrozenNode<P, DIM> {
+        FrozenNode::new_leaves()
+    }
+
+    fn has_leaves(&self) -> bool {
+        self.has_leaves
+    }
+
+    fn has_levels(&self) -> bool {
+        !self.has_leaves
+    }
+
+    fn mbr(&self) -> &Rect<P, DIM> {
+        &self.mbr
+    }
+
+    fn mbr_mut(&mut self) -> &mut Rect<P, DIM> {
+        &mut self.mbr
+    }
+
+    fn len(&self) -> usize {
+        (self.children.end - self.children.start) as usize
+    }
+
+    fn is_empty(&self) -> bool {
+        self.children.start == self.children.end
+    }
+}
+
+impl<P: Scalar, const DIM: usize> MbrLeafGeometry<P, DIM> for FrozenNode<P, DIM> {
+    fn dim(&self) -> usize {
+        self.mbr.dim()
+    }
+
+    fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>) {
+        self.mbr.expand_mbr_to_fit(mbr)
+    }
+
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr.distance_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        self.mbr.distance_from_point(point)
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr.min_distance_from_mbr(mbr)
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr.min_max_distance_from_mbr(mbr)
+    }
+
+    fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        self.mbr.contained_by_mbr(mbr)
+    }
+
+    fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        self.mbr.overlapped_by_mbr(mbr)
+    }
+
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr.area_overlapped_with_mbr(mbr)
+    }
+
+    fn area(&self) -> P::Measure {
+        self.mbr.area()
+    }
+
+    fn min_for_axis(&self, dim: usize) -> P {
+        self.mbr.min_for_axis(dim)
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        self.mbr.max_for_axis(dim)
+    }
+}
+
+/// A read-only tree queried straight from the frozen binary format, such as a memory-mapped file.
+/// Only the header is checked up front. Nodes and leaves are decoded as queries reach them
+pub struct FrozenMap<'a, P: Scalar, const DIM: usize, LG> {
+    node_table: &'a [u8],
+    leaf_table: &'a [u8],
+    data: &'a [u8],
+    node_count: u64,
+    leaf_count: u64,
+    _p: PhantomData<P>,
+    _lg: PhantomData<LG>,
+}
+
+impl<'a, P: Scalar + FrozenScalar, const DIM: usize, LG> FrozenMap<'a, P, DIM, LG>
+where
+    LG: MbrLeafGeometry<P, DIM> + FrozenGeometry<P, DIM>,
+{
+    /// Open a frozen tree written by `MbrMap::freeze`
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<FrozenMap<'a, P, DIM, LG>, FrozenError> {
+        let mut cursor = Cursor { bytes };
+        let header = cursor.take(HEADER_LEN).ok_or(FrozenError::Truncated)?;
+        let mut header = Cursor { bytes: header };
+        if header.take(4) != Some(&FROZEN_MAGIC[..]) {
+            return Err(FrozenError::BadMagic);
+        }
+        let version = header.u16().ok_or(FrozenError::Truncated)?;
+        if version != FROZEN_VERSION {
+            return Err(FrozenError::UnsupportedVersion(version));
+        }
+        let scalar = header.take(2).ok_or(FrozenError::Truncated)?;
+        let (scalar_size, scalar_kind) = (scalar[0], scalar[1]);
+        if scalar_size as usize != P::BYTES {
+            return Err(FrozenError::ScalarMismatch(scalar_size));
+        }
+        if scalar_kind != P::KIND {
+            return Err(FrozenError::ScalarKindMismatch(scalar_kind));
+        }
+        let dim = header.u32().ok_or(FrozenError::Truncated)?;
+        if dim as usize != DIM {
+            return Err(FrozenError::DimensionMismatch(dim));
+        }
+        header.u32().ok_or(FrozenError::Truncated)?;
+        let node_count = header.u64().ok_or(FrozenError::Truncated)?;
+        let leaf_count = header.u64().ok_or(FrozenError::Truncated)?;
+        if node_count == 0 {
+            return Err(FrozenError::InvalidNode(0));
+        }
+        let table_len = |count: u64, record_len: usize| {
+            usize::try_from(count)
+                .ok()
+                .and_then(|count| count.checked_mul(record_len))
+                .ok_or(FrozenError::Truncated)
+        };
+        let node_table = cursor
+            .take(table_len(node_count, node_record_len::<P, DIM>())?)
+            .ok_or(FrozenError::Truncated)?;
+        let leaf_table = cursor
+            .take(table_len(leaf_count, LEAF_RECORD_LEN)?)
+            .ok_or(FrozenError::Truncated)?;
+        Ok(FrozenMap {
+            node_table,
+            leaf_table,
+            data: cursor.bytes,
+            node_count,
+            leaf_count,
+            _p: PhantomData,
+            _lg: PhantomData,
+        })
+    }
+
+    /// Whether the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Length of the map
+    pub fn len(&self) -> usize {
+        self.leaf_count as usize
+    }
+
+    /// Iter for the map. Yields each leaf's geometry and payload bytes
+    pub fn iter(&self) -> FrozenIter<'a, P, DIM, LG, MbrRectQuery<P, DIM>> {
+        self.iter_query(MbrRectQuery::Overlaps(Rect::max()))
+    }
+
+    /// Iter for the map with a given query. Yields each matching leaf's geometry and payload bytes
+    pub fn iter_query<Q: MbrQuery<P, DIM, LG, &'a [u8], FrozenNode<P, DIM>>>(
+        &self,
+        query: Q,
+    ) -> FrozenIter<'a, P, DIM, LG, Q> {
+        FrozenIter {
+            map: *self,
+            query,
+            // start from the root
+            level_stack: vec![Range { start: 0, end: 1 }],
+            leaf_iter: 0..0,
+            failed: false,
+        }
+    }
+
+    fn node(&self, index: u64) -> Result<FrozenNode<P, DIM>, FrozenError> {
+        let record_len = node_record_len::<P, DIM>();
+        let start = index as usize * record_len;
+        let mut cursor = Cursor {
+            bytes: &self.node_table[start..start + record_len],
+        };
+        let invalid = FrozenError::InvalidNode(index);
+        let mbr = cursor.edges().ok_or_else(|| invalid.clone())?;
+        let first = cursor.u64().ok_or_else(|| invalid.clone())?;
+        let len = cursor.u32().ok_or_else(|| invalid.clone())?;
+        let end = first
+            .checked_add(len as u64)
+            .ok_or_else(|| invalid.clone())?;
+        let has_leaves = match cursor.u32() {
+            Some(KIND_LEVELS) if index < first && end <= self.node_count => false,
+            Some(KIND_LEAVES) if end <= self.leaf_count => true,
+            _ => return Err(invalid),
+        };
+        Ok(FrozenNode {
+            mbr,
+            has_leaves,
+            children: first..end,
+        })
+    }
+
+    fn leaf(&self, index: u64) -> Result<MbrLeaf<P, DIM, LG, &'a [u8]>, FrozenError> {
+        let start = index as usize * LEAF_RECORD_LEN;
+        let mut cursor = Cursor {
+            bytes: &self.leaf_table[start..start + LEAF_RECORD_LEN],
+        };
+        let (offset, geometry_len, payload_len) = match (cursor.u64(), cursor.u32(), cursor.u32()) {
+            (Some(offset), Some(geometry_len), Some(payload_len)) => {
+                (offset, geometry_len as usize, payload_len as usize)
+            }
+            _ => return Err(FrozenError::Truncated),
+        };
+        let mut data = Cursor { bytes: self.data };
+        usize::try_from(offset)
+            .ok()
+            .and_then(|offset| data.take(offset))
+            .ok_or(FrozenError::Truncated)?;
+        let geometry = data.take(geometry_len).ok_or(FrozenError::Truncated)?;
+        let payload = data.take(payload_len).ok_or(FrozenError::Truncated)?;
+        let geometry = LG::read_frozen(geometry).ok_or(FrozenError::InvalidGeometry(index))?;
+        Ok(MbrLeaf::new(geometry, payload))
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG> Clone for FrozenMap<'_, P, DIM, LG> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG> Copy for FrozenMap<'_, P, DIM, LG> {}
+
+/// Iterate all leaves of a `FrozenMap` matching a query.
+/// Yields an error and stops if the query reaches a corrupt node or leaf
+pub struct FrozenIter<'a, P: Scalar, const DIM: usize, LG, Q> {
+    map: FrozenMap<'a, P, DIM, LG>,
+    query: Q,
+    level_stack: Vec<Range<u64>>,
+    leaf_iter: Range<u64>,
+    failed: bool,
+}
+
+impl<'a, P: Scalar + FrozenScalar, const DIM: usize, LG, Q> FrozenIter<'a, P, DIM, LG, Q>
+where
+    LG: MbrLeafGeometry<P, DIM> + FrozenGeometry<P, DIM>,
+    Q: MbrQuery<P, DIM, LG, &'a [u8], FrozenNode<P, DIM>>,
+{
+    fn next_entry(&mut self) -> Result<Option<(LG, &'a [u8])>, FrozenError> {
+        loop {
+            for index in self.leaf_iter.by_ref() {
+                let leaf = self.map.leaf(index)?;
+                if self.query.accept_leaf(&leaf) {
+                    return Ok(Some(leaf.extract()));
+                }
+            }
+            let index = match self.level_stack.last_mut() {
+                Some(levels) => match levels.next() {
+                    Some(index) => index,
+                    None => {
+                        self.level_stack.pop();
+                        continue;
+                    }
+                },
+                None => return Ok(None),
+            };
+            let node = self.map.node(index)?;
+            if node.is_empty() || !self.query.accept_level(&node) {
+                continue;
+            }
+            if node.has_leaves() {
+                self.leaf_iter = node.children;
+            } else {
+                self.level_stack.push(node.children);
+            }
+        }
+    }
+}
+
+impl<'a, P: Scalar + FrozenScalar, const DIM: usize, LG, Q> Iterator
+    for FrozenIter<'a, P, DIM, LG, Q>
+where
+    LG: MbrLeafGeometry<P, DIM> + FrozenGeometry<P, DIM>,
+    Q: MbrQuery<P, DIM, LG, &'a [u8], FrozenNode<P, DIM>>,
+{
+    type Item = Result<(LG, &'a [u8]), FrozenError>;
+
+    fn next(&mut self) -> Option<Result<(LG, &'a [u8]), FrozenError>> {
+        if self.failed {
+            return None;
+        }
+        let next = self.next_entry();
+        if next.is_err() {
+            self.failed = true;
+            self.level_stack = Vec::with_capacity(0);
+            self.leaf_iter = 0..0;
+        }
+        next.transpose()
+    }
+}
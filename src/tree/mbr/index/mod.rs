@@ -7,9 +7,11 @@
 
 //! Specific implementations for inserting and removing leaves
 
-use crate::geometry::Rect;
-use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode, MbrQuery};
-use crate::FP;
+use std::mem;
+
+use crate::geometry::{Point, Ray, Rect};
+use crate::tree::mbr::{LeafPart, MbrLeaf, MbrLeafGeometry, MbrNode, MbrQuery};
+use crate::Scalar;
 pub mod r;
 pub mod rstar;
 
@@ -20,14 +22,20 @@ const FORCE_SPLIT: bool = true;
 const DONT_FORCE_SPLIT: bool = false;
 
 /// Insert the leaf into the root
-pub trait IndexInsert<P: FP, const DIM: usize, LG, T, NODE>
+pub trait IndexInsert<P: Scalar, const DIM: usize, LG, T, NODE>
 where
     NODE: MbrNode<P, DIM>,
 {
     fn insert_into_root(&self, root: NODE, leaf: MbrLeaf<P, DIM, LG, T>) -> NODE;
 
+    /// Insert the leaf into the root like `insert_into_root`,
+    /// also returning the child indexes leading from the new root to the leaf
+    fn insert_into_root_at(&self, root: NODE, leaf: MbrLeaf<P, DIM, LG, T>) -> (NODE, Vec<usize>);
+
     fn preferred_min(&self) -> usize;
 
+    fn max(&self) -> usize;
+
     fn new_leaves(&self) -> NODE;
 
     fn new_no_alloc_leaves(&self) -> NODE;
@@ -36,7 +44,7 @@ where
 pub type RemoveReturn<P, const DIM: usize, LG, T, NODE> = (NODE, Vec<MbrLeaf<P, DIM, LG, T>>);
 
 /// Remove entries from the tree that match the query, but not the retain function f.
-pub trait IndexRemove<P: FP, const DIM: usize, LG, T, NODE, I>
+pub trait IndexRemove<P: Scalar, const DIM: usize, LG, T, NODE, I>
 where
     NODE: MbrNode<P, DIM>,
     I: IndexInsert<P, DIM, LG, T, NODE>,
@@ -48,10 +56,19 @@ where
         query: Q,
         f: F,
     ) -> RemoveReturn<P, DIM, LG, T, NODE>;
+
+    /// Remove the leaf at the child indexes leading to it from the root,
+    /// condensing the tree as `remove_from_root` would
+    fn remove_at_path(
+        &self,
+        root: NODE,
+        insert_index: &I,
+        path: &[usize],
+    ) -> (NODE, MbrLeaf<P, DIM, LG, T>);
 }
 
 /// Generic trait for splitting an MbrNode
-pub trait MbrNodeSplit<P: FP, const DIM: usize> {
+pub trait MbrNodeSplit<P: Scalar, const DIM: usize> {
     fn split<V: MbrLeafGeometry<P, DIM>>(
         &self,
         min: usize,
@@ -59,3 +76,194 @@ pub trait MbrNodeSplit<P: FP, const DIM: usize> {
         children: &mut Vec<V>,
     ) -> (Rect<P, DIM>, Vec<V>);
 }
+
+/// How a level being inserted into relates to the leaf an insert follows
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Follow {
+    /// No leaf is followed
+    Nothing,
+    /// The leaf being inserted is followed, and the level is this deep on its path
+    Inserted(usize),
+    /// The followed leaf is under the level, which is this deep on its path
+    Under(usize),
+    /// The followed leaf isn't under the level
+    Elsewhere,
+}
+
+/// The child indexes leading from the root to a followed leaf.
+/// Splits and reinserts move leaves around, so inserts keep the path up to date as they restructure the levels on it
+#[derive(Debug, Default)]
+pub(crate) struct LeafPath {
+    pub(crate) path: Vec<usize>,
+    /// Set when a split moves the rest of the path into the new sibling, until the parent takes the sibling in
+    in_split: bool,
+}
+
+impl LeafPath {
+    /// The follow for the child at `index` that an insert descends into
+    pub(crate) fn child(&mut self, follow: Follow, index: usize) -> Follow {
+        match follow {
+            Follow::Inserted(depth) => {
+                self.path.truncate(depth);
+                self.path.push(index);
+                Follow::Inserted(depth + 1)
+            }
+            Follow::Under(depth) if self.path[depth] == index => Follow::Under(depth + 1),
+            Follow::Under(_) => Follow::Elsewhere,
+            follow => follow,
+        }
+    }
+
+    /// Record the leaf pushed at `index` of a leaf level
+    pub(crate) fn pushed(&mut self, follow: Follow, index: usize) {
+        if let Follow::Inserted(depth) = follow {
+            self.path.truncate(depth);
+            self.path.push(index);
+        }
+    }
+
+    /// Take in a child's split sibling, pushed at `index`, if the path moved into it
+    pub(crate) fn pushed_split(&mut self, follow: Follow, index: usize) {
+        if let Follow::Inserted(depth) | Follow::Under(depth) = follow {
+            if self.take_split() {
+                self.path[depth] = index;
+            }
+        }
+    }
+
+    /// Split the children of a level with `split`, following the path through them.
+    /// Returns what the split returns, with the split off children unwrapped
+    pub(crate) fn split<P, const DIM: usize, V, X, S>(
+        &mut self,
+        follow: Follow,
+        children: &mut Vec<V>,
+        split: S,
+    ) -> (X, Vec<V>)
+    where
+        P: Scalar,
+        V: MbrLeafGeometry<P, DIM>,
+        S: FnOnce(&mut Vec<Tagged<V>>) -> (X, Vec<Tagged<V>>),
+    {
+        let depth = match follow {
+            Follow::Inserted(depth) | Follow::Under(depth) => Some(depth),
+            Follow::Nothing | Follow::Elsewhere => None,
+        };
+        let followed = depth.map(|depth| self.path[depth]);
+        let mut tagged = children
+            .drain(..)
+            .enumerate()
+            .map(|(index, child)| Tagged {
+                child,
+                followed: Some(index) == followed,
+            })
+            .collect();
+        let (result, split_off) = split(&mut tagged);
+        if let Some(depth) = depth {
+            match tagged.iter().position(|child| child.followed) {
+                Some(index) => self.path[depth] = index,
+                None => {
+                    self.path[depth] = split_off
+                        .iter()
+                        .position(|child| child.followed)
+                        .unwrap_or_else(|| unreachable!("a split keeps every child"));
+                    self.in_split = true;
+                }
+            }
+        }
+        children.extend(tagged.into_iter().map(|tagged| tagged.child));
+        let split_off = split_off.into_iter().map(|tagged| tagged.child).collect();
+        (result, split_off)
+    }
+
+    /// Whether the last split moved the path into the new sibling. Clears the flag
+    pub(crate) fn take_split(&mut self) -> bool {
+        mem::replace(&mut self.in_split, false)
+    }
+
+    /// Grow the path for a root that split into a new level holding the old root and its sibling
+    pub(crate) fn split_root(&mut self, follow: Follow) {
+        if let Follow::Inserted(_) | Follow::Under(_) = follow {
+            let index = if self.take_split() { 1 } else { 0 };
+            self.path.insert(0, index);
+        }
+    }
+}
+
+/// A child tagged with whether the followed path runs through it, so a split can be traced
+pub(crate) struct Tagged<V> {
+    pub(crate) child: V,
+    followed: bool,
+}
+
+impl<P: Scalar, const DIM: usize, V> MbrLeafGeometry<P, DIM> for Tagged<V>
+where
+    V: MbrLeafGeometry<P, DIM>,
+{
+    fn dim(&self) -> usize {
+        self.child.dim()
+    }
+
+    fn area(&self) -> P::Measure {
+        self.child.area()
+    }
+
+    fn min_for_axis(&self, dim: usize) -> P {
+        self.child.min_for_axis(dim)
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        self.child.max_for_axis(dim)
+    }
+
+    fn expand_mbr_to_fit(&self, edges: &mut Rect<P, DIM>) {
+        self.child.expand_mbr_to_fit(edges)
+    }
+
+    fn distance_from_mbr_center(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.child.distance_from_mbr_center(edges)
+    }
+
+    fn distance_sq_from_mbr_center(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.child.distance_sq_from_mbr_center(edges)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        self.child.distance_from_point(point)
+    }
+
+    fn min_distance_from_mbr(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.child.min_distance_from_mbr(edges)
+    }
+
+    fn min_max_distance_from_mbr(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.child.min_max_distance_from_mbr(edges)
+    }
+
+    fn contained_by_mbr(&self, edges: &Rect<P, DIM>) -> bool {
+        self.child.contained_by_mbr(edges)
+    }
+
+    fn overlapped_by_mbr(&self, edges: &Rect<P, DIM>) -> bool {
+        self.child.overlapped_by_mbr(edges)
+    }
+
+    fn area_overlapped_with_mbr(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.child.area_overlapped_with_mbr(edges)
+    }
+
+    fn is_finite(&self) -> bool {
+        self.child.is_finite()
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        self.child.max_distance_from_point(point)
+    }
+
+    fn ray_entry(&self, ray: &Ray<P::Measure, DIM>) -> Option<P::Measure> {
+        self.child.ray_entry(ray)
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        self.child.parts()
+    }
+}
@@ -12,41 +12,47 @@ use std::ops::Deref;
 
 use itertools::Itertools;
 use num::{Bounded, One, Zero};
-use ordered_float::NotNan;
+use ordered_float::OrderedFloat;
 
 use crate::geometry::Rect;
 use crate::tree::mbr::index::{
-    IndexInsert, IndexRemove, MbrNodeSplit, RemoveReturn, AT_ROOT, D_MAX, NOT_AT_ROOT,
+    Follow, IndexInsert, IndexRemove, LeafPath, MbrNodeSplit, RemoveReturn, AT_ROOT, D_MAX,
+    NOT_AT_ROOT,
+};
+use crate::tree::mbr::{
+    MbrLeaf, MbrLeafGeometry, MbrNode, MbrNodeChildren, MbrQuery, NodeChildren, RTreeNode,
 };
-use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode, MbrQuery, RTreeNode};
 use crate::vecext::RetainAndAppend;
-use crate::FP;
+use crate::Scalar;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 #[must_use]
-enum InsertResult<P: FP, const DIM: usize, LG, T> {
+enum InsertResult<P: Scalar, const DIM: usize, LG, T> {
     Ok,
     Split(RTreeNode<P, DIM, LG, T>),
 }
 
-pub trait PickSeed<P: FP, const DIM: usize, LG, T> {
+pub trait PickSeed<P: Scalar, const DIM: usize, LG, T> {
     fn pick_seed<V: MbrLeafGeometry<P, DIM>>(
         &self,
         mbr: &Rect<P, DIM>,
-        children: &Vec<V>,
+        children: &[V],
     ) -> (usize, usize);
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Quadratic;
 
-impl<P: FP, const DIM: usize, LG, T> PickSeed<P, DIM, LG, T> for Quadratic
+impl<P: Scalar, const DIM: usize, LG, T> PickSeed<P, DIM, LG, T> for Quadratic
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
     fn pick_seed<V: MbrLeafGeometry<P, DIM>>(
         &self,
         _mbr: &Rect<P, DIM>,
-        children: &Vec<V>,
+        children: &[V],
     ) -> (usize, usize) {
         let (_, k, l) = children
             .iter()
@@ -60,26 +66,27 @@ where
                 (mbr.area() - k_child.area() - l_child.area(), k, l)
             })
             // PS2
-            .max_by_key(|&(j, _, _)| j.try_into().ok().unwrap())
+            .max_by_key(|&(j, _, _)| OrderedFloat(j))
             .unwrap();
         (k, l)
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Linear;
 
-impl<P: FP, const DIM: usize, LG, T> PickSeed<P, DIM, LG, T> for Linear
+impl<P: Scalar, const DIM: usize, LG, T> PickSeed<P, DIM, LG, T> for Linear
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
     fn pick_seed<V: MbrLeafGeometry<P, DIM>>(
         &self,
         mbr: &Rect<P, DIM>,
-        children: &Vec<V>,
+        children: &[V],
     ) -> (usize, usize) {
-        let mut widths = [P::default(); DIM];
+        let mut widths = [P::Measure::default(); DIM];
         izip!(widths.iter_mut(), mbr.deref()).for_each(|(width, &(min, max))| {
-            *width = max - min;
+            *width = max.to_measure() - min.to_measure();
             if *width <= Zero::zero() {
                 *width = One::one();
             }
@@ -109,9 +116,11 @@ where
 
         let (_, mut k, mut l) = izip!(widths.iter(), least_upper.iter(), greatest_lower.iter())
             // LPS2
-            .map(|(width, &(lmax, li), &(gmin, gi))| (((gmin - lmax) / *width), li, gi))
+            .map(|(width, &(lmax, li), &(gmin, gi))| {
+                ((gmin.to_measure() - lmax.to_measure()) / *width, li, gi)
+            })
             // LPS3
-            .max_by_key(|&(separation, _, _)| separation.try_into().ok().unwrap())
+            .max_by_key(|&(separation, _, _)| OrderedFloat(separation))
             .unwrap();
 
         if k > l {
@@ -121,14 +130,15 @@ where
     }
 }
 
-pub struct SeedSplit<P: FP, const DIM: usize, LG, T, PS> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeedSplit<P: Scalar, const DIM: usize, LG, T, PS> {
     pick_seed: PS,
     _p: PhantomData<P>,
     _lg: PhantomData<LG>,
     _t: PhantomData<T>,
 }
 
-impl<P: FP, const DIM: usize, LG, T, PS> SeedSplit<P, DIM, LG, T, PS>
+impl<P: Scalar, const DIM: usize, LG, T, PS> SeedSplit<P, DIM, LG, T, PS>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -151,7 +161,7 @@ where
     }
 }
 
-impl<P: FP, const DIM: usize, LG, T, PS> MbrNodeSplit<P, DIM> for SeedSplit<P, DIM, LG, T, PS>
+impl<P: Scalar, const DIM: usize, LG, T, PS> MbrNodeSplit<P, DIM> for SeedSplit<P, DIM, LG, T, PS>
 where
     LG: MbrLeafGeometry<P, DIM>,
     PS: PickSeed<P, DIM, LG, T>,
@@ -234,16 +244,24 @@ where
     }
 }
 
-pub struct RInsert<P: FP, const DIM: usize, LG, T, NS> {
-    preferred_min: usize,
-    max: usize,
-    splitter: NS,
-    _p: PhantomData<P>,
-    _lg: PhantomData<LG>,
-    _t: PhantomData<T>,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound(deserialize = "NS: Deserialize<'de>"),
+        try_from = "crate::serde_tree::RawRInsert<NS>"
+    )
+)]
+pub struct RInsert<P: Scalar, const DIM: usize, LG, T, NS> {
+    pub(crate) preferred_min: usize,
+    pub(crate) max: usize,
+    pub(crate) splitter: NS,
+    pub(crate) _p: PhantomData<P>,
+    pub(crate) _lg: PhantomData<LG>,
+    pub(crate) _t: PhantomData<T>,
 }
 
-impl<P: FP, const DIM: usize, LG, T, NS> RInsert<P, DIM, LG, T, NS>
+impl<P: Scalar, const DIM: usize, LG, T, NS> RInsert<P, DIM, LG, T, NS>
 where
     LG: MbrLeafGeometry<P, DIM>,
     NS: MbrNodeSplit<P, DIM>,
@@ -269,39 +287,45 @@ where
         &self,
         mbr: &Rect<P, DIM>,
         leaf: &MbrLeaf<P, DIM, LG, T>,
-    ) -> (NotNan<P>, NotNan<P>) {
+    ) -> (OrderedFloat<P::Measure>, OrderedFloat<P::Measure>) {
         let mut expanded = mbr.clone();
         leaf.expand_mbr_to_fit(&mut expanded);
         let mbr_area = mbr.area();
         let expanded_area = expanded.area();
         let area_cost = expanded_area - mbr_area;
-        (
-            area_cost.try_into().ok().unwrap(),
-            expanded_area.try_into().ok().unwrap(),
-        )
+        (OrderedFloat(area_cost), OrderedFloat(expanded_area))
     }
 
-    fn choose_subnode<'tree>(
+    /// The index of the child to insert the leaf into
+    fn choose_subnode(
         &self,
-        level: &'tree mut Vec<RTreeNode<P, DIM, LG, T>>,
+        level: &[RTreeNode<P, DIM, LG, T>],
         leaf: &MbrLeaf<P, DIM, LG, T>,
-    ) -> &'tree mut RTreeNode<P, DIM, LG, T> {
+    ) -> usize {
         assert!(!level.is_empty(), "Level should not be empty!");
         level
-            .iter_mut()
-            .min_by_key(|a| self.area_cost(a.mbr(), leaf))
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, a)| self.area_cost(a.mbr(), leaf))
             .unwrap()
+            .0
     }
 
     //OT1
-    fn handle_overflow(&self, level: &mut RTreeNode<P, DIM, LG, T>) -> InsertResult<P, DIM, LG, T> {
+    fn handle_overflow(
+        &self,
+        level: &mut RTreeNode<P, DIM, LG, T>,
+        path: &mut LeafPath,
+        follow: Follow,
+    ) -> InsertResult<P, DIM, LG, T> {
         match *level {
             RTreeNode::Leaves {
                 ref mut mbr,
                 ref mut children,
             } => {
-                let (split_mbr, split_children) =
-                    self.splitter.split(self.preferred_min, mbr, children);
+                let (split_mbr, split_children) = path.split(follow, children, |children| {
+                    self.splitter.split(self.preferred_min, mbr, children)
+                });
                 InsertResult::Split(RTreeNode::Leaves {
                     mbr: split_mbr,
                     children: split_children,
@@ -311,8 +335,9 @@ where
                 ref mut mbr,
                 ref mut children,
             } => {
-                let (split_mbr, split_children) =
-                    self.splitter.split(self.preferred_min, mbr, children);
+                let (split_mbr, split_children) = path.split(follow, children, |children| {
+                    self.splitter.split(self.preferred_min, mbr, children)
+                });
                 InsertResult::Split(RTreeNode::Level {
                     mbr: split_mbr,
                     children: split_children,
@@ -325,6 +350,8 @@ where
         &self,
         level: &mut RTreeNode<P, DIM, LG, T>,
         leaf: MbrLeaf<P, DIM, LG, T>,
+        path: &mut LeafPath,
+        follow: Follow,
     ) -> InsertResult<P, DIM, LG, T> {
         //I4
         leaf.geometry.expand_mbr_to_fit(level.mbr_mut());
@@ -333,6 +360,7 @@ where
             RTreeNode::Leaves {
                 ref mut children, ..
             } => {
+                path.pushed(follow, children.len());
                 children.push(leaf);
             }
             //I1
@@ -340,35 +368,34 @@ where
                 ref mut children, ..
             } => {
                 //CS3
+                let index = self.choose_subnode(children, &leaf);
+                let child_follow = path.child(follow, index);
                 let insert_result =
-                    self.insert_into_level(self.choose_subnode(children, &leaf), leaf);
+                    self.insert_into_level(&mut children[index], leaf, path, child_follow);
                 //I3
                 if let InsertResult::Split(child) = insert_result {
+                    path.pushed_split(follow, children.len());
                     children.push(child);
                 }
             }
         }
         //I2 & I3
         if level.len() > self.max {
-            return self.handle_overflow(level);
+            return self.handle_overflow(level, path, follow);
         }
         InsertResult::Ok
     }
-}
 
-impl<P: FP, const DIM: usize, LG, T, NS> IndexInsert<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>
-    for RInsert<P, DIM, LG, T, NS>
-where
-    NS: MbrNodeSplit<P, DIM>,
-    LG: MbrLeafGeometry<P, DIM>,
-{
-    fn insert_into_root(
+    fn insert_following(
         &self,
         mut root: RTreeNode<P, DIM, LG, T>,
         leaf: MbrLeaf<P, DIM, LG, T>,
+        path: &mut LeafPath,
+        follow: Follow,
     ) -> RTreeNode<P, DIM, LG, T> {
-        let result = self.insert_into_level(&mut root, leaf);
+        let result = self.insert_into_level(&mut root, leaf, path, follow);
         if let InsertResult::Split(split) = result {
+            path.split_root(follow);
             let mut mbr = root.mbr().clone();
             split.expand_mbr_to_fit(&mut mbr);
             let children = vec![root, split];
@@ -376,11 +403,40 @@ where
         }
         root
     }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NS> IndexInsert<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>
+    for RInsert<P, DIM, LG, T, NS>
+where
+    NS: MbrNodeSplit<P, DIM>,
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn insert_into_root(
+        &self,
+        root: RTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+    ) -> RTreeNode<P, DIM, LG, T> {
+        self.insert_following(root, leaf, &mut LeafPath::default(), Follow::Nothing)
+    }
+
+    fn insert_into_root_at(
+        &self,
+        root: RTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+    ) -> (RTreeNode<P, DIM, LG, T>, Vec<usize>) {
+        let mut path = LeafPath::default();
+        let root = self.insert_following(root, leaf, &mut path, Follow::Inserted(0));
+        (root, path.path)
+    }
 
     fn preferred_min(&self) -> usize {
         self.preferred_min
     }
 
+    fn max(&self) -> usize {
+        self.max
+    }
+
     fn new_leaves(&self) -> RTreeNode<P, DIM, LG, T> {
         RTreeNode::new_leaves()
     }
@@ -390,14 +446,18 @@ where
     }
 }
 
-pub struct RRemove<P: FP, const DIM: usize, LG, T> {
-    min: usize,
-    _p: PhantomData<P>,
-    _lg: PhantomData<LG>,
-    _t: PhantomData<T>,
+/// Removes leaves and condenses the tree for any node layout.
+/// Levels are refit after losing children, and told so through `MbrNodeChildren::record_removal`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "crate::serde_tree::RawRRemove"))]
+pub struct RRemove<P: Scalar, const DIM: usize, LG, T> {
+    pub(crate) min: usize,
+    pub(crate) _p: PhantomData<P>,
+    pub(crate) _lg: PhantomData<LG>,
+    pub(crate) _t: PhantomData<T>,
 }
 
-impl<P: FP, const DIM: usize, LG, T> RRemove<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> RRemove<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -411,108 +471,96 @@ where
         }
     }
 
-    /// Removes matching leaves from a leaf level. Return true if the level should be retained
-    fn remove_matching_leaves<
-        Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
-        F: FnMut(&T) -> bool,
-    >(
-        &self,
-        query: &Q,
-        mbr: &mut Rect<P, DIM>,
-        children: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
-        removed: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
-        to_reinsert: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
-        f: &mut F,
-        at_root: bool,
-    ) -> bool {
-        let orig_len = children.len();
-        // D2
-        children.retain_and_append(removed, |leaf| !query.accept_leaf(leaf) || f(&leaf.item));
-        let children_removed = orig_len != children.len();
-        // CT3
-        if children.len() < self.min && !at_root {
-            to_reinsert.append(children);
-            return false;
-        }
-        // CT4
-        if children_removed {
-            *mbr = Rect::max_inverted();
-            for child in children {
-                child.expand_mbr_to_fit(mbr);
+    /// Recompute the mbr of a level that lost children
+    fn refit<NODE>(&self, level: &mut NODE, max: usize)
+    where
+        NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    {
+        let mut mbr = Rect::max_inverted();
+        match level.children() {
+            NodeChildren::Levels(children) => {
+                for child in children {
+                    child.expand_mbr_to_fit(&mut mbr);
+                }
+            }
+            NodeChildren::Leaves(children) => {
+                for child in children {
+                    child.expand_mbr_to_fit(&mut mbr);
+                }
             }
         }
-        true
+        *level.mbr_mut() = mbr;
+        level.record_removal(max);
     }
 
     /// Consume all child leaves and queue them for reinsert
-    fn consume_leaves_for_reinsert(
+    fn consume_leaves_for_reinsert<NODE>(
         &self,
-        nodes: &mut Vec<RTreeNode<P, DIM, LG, T>>,
+        nodes: &mut Vec<NODE>,
         to_reinsert: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
-    ) {
+    ) where
+        NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    {
         for node in nodes {
-            match *node {
-                RTreeNode::Leaves {
-                    ref mut children, ..
-                } => to_reinsert.append(&mut mem::replace(children, Vec::with_capacity(0))),
-                RTreeNode::Level {
-                    ref mut children, ..
-                } => self.consume_leaves_for_reinsert(children, to_reinsert),
+            match node.children_vec_mut() {
+                NodeChildren::Leaves(children) => {
+                    to_reinsert.append(&mut mem::replace(children, Vec::with_capacity(0)))
+                }
+                NodeChildren::Levels(children) => {
+                    self.consume_leaves_for_reinsert(children, to_reinsert)
+                }
             }
         }
     }
 
-    /// Recursively remove leaves from a level. Return true if the level should be retianed
-    fn remove_leaves_from_level<
-        Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
-        F: FnMut(&T) -> bool,
-    >(
+    /// Recursively remove leaves from a level. Return true if the level should be retained
+    #[allow(clippy::too_many_arguments)]
+    fn remove_leaves_from_level<NODE, Q, F>(
         &self,
         query: &Q,
-        level: &mut RTreeNode<P, DIM, LG, T>,
+        level: &mut NODE,
+        max: usize,
         removed: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
         to_reinsert: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
         f: &mut F,
         at_root: bool,
-    ) -> bool {
+    ) -> bool
+    where
+        NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+        Q: MbrQuery<P, DIM, LG, T, NODE>,
+        F: FnMut(&T) -> bool,
+    {
         // FL1
         if !query.accept_level(level) {
             return true;
         }
-        match *level {
+        let children_removed = match level.children_vec_mut() {
             // FL2
-            RTreeNode::Leaves {
-                ref mut mbr,
-                ref mut children,
-                ..
-            } => {
-                return self.remove_matching_leaves(
-                    query,
-                    mbr,
-                    children,
-                    removed,
-                    to_reinsert,
-                    f,
-                    at_root,
-                )
+            NodeChildren::Leaves(children) => {
+                let orig_len = children.len();
+                // D2
+                children
+                    .retain_and_append(removed, |leaf| !query.accept_leaf(leaf) || f(&leaf.item));
+                // CT3
+                if children.len() < self.min && !at_root {
+                    to_reinsert.append(children);
+                    return false;
+                }
+                orig_len != children.len()
             }
-            RTreeNode::Level {
-                ref mut mbr,
-                ref mut children,
-                ..
-            } => {
+            NodeChildren::Levels(children) => {
                 let orig_len = children.len();
                 children.retain_mut(|child| {
                     self.remove_leaves_from_level(
                         query,
                         child,
+                        max,
                         removed,
                         to_reinsert,
                         f,
                         NOT_AT_ROOT,
                     )
                 });
-                let children_removed = orig_len != children.len();
                 // CT5
                 // This technically goes against the original R-Tree paper,
                 // but it's a bit simpler given the height-naive data structures and algorithms I've written
@@ -520,58 +568,131 @@ where
                     self.consume_leaves_for_reinsert(children, to_reinsert);
                     return false;
                 }
-                if children_removed {
-                    *mbr = Rect::max_inverted();
-                    for child in &*children {
-                        child.expand_mbr_to_fit(mbr);
-                    }
+                orig_len != children.len()
+            }
+        };
+        // CT4
+        if children_removed {
+            self.refit(level, max);
+        }
+        true
+    }
+
+    /// Remove the leaf at the path from a level, condensing it as `remove_leaves_from_level` would.
+    /// Return true if the level should be retained
+    fn remove_path_from_level<NODE>(
+        &self,
+        path: &[usize],
+        level: &mut NODE,
+        max: usize,
+        removed: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
+        to_reinsert: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
+        at_root: bool,
+    ) -> bool
+    where
+        NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    {
+        match level.children_vec_mut() {
+            NodeChildren::Leaves(children) => {
+                removed.push(children.remove(path[0]));
+                // CT3
+                if children.len() < self.min && !at_root {
+                    to_reinsert.append(children);
+                    return false;
+                }
+            }
+            NodeChildren::Levels(children) => {
+                let retain = self.remove_path_from_level(
+                    &path[1..],
+                    &mut children[path[0]],
+                    max,
+                    removed,
+                    to_reinsert,
+                    NOT_AT_ROOT,
+                );
+                if !retain {
+                    children.remove(path[0]);
+                }
+                // CT5
+                if children.len() < self.min && !at_root {
+                    self.consume_leaves_for_reinsert(children, to_reinsert);
+                    return false;
                 }
             }
         }
+        // CT4
+        self.refit(level, max);
         true
     }
 }
 
-impl<P: FP, const DIM: usize, LG, T, I> IndexRemove<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>, I>
+impl<P: Scalar, const DIM: usize, LG, T, NODE, I> IndexRemove<P, DIM, LG, T, NODE, I>
     for RRemove<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
-    I: IndexInsert<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
 {
-    fn remove_from_root<
-        Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
-        F: FnMut(&T) -> bool,
-    >(
+    fn remove_from_root<Q: MbrQuery<P, DIM, LG, T, NODE>, F: FnMut(&T) -> bool>(
         &self,
-        mut root: RTreeNode<P, DIM, LG, T>,
+        mut root: NODE,
         insert_index: &I,
         query: Q,
         mut f: F,
-    ) -> RemoveReturn<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>> {
+    ) -> RemoveReturn<P, DIM, LG, T, NODE> {
         if root.is_empty() {
-            (root, Vec::with_capacity(0))
-        } else {
-            // CT1
-            let mut to_reinsert = Vec::new();
-            let mut removed = Vec::new();
-            // D1 && CT2
-            self.remove_leaves_from_level(
-                &query,
-                &mut root,
-                &mut removed,
-                &mut to_reinsert,
-                &mut f,
-                AT_ROOT,
-            );
-            // Insert algorithms require an empty root to be for leaves
-            if root.is_empty() && root.has_levels() {
-                root = insert_index.new_leaves();
-            }
-            // CT6
-            for leaf in to_reinsert {
-                root = insert_index.insert_into_root(root, leaf);
-            }
-            (root, removed)
+            return (root, Vec::with_capacity(0));
+        }
+        // CT1
+        let mut to_reinsert = Vec::new();
+        let mut removed = Vec::new();
+        // D1 && CT2
+        self.remove_leaves_from_level(
+            &query,
+            &mut root,
+            insert_index.max(),
+            &mut removed,
+            &mut to_reinsert,
+            &mut f,
+            AT_ROOT,
+        );
+        // Insert algorithms require an empty root to be for leaves
+        if root.is_empty() && root.has_levels() {
+            root = insert_index.new_leaves();
+        }
+        // CT6
+        for leaf in to_reinsert {
+            root = insert_index.insert_into_root(root, leaf);
+        }
+        (root, removed)
+    }
+
+    fn remove_at_path(
+        &self,
+        mut root: NODE,
+        insert_index: &I,
+        path: &[usize],
+    ) -> (NODE, MbrLeaf<P, DIM, LG, T>) {
+        let mut to_reinsert = Vec::new();
+        let mut removed = Vec::with_capacity(1);
+        self.remove_path_from_level(
+            path,
+            &mut root,
+            insert_index.max(),
+            &mut removed,
+            &mut to_reinsert,
+            AT_ROOT,
+        );
+        // Insert algorithms require an empty root to be for leaves
+        if root.is_empty() && root.has_levels() {
+            root = insert_index.new_leaves();
+        }
+        for leaf in to_reinsert {
+            root = insert_index.insert_into_root(root, leaf);
         }
+        let leaf = removed
+            .pop()
+            .unwrap_or_else(|| unreachable!("the path leads to a leaf"));
+        (root, leaf)
     }
 }
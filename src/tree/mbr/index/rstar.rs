@@ -7,14 +7,18 @@
 
 use crate::geometry::Rect;
 use num::{Float, Zero};
-use ordered_float::NotNan;
+use ordered_float::OrderedFloat;
 use std::cmp;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::Range;
-use crate::tree::mbr::index::{IndexInsert, AT_ROOT, DONT_FORCE_SPLIT, D_MAX, FORCE_SPLIT, NOT_AT_ROOT};
+use crate::tree::mbr::index::{
+    Follow, IndexInsert, LeafPath, AT_ROOT, DONT_FORCE_SPLIT, D_MAX, FORCE_SPLIT, NOT_AT_ROOT,
+};
 use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode, RTreeNode};
-use crate::FP;
+use crate::Scalar;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 const D_REINSERT_P: f32 = 0.30f32;
 const D_SPLIT_P: f32 = 0.40f32;
@@ -25,45 +29,47 @@ pub trait Margin<P> {
     fn margin(&self) -> P;
 }
 
-impl<P: FP, const DIM: usize> Margin<P> for Rect<P, DIM> {
-    fn margin(&self) -> P {
-        self.edges
-            .iter()
-            .fold(Zero::zero(), |margin, &(x, y)| margin + y - x)
+impl<P: Scalar, const DIM: usize> Margin<P::Measure> for Rect<P, DIM> {
+    fn margin(&self) -> P::Measure {
+        self.edges.iter().fold(Zero::zero(), |margin, &(x, y)| {
+            margin + y.to_measure() - x.to_measure()
+        })
     }
 }
 
+/// Insertion costs are compared as ordered measures
+type Cost<P> = OrderedFloat<<P as Scalar>::Measure>;
+
 #[derive(Debug)]
 #[must_use]
-enum InsertResult<P: FP, const DIM: usize, LG, T> {
+enum InsertResult<P: Scalar, const DIM: usize, LG, T> {
     Ok,
     Reinsert(Vec<MbrLeaf<P, DIM, LG, T>>),
     Split(RTreeNode<P, DIM, LG, T>),
 }
 
-impl<P: FP, const DIM: usize, LG, T> InsertResult<P, DIM, LG, T> {
+impl<P: Scalar, const DIM: usize, LG, T> InsertResult<P, DIM, LG, T> {
     fn is_reinsert(&self) -> bool {
-        match *self {
-            InsertResult::Reinsert(_) => true,
-            _ => false,
-        }
+        matches!(*self, InsertResult::Reinsert(_))
     }
 }
 
 #[derive(Debug)]
-pub struct RStarInsert<P: FP, const DIM: usize, LG, T> {
-    max: usize,
-    preferred_min: usize,
-    reinsert_m: usize,
-    choose_subtree_p: usize,
-    min_k: usize,
-    max_k: usize,
-    _p: PhantomData<P>,
-    _lg: PhantomData<LG>,
-    _t: PhantomData<T>,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "crate::serde_tree::RawRStarInsert"))]
+pub struct RStarInsert<P: Scalar, const DIM: usize, LG, T> {
+    pub(crate) max: usize,
+    pub(crate) preferred_min: usize,
+    pub(crate) reinsert_m: usize,
+    pub(crate) choose_subtree_p: usize,
+    pub(crate) min_k: usize,
+    pub(crate) max_k: usize,
+    pub(crate) _p: PhantomData<P>,
+    pub(crate) _lg: PhantomData<LG>,
+    pub(crate) _t: PhantomData<T>,
 }
 
-impl<P: FP, const DIM: usize, LG, T> RStarInsert<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> RStarInsert<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -114,72 +120,72 @@ where
         }
     }
 
-    fn area_cost(
-        &self,
-        mbr: &Rect<P, DIM>,
-        leaf: &MbrLeaf<P, DIM, LG, T>,
-    ) -> (NotNan<P>, NotNan<P>) {
+    fn area_cost(&self, mbr: &Rect<P, DIM>, leaf: &MbrLeaf<P, DIM, LG, T>) -> (Cost<P>, Cost<P>) {
         let mut expanded = mbr.clone();
         leaf.expand_mbr_to_fit(&mut expanded);
         let mbr_area = mbr.area();
         let area_cost = expanded.area() - mbr_area;
-        (
-            area_cost.try_into().ok().unwrap(),
-            mbr_area.try_into().ok().unwrap(),
-        )
+        (OrderedFloat(area_cost), OrderedFloat(mbr_area))
     }
 
-    fn overlap_cost(&self, mbr: &Rect<P, DIM>, leaf: &MbrLeaf<P, DIM, LG, T>) -> NotNan<P> {
+    fn overlap_cost(&self, mbr: &Rect<P, DIM>, leaf: &MbrLeaf<P, DIM, LG, T>) -> Cost<P> {
         let overlap = leaf.area_overlapped_with_mbr(mbr);
         let overlap_cost = leaf.area() - overlap;
-        overlap_cost.try_into().ok().unwrap()
+        OrderedFloat(overlap_cost)
     }
 
     fn overlap_area_cost(
         &self,
         mbr: &Rect<P, DIM>,
         leaf: &MbrLeaf<P, DIM, LG, T>,
-    ) -> (NotNan<P>, NotNan<P>, NotNan<P>) {
+    ) -> (Cost<P>, Cost<P>, Cost<P>) {
         let (area_cost, mbr_area) = self.area_cost(mbr, leaf);
         let overlap_cost = self.overlap_cost(mbr, leaf);
         (overlap_cost, area_cost, mbr_area)
     }
 
+    /// The index of the child to insert the leaf into
     // CS2 + optimizations
-    fn choose_subnode<'tree>(
+    fn choose_subnode(
         &self,
-        level: &'tree mut Vec<RTreeNode<P, DIM, LG, T>>,
+        level: &[RTreeNode<P, DIM, LG, T>],
         leaf: &MbrLeaf<P, DIM, LG, T>,
-    ) -> &'tree mut RTreeNode<P, DIM, LG, T> {
+    ) -> usize {
         assert!(!level.is_empty(), "Level should not be empty!");
         if level.first().unwrap().has_leaves() {
             if level.len() > self.choose_subtree_p {
-                level.sort_by_key(|a| self.area_cost(a.mbr(), leaf));
-                let (left, _) = level.split_at_mut(self.choose_subtree_p);
-                return left
-                    .iter_mut()
-                    .min_by_key(|a| self.overlap_cost(a.mbr(), leaf))
+                // the candidates are ranked by index, so the level keeps its order
+                let mut candidates: Vec<usize> = (0..level.len()).collect();
+                candidates.sort_by_key(|&index| self.area_cost(level[index].mbr(), leaf));
+                candidates.truncate(self.choose_subtree_p);
+                return candidates
+                    .into_iter()
+                    .min_by_key(|&index| self.overlap_cost(level[index].mbr(), leaf))
                     .unwrap();
             } else {
                 return level
-                    .iter_mut()
-                    .min_by_key(|a| self.overlap_area_cost(a.mbr(), leaf))
-                    .unwrap();
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|&(_, a)| self.overlap_area_cost(a.mbr(), leaf))
+                    .unwrap()
+                    .0;
             }
         }
         level
-            .iter_mut()
-            .min_by_key(|a| self.area_cost(a.mbr(), leaf))
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, a)| self.area_cost(a.mbr(), leaf))
             .unwrap()
+            .0
     }
 
-    fn split_for_reinsert(
+    fn split_for_reinsert<V: MbrLeafGeometry<P, DIM>>(
         &self,
         mbr: &mut Rect<P, DIM>,
-        children: &mut Vec<MbrLeaf<P, DIM, LG, T>>,
-    ) -> Vec<MbrLeaf<P, DIM, LG, T>> {
+        children: &mut Vec<V>,
+    ) -> Vec<V> {
         // RI1 & RI2
-        children.sort_by_key(|a| a.distance_from_mbr_center(mbr).try_into().ok().unwrap());
+        children.sort_by_key(|a| OrderedFloat(a.distance_sq_from_mbr_center(mbr)));
         //RI3
         let split = children.split_off(self.reinsert_m);
         *mbr = Rect::max_inverted();
@@ -189,12 +195,15 @@ where
         split
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn insert_into_level(
         &self,
         level: &mut RTreeNode<P, DIM, LG, T>,
         leaf: MbrLeaf<P, DIM, LG, T>,
         at_root: bool,
         force_split: bool,
+        path: &mut LeafPath,
+        follow: Follow,
     ) -> InsertResult<P, DIM, LG, T> {
         //I4
         leaf.geometry.expand_mbr_to_fit(level.mbr_mut());
@@ -203,6 +212,7 @@ where
             RTreeNode::Leaves {
                 ref mut children, ..
             } => {
+                path.pushed(follow, children.len());
                 children.push(leaf);
             }
             //I1
@@ -211,14 +221,19 @@ where
                 ref mut children,
             } => {
                 //CS3
+                let index = self.choose_subnode(children, &leaf);
+                let child_follow = path.child(follow, index);
                 let insert_result = self.insert_into_level(
-                    self.choose_subnode(children, &leaf),
+                    &mut children[index],
                     leaf,
                     NOT_AT_ROOT,
                     force_split,
+                    path,
+                    child_follow,
                 );
                 //I3
                 if let InsertResult::Split(child) = insert_result {
+                    path.pushed_split(follow, children.len());
                     children.push(child);
                 } else {
                     //I4
@@ -234,7 +249,7 @@ where
         }
         //I2 & I3
         if level.len() > self.max {
-            return self.handle_overflow(level, at_root, force_split);
+            return self.handle_overflow(level, at_root, force_split, path, follow);
         }
         InsertResult::Ok
     }
@@ -243,19 +258,19 @@ where
     fn best_split_position_for_axis<V: MbrLeafGeometry<P, DIM>>(
         &self,
         axis: usize,
-        children: &mut Vec<V>,
-    ) -> (P, (usize, usize, usize)) {
-        let mut margin: P = Zero::zero();
-        let mut d_area: P = Float::max_value();
-        let mut d_overlap: P = Float::max_value();
+        children: &mut [V],
+    ) -> (P::Measure, (usize, usize, usize)) {
+        let mut margin: P::Measure = Zero::zero();
+        let mut d_area: P::Measure = Float::max_value();
+        let mut d_overlap: P::Measure = Float::max_value();
         let mut d_edge: usize = 0;
         let mut d_index: usize = 0;
 
         for edge in 0..2 {
             if edge == 0 {
-                children.sort_by_key(|child| child.min_for_axis(axis).try_into().ok().unwrap());
+                children.sort_by_key(|child| OrderedFloat(child.min_for_axis(axis).to_measure()));
             } else {
-                children.sort_by_key(|child| child.max_for_axis(axis).try_into().ok().unwrap());
+                children.sort_by_key(|child| OrderedFloat(child.max_for_axis(axis).to_measure()));
             }
 
             for k in self.min_k..self.max_k {
@@ -299,14 +314,14 @@ where
             // CSA1
             .map(|axis| self.best_split_position_for_axis(axis, children))
             // CSA2
-            .min_by_key(|&(margin, _)| margin.try_into().ok().unwrap())
+            .min_by_key(|&(margin, _)| OrderedFloat(margin))
             .unwrap()
             .1;
 
         if s_edge == 0 {
-            children.sort_by_key(|child| child.min_for_axis(s_axis).try_into().ok().unwrap());
+            children.sort_by_key(|child| OrderedFloat(child.min_for_axis(s_axis).to_measure()));
         } else {
-            children.sort_by_key(|child| child.max_for_axis(s_axis).try_into().ok().unwrap());
+            children.sort_by_key(|child| OrderedFloat(child.max_for_axis(s_axis).to_measure()));
         }
         // S3
         let split_children = children.split_off(s_index);
@@ -327,13 +342,21 @@ where
         level: &mut RTreeNode<P, DIM, LG, T>,
         at_root: bool,
         force_split: bool,
+        path: &mut LeafPath,
+        follow: Follow,
     ) -> InsertResult<P, DIM, LG, T> {
         if !at_root && !force_split {
             match *level {
                 RTreeNode::Leaves {
                     ref mut mbr,
                     ref mut children,
-                } => return InsertResult::Reinsert(self.split_for_reinsert(mbr, children)),
+                } => {
+                    // a followed leaf picked for reinsert is left marked as split off, at its index among the picked leaves
+                    let ((), reinsert) = path.split(follow, children, |children| {
+                        ((), self.split_for_reinsert(mbr, children))
+                    });
+                    return InsertResult::Reinsert(reinsert);
+                }
                 _ => unreachable!(),
             }
         }
@@ -342,7 +365,8 @@ where
                 ref mut mbr,
                 ref mut children,
             } => {
-                let (split_mbr, split_children) = self.split(mbr, children);
+                let (split_mbr, split_children) =
+                    path.split(follow, children, |children| self.split(mbr, children));
                 InsertResult::Split(RTreeNode::Leaves {
                     mbr: split_mbr,
                     children: split_children,
@@ -352,7 +376,8 @@ where
                 ref mut mbr,
                 ref mut children,
             } => {
-                let (split_mbr, split_children) = self.split(mbr, children);
+                let (split_mbr, split_children) =
+                    path.split(follow, children, |children| self.split(mbr, children));
                 InsertResult::Split(RTreeNode::Level {
                     mbr: split_mbr,
                     children: split_children,
@@ -369,30 +394,50 @@ where
         let mut mbr = root.mbr().clone();
         split.expand_mbr_to_fit(&mut mbr);
         RTreeNode::Level {
-            mbr: mbr,
+            mbr,
             children: vec![root, split],
         }
     }
-}
 
-impl<P: FP, const DIM: usize, LG, T> IndexInsert<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>
-    for RStarInsert<P, DIM, LG, T>
-where
-    LG: MbrLeafGeometry<P, DIM>,
-{
-    fn insert_into_root(
+    fn insert_following(
         &self,
         mut root: RTreeNode<P, DIM, LG, T>,
         leaf: MbrLeaf<P, DIM, LG, T>,
+        path: &mut LeafPath,
+        follow: Follow,
     ) -> RTreeNode<P, DIM, LG, T> {
-        let insert_results = self.insert_into_level(&mut root, leaf, FORCE_SPLIT, DONT_FORCE_SPLIT);
+        let insert_results =
+            self.insert_into_level(&mut root, leaf, FORCE_SPLIT, DONT_FORCE_SPLIT, path, follow);
         match insert_results {
-            InsertResult::Split(child) => self.handle_split_root(root, child),
+            InsertResult::Split(child) => {
+                path.split_root(follow);
+                self.handle_split_root(root, child)
+            }
             // RI4
             InsertResult::Reinsert(leaves) => {
-                for leaf in leaves {
-                    match self.insert_into_level(&mut root, leaf, AT_ROOT, FORCE_SPLIT) {
+                // the followed leaf is followed again from the root if it was picked for reinsert
+                let picked = if path.take_split() {
+                    path.path.pop()
+                } else {
+                    None
+                };
+                for (index, leaf) in leaves.into_iter().enumerate() {
+                    let follow = match (follow, picked) {
+                        (Follow::Nothing, _) => Follow::Nothing,
+                        (_, Some(picked)) if index < picked => Follow::Nothing,
+                        (_, Some(picked)) if index == picked => Follow::Inserted(0),
+                        _ => Follow::Under(0),
+                    };
+                    match self.insert_into_level(
+                        &mut root,
+                        leaf,
+                        AT_ROOT,
+                        FORCE_SPLIT,
+                        path,
+                        follow,
+                    ) {
                         InsertResult::Split(child) => {
+                            path.split_root(follow);
                             root = self.handle_split_root(root, child);
                         }
                         InsertResult::Reinsert(_) => unreachable!(),
@@ -404,11 +449,48 @@ where
             _ => root,
         }
     }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> Default for RStarInsert<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn default() -> RStarInsert<P, DIM, LG, T> {
+        RStarInsert::new()
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> IndexInsert<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>
+    for RStarInsert<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn insert_into_root(
+        &self,
+        root: RTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+    ) -> RTreeNode<P, DIM, LG, T> {
+        self.insert_following(root, leaf, &mut LeafPath::default(), Follow::Nothing)
+    }
+
+    fn insert_into_root_at(
+        &self,
+        root: RTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+    ) -> (RTreeNode<P, DIM, LG, T>, Vec<usize>) {
+        let mut path = LeafPath::default();
+        let root = self.insert_following(root, leaf, &mut path, Follow::Inserted(0));
+        (root, path.path)
+    }
 
     fn preferred_min(&self) -> usize {
         self.preferred_min
     }
 
+    fn max(&self) -> usize {
+        self.max
+    }
+
     fn new_leaves(&self) -> RTreeNode<P, DIM, LG, T> {
         RTreeNode::new_leaves()
     }
@@ -429,8 +511,12 @@ mod tests {
         let g_zero = [0.0f64, 0.0f64, 0.0f64];
 
         // contained
-        let zero_one = Rect::from_corners(g_zero.clone(), g_one.clone());
+        let zero_one = Rect::from_corners(g_zero, g_one);
         // margin
         assert_relative_eq!(3.0f64, zero_one.margin());
+
+        // integer margins are measured in f64, so they can't overflow
+        let everything = Rect::from_corners([i32::MIN; 3], [i32::MAX; 3]);
+        assert_relative_eq!(3.0f64 * u32::MAX as f64, everything.margin());
     }
 }
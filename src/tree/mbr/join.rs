@@ -0,0 +1,418 @@
+// Copyright 2016 spatial-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Spatial joins between two maps, or a map and itself
+//!
+//! Algorithm described by Brinkhoff, T.; Kriegel, H. P.; Seeger, B. (1993). "Efficient Processing of Spatial Joins Using R-trees"
+
+use std::ptr;
+
+use num::{Float, Zero};
+use ordered_float::OrderedFloat;
+
+use crate::geometry::{Point, Rect};
+use crate::tree::mbr::index::{IndexInsert, IndexRemove};
+use crate::tree::mbr::{
+    MbrLeaf, MbrLeafGeometry, MbrMap, MbrNode, MbrNodeChildren, NodeChildren, RTreeNode,
+};
+use crate::FP;
+
+impl<P: FP, const DIM: usize, LG, I, R, T, NODE> MbrMap<NODE, I, R>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM>,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    /// Iter every pair of entries from this map and the other whose geometries overlap, in no particular order.
+    ///
+    /// Pairs are found by traversing both trees at once, only descending into pairs of levels whose mbrs intersect.
+    /// Geometries are compared exactly by their `parts`, as every built-in geometry describes them.
+    /// A custom geometry that keeps the default parts is compared by its mbr, so it's reported with anything
+    /// touching its mbr
+    pub fn join<'tree, LG2, T2, NODE2, I2, R2>(
+        &'tree self,
+        other: &'tree MbrMap<NODE2, I2, R2>,
+    ) -> JoinIter<'tree, P, DIM, LG, T, NODE, LG2, T2, NODE2>
+    where
+        P: 'tree,
+        NODE2: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG2, T2>>,
+        LG2: MbrLeafGeometry<P, DIM> + 'tree,
+        I2: IndexInsert<P, DIM, LG2, T2, NODE2>,
+        R2: IndexRemove<P, DIM, LG2, T2, NODE2, I2>,
+    {
+        JoinIter::new(&self.root, &other.root)
+    }
+
+    /// Iter every unordered pair of distinct entries whose geometries overlap, each pair once and in no particular order.
+    /// Geometries touch as in `join`
+    pub fn overlapping_pairs(&self) -> PairsIter<'_, P, DIM, LG, T, NODE> {
+        PairsIter::new(&self.root, Zero::zero())
+    }
+
+    /// Iter every unordered pair of distinct entries whose geometries are within `epsilon` of each other.
+    /// Geometries are measured by their `parts` as in `join`, so custom geometries that keep the default parts
+    /// are measured by their mbr
+    pub fn overlapping_pairs_within(&self, epsilon: P) -> PairsIter<'_, P, DIM, LG, T, NODE> {
+        PairsIter::new(&self.root, epsilon)
+    }
+}
+
+/// Iterate through every overlapping pair of entries from two maps
+#[allow(clippy::type_complexity)]
+pub struct JoinIter<
+    'tree,
+    P: FP,
+    const DIM: usize,
+    LG,
+    T,
+    NODE = RTreeNode<P, DIM, LG, T>,
+    LG2 = LG,
+    T2 = T,
+    NODE2 = RTreeNode<P, DIM, LG2, T2>,
+> where
+    P: 'tree,
+    LG: 'tree,
+    T: 'tree,
+    LG2: 'tree,
+    T2: 'tree,
+{
+    stack: Vec<(&'tree NODE, &'tree NODE2)>,
+    pending: Vec<(
+        &'tree MbrLeaf<P, DIM, LG, T>,
+        &'tree MbrLeaf<P, DIM, LG2, T2>,
+    )>,
+}
+
+impl<'tree, P: FP, const DIM: usize, LG, T, NODE, LG2, T2, NODE2>
+    JoinIter<'tree, P, DIM, LG, T, NODE, LG2, T2, NODE2>
+where
+    P: 'tree,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG2: MbrLeafGeometry<P, DIM> + 'tree,
+    T2: 'tree,
+    NODE2: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG2, T2>>,
+{
+    /// Constructor
+    fn new(
+        root: &'tree NODE,
+        other_root: &'tree NODE2,
+    ) -> JoinIter<'tree, P, DIM, LG, T, NODE, LG2, T2, NODE2> {
+        let mut stack = Vec::new();
+        if !root.is_empty()
+            && !other_root.is_empty()
+            && extents_within(root, other_root, Zero::zero())
+        {
+            stack.push((root, other_root));
+        }
+        JoinIter {
+            stack,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<'tree, P: FP, const DIM: usize, LG, T, NODE, LG2, T2, NODE2> Iterator
+    for JoinIter<'tree, P, DIM, LG, T, NODE, LG2, T2, NODE2>
+where
+    P: 'tree,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG2: MbrLeafGeometry<P, DIM> + 'tree,
+    T2: 'tree,
+    NODE2: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG2, T2>>,
+{
+    type Item = ((&'tree LG, &'tree T), (&'tree LG2, &'tree T2));
+
+    fn next(&mut self) -> Option<((&'tree LG, &'tree T), (&'tree LG2, &'tree T2))> {
+        loop {
+            if let Some((leaf, other_leaf)) = self.pending.pop() {
+                return Some((leaf.as_tuple(), other_leaf.as_tuple()));
+            }
+            let (level, other_level) = self.stack.pop()?;
+            expand_pair(
+                level,
+                other_level,
+                Zero::zero(),
+                &mut self.stack,
+                &mut self.pending,
+            );
+        }
+    }
+}
+
+/// Queue the child pairs of two levels within `epsilon` of each other.
+/// Only children within `epsilon` of both levels' mbrs are considered
+#[allow(clippy::type_complexity)]
+fn expand_pair<'tree, P: FP, const DIM: usize, LG, T, NODE, LG2, T2, NODE2>(
+    level: &'tree NODE,
+    other_level: &'tree NODE2,
+    epsilon: P,
+    stack: &mut Vec<(&'tree NODE, &'tree NODE2)>,
+    pending: &mut Vec<(
+        &'tree MbrLeaf<P, DIM, LG, T>,
+        &'tree MbrLeaf<P, DIM, LG2, T2>,
+    )>,
+) where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG2: MbrLeafGeometry<P, DIM>,
+    NODE2: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG2, T2>>,
+{
+    let window = window(level.mbr(), other_level.mbr(), epsilon);
+    match (level.children(), other_level.children()) {
+        (NodeChildren::Leaves(leaves), NodeChildren::Leaves(other_leaves)) => {
+            sweep(
+                leaves,
+                other_leaves,
+                &window,
+                epsilon,
+                |leaf, other_leaf| {
+                    if leaves_within(leaf, other_leaf, epsilon) {
+                        pending.push((leaf, other_leaf));
+                    }
+                },
+            );
+        }
+        (NodeChildren::Levels(children), NodeChildren::Levels(other_children)) => {
+            sweep(
+                children,
+                other_children,
+                &window,
+                epsilon,
+                |child, other_child| {
+                    stack.push((child, other_child));
+                },
+            );
+        }
+        // The trees may differ in height. Descend the taller side until both reach their leaves
+        (NodeChildren::Levels(children), NodeChildren::Leaves(_)) => {
+            for child in children {
+                if extents_within(child, &window, Zero::zero()) {
+                    stack.push((child, other_level));
+                }
+            }
+        }
+        (NodeChildren::Leaves(_), NodeChildren::Levels(other_children)) => {
+            for other_child in other_children {
+                if extents_within(other_child, &window, Zero::zero()) {
+                    stack.push((level, other_child));
+                }
+            }
+        }
+    }
+}
+
+/// Whether the extents of both are within `epsilon` along every axis, boundaries inclusive.
+/// Unlike `overlapped_by_mbr` this holds for degenerate mbrs
+fn extents_within<P: FP, const DIM: usize, A, B>(a: &A, b: &B, epsilon: P) -> bool
+where
+    A: MbrLeafGeometry<P, DIM> + ?Sized,
+    B: MbrLeafGeometry<P, DIM> + ?Sized,
+{
+    (0..DIM).all(|axis| {
+        a.min_for_axis(axis) - epsilon <= b.max_for_axis(axis)
+            && b.min_for_axis(axis) - epsilon <= a.max_for_axis(axis)
+    })
+}
+
+/// The region any pair of children within `epsilon` of each other must touch
+fn window<P: FP, const DIM: usize>(a: &Rect<P, DIM>, b: &Rect<P, DIM>, epsilon: P) -> Rect<P, DIM> {
+    let mut window = a.clone();
+    for (edge, &(lo, hi)) in window.edges.iter_mut().zip(b.edges.iter()) {
+        *edge = (
+            Float::max(edge.0, lo) - epsilon,
+            Float::min(edge.1, hi) + epsilon,
+        );
+    }
+    window
+}
+
+fn mbr_of<P: FP, const DIM: usize, G: MbrLeafGeometry<P, DIM>>(geometry: &G) -> Rect<P, DIM> {
+    let mut mbr = Rect::max_inverted();
+    geometry.expand_mbr_to_fit(&mut mbr);
+    mbr
+}
+
+/// Refine a candidate pair by measuring each geometry against the other's mbr, then their parts pairwise,
+/// boundaries inclusive. Polygons' parts are only their edges, so a point of either geometry within `epsilon`
+/// of the other also counts, as when a line string lies inside a polygon
+fn leaves_within<P: FP, const DIM: usize, A, B>(a: &A, b: &B, epsilon: P) -> bool
+where
+    A: MbrLeafGeometry<P, DIM>,
+    B: MbrLeafGeometry<P, DIM>,
+{
+    if a.min_distance_from_mbr(&mbr_of(b)) > epsilon
+        || b.min_distance_from_mbr(&mbr_of(a)) > epsilon
+    {
+        return false;
+    }
+    let (parts, other_parts) = (a.parts(), b.parts());
+    parts.iter().any(|part| {
+        other_parts
+            .iter()
+            .any(|other_part| part.distance(other_part) <= epsilon)
+    }) || parts
+        .iter()
+        .any(|part| b.distance_from_point(&Point::new(part.anchor())) <= epsilon)
+        || other_parts
+            .iter()
+            .any(|part| a.distance_from_point(&Point::new(part.anchor())) <= epsilon)
+}
+
+/// Report every pair with extents within `epsilon`, restricted to those touching the window.
+/// Both sides are sorted along the first axis and swept, so only pairs that are near along it are compared
+fn sweep<'a, P: FP, const DIM: usize, A, B, F>(
+    a: &'a [A],
+    b: &'a [B],
+    window: &Rect<P, DIM>,
+    epsilon: P,
+    mut f: F,
+) where
+    A: MbrLeafGeometry<P, DIM>,
+    B: MbrLeafGeometry<P, DIM>,
+    F: FnMut(&'a A, &'a B),
+{
+    let mut a: Vec<&A> = a
+        .iter()
+        .filter(|x| extents_within(*x, window, Zero::zero()))
+        .collect();
+    let mut b: Vec<&B> = b
+        .iter()
+        .filter(|x| extents_within(*x, window, Zero::zero()))
+        .collect();
+    a.sort_by_key(|x| OrderedFloat(x.min_for_axis(0)));
+    b.sort_by_key(|x| OrderedFloat(x.min_for_axis(0)));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].min_for_axis(0) <= b[j].min_for_axis(0) {
+            let max = a[i].max_for_axis(0) + epsilon;
+            for &other in b[j..].iter().take_while(|x| x.min_for_axis(0) <= max) {
+                if extents_within(a[i], other, epsilon) {
+                    f(a[i], other);
+                }
+            }
+            i += 1;
+        } else {
+            let max = b[j].max_for_axis(0) + epsilon;
+            for &other in a[i..].iter().take_while(|x| x.min_for_axis(0) <= max) {
+                if extents_within(other, b[j], epsilon) {
+                    f(other, b[j]);
+                }
+            }
+            j += 1;
+        }
+    }
+}
+
+/// Report every unordered pair of distinct items with extents within `epsilon`, once each
+fn self_sweep<'a, P: FP, const DIM: usize, A, F>(items: &'a [A], epsilon: P, mut f: F)
+where
+    A: MbrLeafGeometry<P, DIM>,
+    F: FnMut(&'a A, &'a A),
+{
+    let mut items: Vec<&A> = items.iter().collect();
+    items.sort_by_key(|x| OrderedFloat(x.min_for_axis(0)));
+    for (i, &item) in items.iter().enumerate() {
+        let max = item.max_for_axis(0) + epsilon;
+        for &other in items[i + 1..]
+            .iter()
+            .take_while(|x| x.min_for_axis(0) <= max)
+        {
+            if extents_within(item, other, epsilon) {
+                f(item, other);
+            }
+        }
+    }
+}
+
+/// Iterate through every unordered pair of nearby entries in a map
+#[allow(clippy::type_complexity)]
+pub struct PairsIter<'tree, P: FP, const DIM: usize, LG, T, NODE = RTreeNode<P, DIM, LG, T>>
+where
+    P: 'tree,
+    LG: 'tree,
+    T: 'tree,
+{
+    epsilon: P,
+    stack: Vec<(&'tree NODE, &'tree NODE)>,
+    pending: Vec<(&'tree MbrLeaf<P, DIM, LG, T>, &'tree MbrLeaf<P, DIM, LG, T>)>,
+}
+
+impl<'tree, P: FP, const DIM: usize, LG, T, NODE> PairsIter<'tree, P, DIM, LG, T, NODE>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    /// Constructor
+    fn new(root: &'tree NODE, epsilon: P) -> PairsIter<'tree, P, DIM, LG, T, NODE> {
+        let mut stack = Vec::new();
+        if !root.is_empty() {
+            stack.push((root, root));
+        }
+        PairsIter {
+            epsilon,
+            stack,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Pair a level's children with each other. Pairs of distinct levels are expanded as in a join
+    fn expand_self(&mut self, level: &'tree NODE) {
+        let epsilon = self.epsilon;
+        match level.children() {
+            NodeChildren::Leaves(leaves) => {
+                let pending = &mut self.pending;
+                self_sweep(leaves, epsilon, |leaf, other_leaf| {
+                    if leaves_within(leaf, other_leaf, epsilon) {
+                        pending.push((leaf, other_leaf));
+                    }
+                });
+            }
+            NodeChildren::Levels(children) => {
+                let stack = &mut self.stack;
+                for child in children {
+                    stack.push((child, child));
+                }
+                self_sweep(children, epsilon, |child, other_child| {
+                    stack.push((child, other_child));
+                });
+            }
+        }
+    }
+}
+
+impl<'tree, P: FP, const DIM: usize, LG, T, NODE> Iterator for PairsIter<'tree, P, DIM, LG, T, NODE>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    type Item = ((&'tree LG, &'tree T), (&'tree LG, &'tree T));
+
+    fn next(&mut self) -> Option<((&'tree LG, &'tree T), (&'tree LG, &'tree T))> {
+        loop {
+            if let Some((leaf, other_leaf)) = self.pending.pop() {
+                return Some((leaf.as_tuple(), other_leaf.as_tuple()));
+            }
+            let (level, other_level) = self.stack.pop()?;
+            if ptr::eq(level, other_level) {
+                self.expand_self(level);
+            } else {
+                expand_pair(
+                    level,
+                    other_level,
+                    self.epsilon,
+                    &mut self.stack,
+                    &mut self.pending,
+                );
+            }
+        }
+    }
+}
@@ -5,21 +5,24 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use crate::geometry::Rect;
+use crate::geometry::{Point, Ray, Rect};
 use std::fmt::Debug;
 use std::marker::PhantomData;
-use crate::tree::mbr::MbrLeafGeometry;
-use crate::FP;
+use crate::tree::mbr::{LeafPart, MbrLeafGeometry};
+use crate::Scalar;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// A tree leaf
 #[derive(Debug, Clone)]
-pub struct MbrLeaf<P: FP, const DIM: usize, LG, T> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MbrLeaf<P: Scalar, const DIM: usize, LG, T> {
     pub geometry: LG,
     pub item: T,
     _p: PhantomData<P>,
 }
 
-impl<P: FP, const DIM: usize, LG, T> MbrLeaf<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> MbrLeaf<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -46,7 +49,7 @@ where
     }
 }
 
-impl<P: FP, const DIM: usize, LG, T> MbrLeafGeometry<P, DIM> for MbrLeaf<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> MbrLeafGeometry<P, DIM> for MbrLeaf<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -54,7 +57,7 @@ where
         self.geometry.dim()
     }
 
-    fn area(&self) -> P {
+    fn area(&self) -> P::Measure {
         self.geometry.area()
     }
 
@@ -70,10 +73,26 @@ where
         self.geometry.expand_mbr_to_fit(edges)
     }
 
-    fn distance_from_mbr_center(&self, edges: &Rect<P, DIM>) -> P {
+    fn distance_from_mbr_center(&self, edges: &Rect<P, DIM>) -> P::Measure {
         self.geometry.distance_from_mbr_center(edges)
     }
 
+    fn distance_sq_from_mbr_center(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.geometry.distance_sq_from_mbr_center(edges)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        self.geometry.distance_from_point(point)
+    }
+
+    fn min_distance_from_mbr(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.geometry.min_distance_from_mbr(edges)
+    }
+
+    fn min_max_distance_from_mbr(&self, edges: &Rect<P, DIM>) -> P::Measure {
+        self.geometry.min_max_distance_from_mbr(edges)
+    }
+
     fn contained_by_mbr(&self, edges: &Rect<P, DIM>) -> bool {
         self.geometry.contained_by_mbr(edges)
     }
@@ -82,7 +101,23 @@ where
         self.geometry.overlapped_by_mbr(edges)
     }
 
-    fn area_overlapped_with_mbr(&self, edges: &Rect<P, DIM>) -> P {
+    fn area_overlapped_with_mbr(&self, edges: &Rect<P, DIM>) -> P::Measure {
         self.geometry.area_overlapped_with_mbr(edges)
     }
+
+    fn is_finite(&self) -> bool {
+        self.geometry.is_finite()
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        self.geometry.max_distance_from_point(point)
+    }
+
+    fn ray_entry(&self, ray: &Ray<P::Measure, DIM>) -> Option<P::Measure> {
+        self.geometry.ray_entry(ray)
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        self.geometry.parts()
+    }
 }
@@ -5,10 +5,17 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use crate::geometry::{LineSegment, Point, Rect, Shapes};
-use num::{pow, Float, FromPrimitive, One, Zero};
+use crate::geometry::{
+    Capsule, LineSegment, LineString, MultiPolygon, Point, Polygon, Ray, Rect, Shapes, Sphere,
+    Triangle,
+};
+use itertools::Itertools;
+use num::{pow, Bounded, Float, FromPrimitive, One, Zero};
+use ordered_float::OrderedFloat;
+use std::any::Any;
+use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
-use crate::FP;
+use crate::{Scalar, FP};
 
 /// The minimum functionality required to insert leaf geometry into `MbrMap`
 /// Until the rust compiler allows compile-time generic integers, we'll be using generic_array's `ArrayLength` to specify
@@ -16,12 +23,14 @@ use crate::FP;
 ///
 /// The parameter `mbr` represents a minimum bounding rectangle.
 /// An mbr whose corners are at (x1, y1), (x2, y2) will have the corresponding edges: (x1, x2), (y1, y2)
-pub trait MbrLeafGeometry<P: FP, const DIM: usize> {
+///
+/// Areas and distances are returned in the coordinate's `Scalar::Measure`, which is the coordinate type itself for floats
+pub trait MbrLeafGeometry<P: Scalar, const DIM: usize> {
     /// The geometry's dimension count
     fn dim(&self) -> usize;
 
     /// Determine the area of the geometry
-    fn area(&self) -> P;
+    fn area(&self) -> P::Measure;
 
     /// the minimum extent for a given axis
     fn min_for_axis(&self, dim: usize) -> P;
@@ -33,25 +42,221 @@ pub trait MbrLeafGeometry<P: FP, const DIM: usize> {
     fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>);
 
     /// Determine the distance from the mbr's center
-    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P;
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure;
+
+    /// Determine the squared distance from the mbr's center.
+    /// Cheaper than `distance_from_mbr_center` when only the ordering matters
+    fn distance_sq_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        pow(self.distance_from_mbr_center(mbr), 2)
+    }
+
+    /// Determine the shortest distance between the point and any part of the leaf.
+    /// Returns 0 if the point lies on or inside the leaf
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure;
+
+    /// Determine the longest distance between the point and any part of the leaf.
+    /// Defaults to the farthest corner of the leaf's extents, which is exact for points, rects and mbrs
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        let dist: P::Measure =
+            izip!(0..self.dim(), point.deref()).fold(Zero::zero(), |distance, (axis, &z)| {
+                let z = z.to_measure();
+                let far = Float::max(
+                    Float::abs(z - self.min_for_axis(axis).to_measure()),
+                    Float::abs(self.max_for_axis(axis).to_measure() - z),
+                );
+                distance + pow(far, 2)
+            });
+        dist.sqrt()
+    }
+
+    /// Determine the shortest distance between any part of the leaf and the mbr (MINDIST).
+    /// Returns 0 if the leaf and the mbr touch
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure;
+
+    /// Determine an upper bound on the distance between the leaf and the closest object bounded by the mbr (MINMAXDIST).
+    /// Relies on every face of a minimum bounding rectangle touching at least one of its objects.
+    ///
+    /// Described by Roussopoulos, N.; Kelley, S.; Vincent, F. (1995). "Nearest Neighbor Queries"
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure;
 
     /// Determine if the leaf is completely contained in the mbr
     fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool;
 
-    /// Determine if the leaf overlaps the mbr
+    /// Determine if the leaf overlaps the mbr.
+    /// Rects and polygons have to share some of the mbr's interior, so neighbours that only share a face don't overlap.
+    /// Every other leaf overlaps an mbr it only touches, since points and lines have no interior to share
     fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool;
 
     /// Determines the leaf area shared with the rectangle.
     /// In cases where the leaf and mbr overlap, but the leaf has no area (point or a line, for example), return 0
-    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P;
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure;
+
+    /// Determine if every coordinate of the leaf is finite. Leaves that aren't can't be indexed.
+    fn is_finite(&self) -> bool {
+        (0..self.dim())
+            .all(|dim| self.min_for_axis(dim).is_finite() && self.max_for_axis(dim).is_finite())
+    }
+
+    /// Determine the smallest ray parameter t where the ray touches the leaf, if it does.
+    /// Returns 0 if the ray starts on or inside the leaf.
+    ///
+    /// Defaults to the slab test against the leaf's extents, which is exact for rects and mbrs
+    fn ray_entry(&self, ray: &Ray<P::Measure, DIM>) -> Option<P::Measure> {
+        let mut t_min: P::Measure = Zero::zero();
+        let mut t_max: P::Measure = Bounded::max_value();
+        for (axis, &o, &d) in izip!(0..self.dim(), ray.origin.deref(), ray.direction.iter()) {
+            let (lo, hi) = (
+                self.min_for_axis(axis).to_measure(),
+                self.max_for_axis(axis).to_measure(),
+            );
+            if d == Zero::zero() {
+                // parallel to the slab
+                if o < lo || hi < o {
+                    return None;
+                }
+                continue;
+            }
+            let (t1, t2) = ((lo - o) / d, (hi - o) / d);
+            t_min = Float::max(t_min, Float::min(t1, t2));
+            t_max = Float::min(t_max, Float::max(t1, t2));
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    /// The convex parts of the leaf's exact shape, which spatial joins measure pairwise.
+    /// Together they cover the leaf, except for polygons, which return their edges and rely on `distance_from_point`
+    /// for their interior. Defaults to the leaf's extents, which is exact for points and rects
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        vec![LeafPart::Rect(extents_of(self))]
+    }
+}
+
+/// A convex part of a leaf's shape
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeafPart<P: Scalar, const DIM: usize> {
+    /// Every point within the radius of the convex hull of 1 to 3 corners: a point or sphere,
+    /// a segment or capsule, or a triangle
+    Hull(Vec<[P; DIM]>, P),
+    /// An axis-aligned box
+    Rect(Rect<P, DIM>),
+}
+
+impl<P: Scalar, const DIM: usize> LeafPart<P, DIM> {
+    /// Any one point of the part
+    pub fn anchor(&self) -> [P; DIM] {
+        match *self {
+            LeafPart::Hull(ref corners, _) => corners[0],
+            LeafPart::Rect(ref rect) => {
+                let mut anchor = [P::zero(); DIM];
+                for (coord, &(lo, _)) in anchor.iter_mut().zip(rect.iter()) {
+                    *coord = lo;
+                }
+                anchor
+            }
+        }
+    }
+}
+
+impl<P: FP, const DIM: usize> LeafPart<P, DIM> {
+    /// Determine the shortest distance between the parts. Returns 0 if they touch
+    pub fn distance(&self, other: &LeafPart<P, DIM>) -> P {
+        let dist = match (self, other) {
+            (LeafPart::Rect(rect), LeafPart::Rect(other_rect)) => {
+                rect.min_distance_from_mbr(other_rect)
+            }
+            (LeafPart::Hull(corners, radius), LeafPart::Rect(rect))
+            | (LeafPart::Rect(rect), LeafPart::Hull(corners, radius)) => {
+                hull_distance_from_mbr(corners, rect) - *radius
+            }
+            (LeafPart::Hull(corners, radius), LeafPart::Hull(other_corners, other_radius)) => {
+                if DIM == 2
+                    && corners.len() == 2
+                    && other_corners.len() == 2
+                    && segments_touch(corners, other_corners)
+                {
+                    Zero::zero()
+                } else {
+                    hull_distance(corners, other_corners) - *radius - *other_radius
+                }
+            }
+        };
+        Float::max(dist, Zero::zero())
+    }
 }
 
-impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Point<P, DIM> {
+/// An object-safe leaf geometry for `Shapes::Custom`, so application shapes can share a tree with the built-in ones.
+/// Implemented for every `MbrLeafGeometry` that is also `Debug`, `Clone`, `PartialEq`, `Send`, `Sync` and `'static`
+pub trait DynLeafGeometry<P: Scalar, const DIM: usize>:
+    MbrLeafGeometry<P, DIM> + Debug + Send + Sync
+{
+    /// Clone into a new box
+    fn clone_box(&self) -> Box<dyn DynLeafGeometry<P, DIM>>;
+
+    /// The geometry as `Any`, for downcasting to its concrete type
+    fn as_any(&self) -> &dyn Any;
+
+    /// Determine if the other geometry has the same concrete type and is equal to this one
+    fn eq_dyn(&self, other: &dyn DynLeafGeometry<P, DIM>) -> bool;
+}
+
+impl<P: Scalar, const DIM: usize, LG> DynLeafGeometry<P, DIM> for LG
+where
+    LG: MbrLeafGeometry<P, DIM> + Debug + Clone + PartialEq + Send + Sync + 'static,
+{
+    fn clone_box(&self) -> Box<dyn DynLeafGeometry<P, DIM>> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_dyn(&self, other: &dyn DynLeafGeometry<P, DIM>) -> bool {
+        other.as_any().downcast_ref::<LG>() == Some(self)
+    }
+}
+
+impl<P: Scalar, const DIM: usize> Clone for Box<dyn DynLeafGeometry<P, DIM>> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl<P: Scalar, const DIM: usize> PartialEq for dyn DynLeafGeometry<P, DIM> {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_dyn(other)
+    }
+}
+
+/// Exact predicates against a `Polygon`, for 2-dimensional leaves matched by `MbrPolygonQuery`.
+/// The defaults measure the leaf's mbr, which is exact for points and rects
+pub trait PolygonLeafGeometry<P: FP>: MbrLeafGeometry<P, 2> {
+    /// Determine if the leaf is completely inside the polygon, boundary inclusive
+    fn contained_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        polygon.contains_mbr(&extents_of(self))
+    }
+
+    /// Determine if the leaf overlaps the polygon.
+    /// Like rects, leaves with an area have to reach the polygon's interior, while the rest only have to touch it
+    fn overlapped_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        let mbr = extents_of(self);
+        if mbr.area() > Zero::zero() {
+            polygon.interior_overlaps_mbr(&mbr)
+        } else {
+            polygon.touches_mbr(&mbr)
+        }
+    }
+}
+
+impl<P: Scalar, const DIM: usize> MbrLeafGeometry<P, DIM> for Point<P, DIM> {
     fn dim(&self) -> usize {
         self.coords.len()
     }
 
-    fn area(&self) -> P {
+    fn area(&self) -> P::Measure {
         Zero::zero()
     }
 
@@ -65,20 +270,38 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Point<P, DIM> {
 
     fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>) {
         for (&mut (ref mut x, ref mut y), &z) in izip!(mbr.deref_mut(), self.deref()) {
-            *x = Float::min(*x, z);
-            *y = Float::max(*y, z);
+            *x = x.partial_min(z);
+            *y = y.partial_max(z);
         }
     }
 
-    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
-        let two = FromPrimitive::from_usize(2).unwrap();
-        let dist: P = izip!(mbr.deref(), self.deref())
-            .fold(Zero::zero(), |distance, (&(x, y), &z)| {
-                distance + pow(((x + y) / two) - z, 2)
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.distance_sq_from_mbr_center(mbr).sqrt()
+    }
+
+    fn distance_sq_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        distance_sq_from_mbr_center(self.iter().map(|&z| (z, z)), mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        let dist: P::Measure = izip!(self.deref(), point.deref())
+            .fold(Zero::zero(), |distance, (&x, &z)| {
+                distance + pow(x.to_measure() - z.to_measure(), 2)
             });
         dist.sqrt()
     }
 
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        mbr.distance_from_point(self)
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        min_max_distance_from_extents(
+            self.iter().map(|&z| (z.to_measure(), z.to_measure())),
+            &mbr.to_measure(),
+        )
+    }
+
     fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
         self.overlapped_by_mbr(mbr)
     }
@@ -93,9 +316,34 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Point<P, DIM> {
     }
 
     #[allow(unused_variables)]
-    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
         Zero::zero()
     }
+
+    fn is_finite(&self) -> bool {
+        self.coords.iter().all(|&coord| coord.is_finite())
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        vec![LeafPart::Hull(vec![self.coords], Zero::zero())]
+    }
+
+    fn ray_entry(&self, ray: &Ray<P::Measure, DIM>) -> Option<P::Measure> {
+        let point = self.to_measure();
+        // project the point onto the ray
+        let (dot, len_sq) = izip!(point.deref(), ray.origin.deref(), ray.direction.iter()).fold(
+            (Zero::zero(), Zero::zero()),
+            |(dot, len_sq): (P::Measure, P::Measure), (&z, &o, &d)| {
+                (dot + (z - o) * d, len_sq + d * d)
+            },
+        );
+        let t = Float::max(dot / len_sq, Zero::zero());
+        if ray_touches(&point, &ray.point_at(t)) {
+            Some(t)
+        } else {
+            None
+        }
+    }
 }
 
 impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for LineSegment<P, DIM> {
@@ -126,37 +374,203 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for LineSegment<P, DIM> {
     }
 
     fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
-        let two = FromPrimitive::from_usize(2).unwrap();
-        let dist: P = izip!(mbr.deref(), self.x.deref(), self.y.deref())
-            .fold(Zero::zero(), |distance, (&(x1, y1), &x2, &y2)| {
-                distance + pow((x1 + y1) / two - (x2 + y2) / two, 2)
+        self.distance_sq_from_mbr_center(mbr).sqrt()
+    }
+
+    fn distance_sq_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+        let extents = izip!(self.x.iter(), self.y.iter()).map(|(&x, &y)| (x, y));
+        distance_sq_from_mbr_center(extents, mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        // project the point onto the segment, clamping the projection to the segment's ends
+        let (dot, len_sq) = izip!(self.x.deref(), self.y.deref(), point.deref()).fold(
+            (Zero::zero(), Zero::zero()),
+            |(dot, len_sq): (P, P), (&x, &y, &z)| (dot + (z - x) * (y - x), len_sq + pow(y - x, 2)),
+        );
+        if len_sq <= Zero::zero() {
+            return self.x.distance_from_point(point);
+        }
+        let t = Float::min(Float::max(dot / len_sq, Zero::zero()), One::one());
+        let dist: P = izip!(self.x.deref(), self.y.deref(), point.deref())
+            .fold(Zero::zero(), |distance, (&x, &y, &z)| {
+                distance + pow(x + t * (y - x) - z, 2)
             });
         dist.sqrt()
     }
 
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        let zero: P = Zero::zero();
+        let one: P = One::one();
+        let two: P = FromPrimitive::from_usize(2).unwrap();
+        // The squared distance to the mbr is piecewise quadratic along the segment,
+        // changing form wherever the segment crosses one of the mbr's faces
+        let mut breaks = vec![zero, one];
+        for (&x, &y, &(lo, hi)) in izip!(self.x.deref(), self.y.deref(), mbr.deref()) {
+            let d = y - x;
+            if d != zero {
+                for face in [lo, hi] {
+                    let t = (face - x) / d;
+                    if zero < t && t < one {
+                        breaks.push(t);
+                    }
+                }
+            }
+        }
+        breaks.sort_by_key(|&t| OrderedFloat(t));
+
+        let mut min_dist: P = Bounded::max_value();
+        for (&t0, &t1) in breaks.iter().tuple_windows() {
+            let mid = (t0 + t1) / two;
+            // coefficients of a*t^2 + 2*b*t + c over [t0, t1]
+            let (mut a, mut b, mut c) = (zero, zero, zero);
+            for (&x, &y, &(lo, hi)) in izip!(self.x.deref(), self.y.deref(), mbr.deref()) {
+                let d = y - x;
+                let z = x + mid * d;
+                let face = if z < lo {
+                    lo
+                } else if hi < z {
+                    hi
+                } else {
+                    continue;
+                };
+                let e = x - face;
+                a += d * d;
+                b += e * d;
+                c += e * e;
+            }
+            let t = if a > zero {
+                Float::min(Float::max(-b / a, t0), t1)
+            } else {
+                t0
+            };
+            min_dist = Float::min(min_dist, a * t * t + two * b * t + c);
+        }
+        Float::max(min_dist, zero).sqrt()
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        Float::min(
+            self.x.min_max_distance_from_mbr(mbr),
+            self.y.min_max_distance_from_mbr(mbr),
+        )
+    }
+
     fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
         self.x.contained_by_mbr(mbr) && self.y.contained_by_mbr(mbr)
     }
 
     fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
-        self.x.overlapped_by_mbr(mbr) || self.y.overlapped_by_mbr(mbr)
+        // Liang-Barsky clipping, extended to every axis. The segment overlaps if any part of it survives
+        let mut t0: P = Zero::zero();
+        let mut t1: P = One::one();
+        for (&(lo, hi), &x, &y) in izip!(mbr.deref(), self.x.deref(), self.y.deref()) {
+            let d = y - x;
+            for &(p, q) in &[(-d, x - lo), (d, hi - x)] {
+                if p == Zero::zero() {
+                    // parallel to this face, so it's either entirely inside or outside of it
+                    if q < Zero::zero() {
+                        return false;
+                    }
+                    continue;
+                }
+                let r = q / p;
+                if p < Zero::zero() {
+                    t0 = Float::max(t0, r);
+                } else {
+                    t1 = Float::min(t1, r);
+                }
+                if t1 < t0 {
+                    return false;
+                }
+            }
+        }
+        true
     }
 
     #[allow(unused_variables)]
     fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
         Zero::zero()
     }
+
+    fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        // the farthest point of a segment is always one of its ends
+        Float::max(
+            self.x.distance_from_point(point),
+            self.y.distance_from_point(point),
+        )
+    }
+
+    fn ray_entry(&self, ray: &Ray<P, DIM>) -> Option<P> {
+        let zero: P = Zero::zero();
+        let one: P = One::one();
+        // closest points between the ray and the segment, as described by Ericson, C. (2005). "Real-Time Collision Detection"
+        let (mut a, mut b, mut c, mut e, mut f) = (zero, zero, zero, zero, zero);
+        for (&x, &y, &o, &d) in izip!(
+            self.x.deref(),
+            self.y.deref(),
+            ray.origin.deref(),
+            ray.direction.iter()
+        ) {
+            let (seg, r) = (y - x, o - x);
+            a += d * d;
+            b += d * seg;
+            c += d * r;
+            e += seg * seg;
+            f += seg * r;
+        }
+        if e <= zero {
+            return self.x.ray_entry(ray);
+        }
+        let denom = a * e - b * b;
+        let mut t = if denom > zero {
+            Float::max((b * f - c * e) / denom, zero)
+        } else {
+            // parallel, so any t will do before clamping to the segment
+            zero
+        };
+        let s = (b * t + f) / e;
+        let s = if s < zero {
+            t = Float::max(-c / a, zero);
+            zero
+        } else if s > one {
+            t = Float::max((b - c) / a, zero);
+            one
+        } else {
+            s
+        };
+        let mut closest = self.x.clone();
+        for (coord, &y) in closest.coords.iter_mut().zip(self.y.deref()) {
+            *coord = *coord + s * (y - *coord);
+        }
+        if ray_touches(&closest, &ray.point_at(t)) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        vec![LeafPart::Hull(
+            vec![self.x.coords, self.y.coords],
+            Zero::zero(),
+        )]
+    }
 }
 
-impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Rect<P, DIM> {
+impl<P: Scalar, const DIM: usize> MbrLeafGeometry<P, DIM> for Rect<P, DIM> {
     fn dim(&self) -> usize {
         self.edges.len()
     }
 
-    fn area(&self) -> P {
-        self.deref()
-            .iter()
-            .fold(One::one(), |area, &(x, y)| area * (y - x))
+    fn area(&self) -> P::Measure {
+        self.deref().iter().fold(One::one(), |area, &(x, y)| {
+            area * (y.to_measure() - x.to_measure())
+        })
     }
 
     fn min_for_axis(&self, dim: usize) -> P {
@@ -169,20 +583,53 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Rect<P, DIM> {
 
     fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>) {
         for (&mut (ref mut x1, ref mut y1), &(x2, y2)) in izip!(mbr.deref_mut(), self.deref()) {
-            *x1 = Float::min(*x1, x2);
-            *y1 = Float::max(*y1, y2);
+            *x1 = x1.partial_min(x2);
+            *y1 = y1.partial_max(y2);
         }
     }
 
-    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
-        let two = FromPrimitive::from_usize(2).unwrap();
-        let dist: P = izip!(mbr.deref(), self.deref())
-            .fold(Zero::zero(), |distance, (&(x1, y1), &(x2, y2))| {
-                distance + pow((x1 + y1) / two - (x2 + y2) / two, 2)
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.distance_sq_from_mbr_center(mbr).sqrt()
+    }
+
+    fn distance_sq_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        distance_sq_from_mbr_center(self.iter().cloned(), mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        let dist: P::Measure =
+            izip!(self.deref(), point.deref()).fold(Zero::zero(), |distance, (&(x, y), &z)| {
+                if z < x {
+                    distance + pow(x.to_measure() - z.to_measure(), 2)
+                } else if y < z {
+                    distance + pow(z.to_measure() - y.to_measure(), 2)
+                } else {
+                    distance
+                }
             });
         dist.sqrt()
     }
 
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        let dist: P::Measure = izip!(mbr.deref(), self.deref()).fold(
+            Zero::zero(),
+            |distance, (&(x1, y1), &(x2, y2))| {
+                if y2 < x1 {
+                    distance + pow(x1.to_measure() - y2.to_measure(), 2)
+                } else if y1 < x2 {
+                    distance + pow(x2.to_measure() - y1.to_measure(), 2)
+                } else {
+                    distance
+                }
+            },
+        );
+        dist.sqrt()
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        min_max_distance_from_extents(self.to_measure().iter().cloned(), &mbr.to_measure())
+    }
+
     fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
         for (&(x1, y1), &(x2, y2)) in izip!(mbr.deref(), self.deref()) {
             if x2 < x1 || y1 < y2 {
@@ -194,18 +641,111 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Rect<P, DIM> {
 
     fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
         for (&(x1, y1), &(x2, y2)) in izip!(mbr.deref(), self.deref()) {
-            if !(x1 < y2) || !(x2 < y1) {
+            if y2 <= x1 || y1 <= x2 {
                 return false;
             }
         }
         true
     }
 
-    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
         izip!(mbr.deref(), self.deref()).fold(One::one(), |area, (&(x1, y1), &(x2, y2))| {
-            area * Float::max(Float::min(y1, y2) - Float::max(x1, x2), Zero::zero())
+            let (lo, hi) = (x1.partial_max(x2), y1.partial_min(y2));
+            if hi <= lo {
+                return Zero::zero();
+            }
+            area * (hi.to_measure() - lo.to_measure())
         })
     }
+
+    fn is_finite(&self) -> bool {
+        self.edges
+            .iter()
+            .all(|&(x, y)| x.is_finite() && y.is_finite())
+    }
+}
+
+impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for LineString<P, DIM> {
+    fn dim(&self) -> usize {
+        DIM
+    }
+
+    fn area(&self) -> P {
+        Zero::zero()
+    }
+
+    fn min_for_axis(&self, dim: usize) -> P {
+        self.mbr().min_for_axis(dim)
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        self.mbr().max_for_axis(dim)
+    }
+
+    fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>) {
+        self.mbr().expand_mbr_to_fit(mbr);
+    }
+
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+        self.mbr().distance_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        self.segments()
+            .map(|segment| segment.distance_from_point(point))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        // the farthest point of a line string is always one of its vertices
+        self.vertices()
+            .iter()
+            .map(|vertex| vertex.distance_from_point(point))
+            .fold(Zero::zero(), Float::max)
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        self.segments()
+            .map(|segment| segment.min_distance_from_mbr(mbr))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        self.vertices()
+            .iter()
+            .map(|vertex| vertex.min_max_distance_from_mbr(mbr))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        self.mbr().contained_by_mbr(mbr)
+    }
+
+    fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        self.segments()
+            .any(|segment| segment.overlapped_by_mbr(mbr))
+    }
+
+    #[allow(unused_variables)]
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        Zero::zero()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.vertices().iter().all(|vertex| vertex.is_finite())
+    }
+
+    fn ray_entry(&self, ray: &Ray<P, DIM>) -> Option<P> {
+        self.segments()
+            .filter_map(|segment| segment.ray_entry(ray))
+            .min_by_key(|&t| OrderedFloat(t))
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        self.segments()
+            .flat_map(|segment| segment.parts())
+            .collect()
+    }
 }
 
 impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
@@ -214,6 +754,8 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.dim(),
             Shapes::LineSegment(ref linesegment) => linesegment.dim(),
             Shapes::Rect(ref rect) => rect.dim(),
+            Shapes::LineString(ref linestring) => linestring.dim(),
+            Shapes::Custom(ref custom) => custom.dim(),
         }
     }
 
@@ -222,6 +764,8 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.area(),
             Shapes::LineSegment(ref linesegment) => linesegment.area(),
             Shapes::Rect(ref rect) => rect.area(),
+            Shapes::LineString(ref linestring) => linestring.area(),
+            Shapes::Custom(ref custom) => custom.area(),
         }
     }
 
@@ -230,6 +774,8 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.min_for_axis(dim),
             Shapes::LineSegment(ref linesegment) => linesegment.min_for_axis(dim),
             Shapes::Rect(ref rect) => rect.min_for_axis(dim),
+            Shapes::LineString(ref linestring) => linestring.min_for_axis(dim),
+            Shapes::Custom(ref custom) => custom.min_for_axis(dim),
         }
     }
 
@@ -238,6 +784,8 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.max_for_axis(dim),
             Shapes::LineSegment(ref linesegment) => linesegment.max_for_axis(dim),
             Shapes::Rect(ref rect) => rect.max_for_axis(dim),
+            Shapes::LineString(ref linestring) => linestring.max_for_axis(dim),
+            Shapes::Custom(ref custom) => custom.max_for_axis(dim),
         }
     }
 
@@ -246,6 +794,8 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.expand_mbr_to_fit(mbr),
             Shapes::LineSegment(ref linesegment) => linesegment.expand_mbr_to_fit(mbr),
             Shapes::Rect(ref rect) => rect.expand_mbr_to_fit(mbr),
+            Shapes::LineString(ref linestring) => linestring.expand_mbr_to_fit(mbr),
+            Shapes::Custom(ref custom) => custom.expand_mbr_to_fit(mbr),
         }
     }
 
@@ -254,6 +804,48 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.distance_from_mbr_center(mbr),
             Shapes::LineSegment(ref linesegment) => linesegment.distance_from_mbr_center(mbr),
             Shapes::Rect(ref rect) => rect.distance_from_mbr_center(mbr),
+            Shapes::LineString(ref linestring) => linestring.distance_from_mbr_center(mbr),
+            Shapes::Custom(ref custom) => custom.distance_from_mbr_center(mbr),
+        }
+    }
+
+    fn distance_sq_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+        match *self {
+            Shapes::Point(ref point) => point.distance_sq_from_mbr_center(mbr),
+            Shapes::LineSegment(ref linesegment) => linesegment.distance_sq_from_mbr_center(mbr),
+            Shapes::Rect(ref rect) => rect.distance_sq_from_mbr_center(mbr),
+            Shapes::LineString(ref linestring) => linestring.distance_sq_from_mbr_center(mbr),
+            Shapes::Custom(ref custom) => custom.distance_sq_from_mbr_center(mbr),
+        }
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        match *self {
+            Shapes::Point(ref point_shape) => point_shape.distance_from_point(point),
+            Shapes::LineSegment(ref linesegment) => linesegment.distance_from_point(point),
+            Shapes::Rect(ref rect) => rect.distance_from_point(point),
+            Shapes::LineString(ref linestring) => linestring.distance_from_point(point),
+            Shapes::Custom(ref custom) => custom.distance_from_point(point),
+        }
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        match *self {
+            Shapes::Point(ref point) => point.min_distance_from_mbr(mbr),
+            Shapes::LineSegment(ref linesegment) => linesegment.min_distance_from_mbr(mbr),
+            Shapes::Rect(ref rect) => rect.min_distance_from_mbr(mbr),
+            Shapes::LineString(ref linestring) => linestring.min_distance_from_mbr(mbr),
+            Shapes::Custom(ref custom) => custom.min_distance_from_mbr(mbr),
+        }
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        match *self {
+            Shapes::Point(ref point) => point.min_max_distance_from_mbr(mbr),
+            Shapes::LineSegment(ref linesegment) => linesegment.min_max_distance_from_mbr(mbr),
+            Shapes::Rect(ref rect) => rect.min_max_distance_from_mbr(mbr),
+            Shapes::LineString(ref linestring) => linestring.min_max_distance_from_mbr(mbr),
+            Shapes::Custom(ref custom) => custom.min_max_distance_from_mbr(mbr),
         }
     }
 
@@ -262,6 +854,8 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.contained_by_mbr(mbr),
             Shapes::LineSegment(ref linesegment) => linesegment.contained_by_mbr(mbr),
             Shapes::Rect(ref rect) => rect.contained_by_mbr(mbr),
+            Shapes::LineString(ref linestring) => linestring.contained_by_mbr(mbr),
+            Shapes::Custom(ref custom) => custom.contained_by_mbr(mbr),
         }
     }
 
@@ -270,6 +864,8 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.overlapped_by_mbr(mbr),
             Shapes::LineSegment(ref linesegment) => linesegment.overlapped_by_mbr(mbr),
             Shapes::Rect(ref rect) => rect.overlapped_by_mbr(mbr),
+            Shapes::LineString(ref linestring) => linestring.overlapped_by_mbr(mbr),
+            Shapes::Custom(ref custom) => custom.overlapped_by_mbr(mbr),
         }
     }
 
@@ -278,69 +874,1302 @@ impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Shapes<P, DIM> {
             Shapes::Point(ref point) => point.area_overlapped_with_mbr(mbr),
             Shapes::LineSegment(ref linesegment) => linesegment.area_overlapped_with_mbr(mbr),
             Shapes::Rect(ref rect) => rect.area_overlapped_with_mbr(mbr),
+            Shapes::LineString(ref linestring) => linestring.area_overlapped_with_mbr(mbr),
+            Shapes::Custom(ref custom) => custom.area_overlapped_with_mbr(mbr),
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::geometry::{LineSegment, Point, Rect, Shapes};
-    use std::ops::Deref;
-
-    const ONE: [f64; 3] = [1.0f64, 1.0f64, 1.0f64];
-    const ZERO: [f64; 3] = [0.0f64, 0.0f64, 0.0f64];
-    const NEG_ONE: [f64; 3] = [-1.0f64, -1.0f64, -1.0f64];
-    const NEG_TWO: [f64; 3] = [-2.0f64, -2.0f64, -2.0f64];
-
-    // distance of [0.5, 0.5, 0.5]
-    const EXPECTED_DISTANCE: f64 = 0.86602540378f64;
 
-    #[test]
-    fn point() {
-        let point: Point<f64, 3> = Point::new([0.0; 3]);
-        for i in point.deref() {
-            assert_relative_eq!(0.0f64, i);
+    fn is_finite(&self) -> bool {
+        match *self {
+            Shapes::Point(ref point) => point.is_finite(),
+            Shapes::LineSegment(ref linesegment) => linesegment.is_finite(),
+            Shapes::Rect(ref rect) => rect.is_finite(),
+            Shapes::LineString(ref linestring) => linestring.is_finite(),
+            Shapes::Custom(ref custom) => custom.is_finite(),
         }
+    }
 
-        let zero: Shapes<f64, 3> = Shapes::Point(Point::from_slice(&ZERO));
-        let one: Shapes<f64, 3> = Shapes::Point(Point::from_slice(&ONE));
-        let neg_one: Shapes<f64, 3> = Shapes::Point(Point::from_slice(&NEG_ONE));
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        match *self {
+            Shapes::Point(ref point_shape) => point_shape.max_distance_from_point(point),
+            Shapes::LineSegment(ref linesegment) => linesegment.max_distance_from_point(point),
+            Shapes::Rect(ref rect) => rect.max_distance_from_point(point),
+            Shapes::LineString(ref linestring) => linestring.max_distance_from_point(point),
+            Shapes::Custom(ref custom) => custom.max_distance_from_point(point),
+        }
+    }
 
-        // Shape tests
-        // dim
-        assert_eq!(ZERO.len(), zero.dim());
-        // area
-        assert_relative_eq!(0.0f64, zero.area());
-        // min/max for axis
-        for (i, item) in ZERO.iter().enumerate() {
-            assert_relative_eq!(*item, zero.min_for_axis(i));
-            assert_relative_eq!(*item, zero.max_for_axis(i));
+    fn ray_entry(&self, ray: &Ray<P, DIM>) -> Option<P> {
+        match *self {
+            Shapes::Point(ref point) => point.ray_entry(ray),
+            Shapes::LineSegment(ref linesegment) => linesegment.ray_entry(ray),
+            Shapes::Rect(ref rect) => rect.ray_entry(ray),
+            Shapes::LineString(ref linestring) => linestring.ray_entry(ray),
+            Shapes::Custom(ref custom) => custom.ray_entry(ray),
         }
-        let mut bounding_mbr = Rect::max_inverted();
-        // expand_mbr_to_fit
-        zero.expand_mbr_to_fit(&mut bounding_mbr);
-        one.expand_mbr_to_fit(&mut bounding_mbr);
-        for (i, (x, y)) in izip!(&ZERO, &ONE).enumerate() {
-            assert_relative_eq!(*x, bounding_mbr.min_for_axis(i));
-            assert_relative_eq!(*y, bounding_mbr.max_for_axis(i));
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        match *self {
+            Shapes::Point(ref point) => point.parts(),
+            Shapes::LineSegment(ref linesegment) => linesegment.parts(),
+            Shapes::Rect(ref rect) => rect.parts(),
+            Shapes::LineString(ref linestring) => linestring.parts(),
+            Shapes::Custom(ref custom) => custom.parts(),
         }
+    }
+}
 
-        // distance_from_mbr_center
-        assert_relative_eq!(
-            EXPECTED_DISTANCE,
-            zero.distance_from_mbr_center(&bounding_mbr),
-            max_relative = 0.00000001
-        );
+impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Sphere<P, DIM> {
+    fn dim(&self) -> usize {
+        self.center.dim()
+    }
 
-        // contained_by_mbr
-        assert!(zero.contained_by_mbr(&bounding_mbr));
-        assert!(one.contained_by_mbr(&bounding_mbr));
-        assert!(!neg_one.contained_by_mbr(&bounding_mbr));
+    fn area(&self) -> P {
+        ball_volume(DIM, self.radius)
+    }
 
-        // overlapped_by_mbr
-        assert!(zero.overlapped_by_mbr(&bounding_mbr));
-        assert!(one.overlapped_by_mbr(&bounding_mbr));
+    fn min_for_axis(&self, dim: usize) -> P {
+        self.center.min_for_axis(dim) - self.radius
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        self.center.max_for_axis(dim) + self.radius
+    }
+
+    fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>) {
+        for (&mut (ref mut x, ref mut y), &z) in izip!(mbr.deref_mut(), self.center.deref()) {
+            *x = Float::min(*x, z - self.radius);
+            *y = Float::max(*y, z + self.radius);
+        }
+    }
+
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+        self.center.distance_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        Float::max(
+            self.center.distance_from_point(point) - self.radius,
+            Zero::zero(),
+        )
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        self.center.distance_from_point(point) + self.radius
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        Float::max(
+            mbr.distance_from_point(&self.center) - self.radius,
+            Zero::zero(),
+        )
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        Float::max(
+            self.center.min_max_distance_from_mbr(mbr) - self.radius,
+            Zero::zero(),
+        )
+    }
+
+    fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        izip!(mbr.deref(), self.center.deref())
+            .all(|(&(x, y), &z)| x <= z - self.radius && z + self.radius <= y)
+    }
+
+    fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        mbr.distance_from_point(&self.center) <= self.radius
+    }
+
+    // Approximate, see estimated_area_overlapped. Only used to rank candidate nodes during insertion
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        estimated_area_overlapped(self, mbr)
+    }
+
+    fn ray_entry(&self, ray: &Ray<P, DIM>) -> Option<P> {
+        ball_ray_entry(&self.center, self.radius, ray)
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        vec![LeafPart::Hull(vec![self.center.coords], self.radius)]
+    }
+}
+
+impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Capsule<P, DIM> {
+    fn dim(&self) -> usize {
+        self.segment.dim()
+    }
+
+    // the cylinder between the end caps plus a whole sphere for the caps
+    fn area(&self) -> P {
+        let length = self.segment.x.distance_from_point(&self.segment.y);
+        ball_volume(DIM, self.radius) + ball_volume(DIM.saturating_sub(1), self.radius) * length
+    }
+
+    fn min_for_axis(&self, dim: usize) -> P {
+        self.segment.min_for_axis(dim) - self.radius
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        self.segment.max_for_axis(dim) + self.radius
+    }
+
+    fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>) {
+        for (axis, &mut (ref mut x, ref mut y)) in mbr.deref_mut().iter_mut().enumerate() {
+            *x = Float::min(*x, self.min_for_axis(axis));
+            *y = Float::max(*y, self.max_for_axis(axis));
+        }
+    }
+
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+        self.segment.distance_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        Float::max(
+            self.segment.distance_from_point(point) - self.radius,
+            Zero::zero(),
+        )
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        self.segment.max_distance_from_point(point) + self.radius
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        Float::max(
+            self.segment.min_distance_from_mbr(mbr) - self.radius,
+            Zero::zero(),
+        )
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        Float::max(
+            self.segment.min_max_distance_from_mbr(mbr) - self.radius,
+            Zero::zero(),
+        )
+    }
+
+    fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        mbr.iter()
+            .enumerate()
+            .all(|(axis, &(x, y))| x <= self.min_for_axis(axis) && self.max_for_axis(axis) <= y)
+    }
+
+    fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        self.segment.min_distance_from_mbr(mbr) <= self.radius
+    }
+
+    // Approximate, see estimated_area_overlapped. Only used to rank candidate nodes during insertion
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        estimated_area_overlapped(self, mbr)
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        vec![LeafPart::Hull(
+            vec![self.segment.x.coords, self.segment.y.coords],
+            self.radius,
+        )]
+    }
+
+    // The capsule is its end spheres and the cylinder between them. A ray entering through a cap enters a sphere,
+    // so only the cylinder's side needs testing beyond the spheres
+    fn ray_entry(&self, ray: &Ray<P, DIM>) -> Option<P> {
+        let (x, y) = (&self.segment.x.coords, &self.segment.y.coords);
+        if self.segment.distance_from_point(&ray.origin) <= self.radius {
+            return Some(Zero::zero());
+        }
+        let axis = sub(y, x);
+        let axis_sq = dot(&axis, &axis);
+        let mut entry = min_entry(
+            ball_ray_entry(&self.segment.x, self.radius, ray),
+            ball_ray_entry(&self.segment.y, self.radius, ray),
+        );
+        if axis_sq > Zero::zero() {
+            let offset = sub(&ray.origin.coords, x);
+            let perpendicular = |v: &[P; DIM]| {
+                let along = dot(v, &axis) / axis_sq;
+                let mut perp = *v;
+                for (coord, &a) in perp.iter_mut().zip(axis.iter()) {
+                    *coord = *coord - along * a;
+                }
+                perp
+            };
+            let (m, n) = (perpendicular(&offset), perpendicular(&ray.direction));
+            let a = dot(&n, &n);
+            let b = dot(&m, &n);
+            let c = dot(&m, &m) - self.radius * self.radius;
+            let discriminant = b * b - a * c;
+            if a > Zero::zero() && discriminant >= Zero::zero() {
+                let t = (-b - discriminant.sqrt()) / a;
+                let along = dot(&sub(&ray.point_at(t).coords, x), &axis) / axis_sq;
+                if t >= Zero::zero() && along >= Zero::zero() && along <= One::one() {
+                    entry = min_entry(entry, Some(t));
+                }
+            }
+        }
+        entry
+    }
+}
+
+impl<P: FP, const DIM: usize> MbrLeafGeometry<P, DIM> for Triangle<P, DIM> {
+    fn dim(&self) -> usize {
+        self.a.dim()
+    }
+
+    // the surface area, since a triangle has no volume beyond 2 dimensions
+    fn area(&self) -> P {
+        let two: P = FromPrimitive::from_usize(2).unwrap();
+        let (ab, ac) = (
+            sub(&self.b.coords, &self.a.coords),
+            sub(&self.c.coords, &self.a.coords),
+        );
+        let doubled_sq = dot(&ab, &ab) * dot(&ac, &ac) - pow(dot(&ab, &ac), 2);
+        Float::max(doubled_sq, Zero::zero()).sqrt() / two
+    }
+
+    fn min_for_axis(&self, dim: usize) -> P {
+        Float::min(
+            self.a.min_for_axis(dim),
+            Float::min(self.b.min_for_axis(dim), self.c.min_for_axis(dim)),
+        )
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        Float::max(
+            self.a.max_for_axis(dim),
+            Float::max(self.b.max_for_axis(dim), self.c.max_for_axis(dim)),
+        )
+    }
+
+    fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, DIM>) {
+        self.a.expand_mbr_to_fit(mbr);
+        self.b.expand_mbr_to_fit(mbr);
+        self.c.expand_mbr_to_fit(mbr);
+    }
+
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+        let three: P = FromPrimitive::from_usize(3).unwrap();
+        let mut centroid = self.a.clone();
+        for (coord, &b, &c) in izip!(centroid.coords.iter_mut(), self.b.deref(), self.c.deref()) {
+            *coord = (*coord + b + c) / three;
+        }
+        centroid.distance_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        self.closest_point(point).distance_from_point(point)
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, DIM>) -> P {
+        // the farthest point of a triangle is always one of its corners
+        Float::max(
+            self.a.distance_from_point(point),
+            Float::max(
+                self.b.distance_from_point(point),
+                self.c.distance_from_point(point),
+            ),
+        )
+    }
+
+    // Cut the triangle along every face plane of the mbr. Over each piece, the nearest feature of the mbr stays the same,
+    // so the squared distance is one quadratic. Its minimum is at the quadratic's stationary point or on the piece's edges
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        if self.overlapped_by_mbr(mbr) {
+            return Zero::zero();
+        }
+        let (zero, one): (P, P) = (Zero::zero(), One::one());
+        // each piece keeps, for every axis, the face it's beyond, or None when it's between them
+        let mut pieces = vec![(self.corners(), [None; DIM])];
+        for (axis, &(lo, hi)) in mbr.iter().enumerate() {
+            let mut split = Vec::with_capacity(pieces.len() * 3);
+            for (piece, faces) in pieces {
+                let between = clip_to_face(&clip_to_face(&piece, axis, lo, one), axis, hi, -one);
+                for (part, face) in [
+                    (clip_to_face(&piece, axis, lo, -one), Some(lo)),
+                    (between, None),
+                    (clip_to_face(&piece, axis, hi, one), Some(hi)),
+                ] {
+                    if !part.is_empty() {
+                        let mut faces = faces;
+                        faces[axis] = face;
+                        split.push((part, faces));
+                    }
+                }
+            }
+            pieces = split;
+        }
+
+        let (a, ab, ac) = (
+            self.a.coords,
+            sub(&self.b.coords, &self.a.coords),
+            sub(&self.c.coords, &self.a.coords),
+        );
+        let mut min_dist: P = Bounded::max_value();
+        let mut consider = |coords: [P; DIM]| {
+            min_dist = Float::min(min_dist, mbr.distance_from_point(&Point { coords }));
+        };
+        for (piece, faces) in pieces {
+            // offsets from the piece's faces, ignoring the axes it's between faces on
+            let masked = |coords: &[P; DIM], from_faces: bool| {
+                let mut masked = *coords;
+                for (coord, face) in masked.iter_mut().zip(faces.iter()) {
+                    *coord = match *face {
+                        Some(face) if from_faces => *coord - face,
+                        Some(_) => *coord,
+                        None => zero,
+                    };
+                }
+                masked
+            };
+            let weights = least_squares(&[masked(&ab, false), masked(&ac, false)], &masked(&a, true));
+            if let Some(weights) = weights {
+                let (u, v) = (weights[0], weights[1]);
+                if u >= zero && v >= zero && u + v <= one {
+                    let mut coords = a;
+                    for (coord, &b, &c) in izip!(coords.iter_mut(), ab.iter(), ac.iter()) {
+                        *coord = *coord + u * b + v * c;
+                    }
+                    consider(coords);
+                }
+            }
+            for (i, x) in piece.iter().enumerate() {
+                let y = &piece[(i + 1) % piece.len()];
+                let along = masked(&sub(y, x), false);
+                let length_sq = dot(&along, &along);
+                let t = if length_sq > zero {
+                    Float::min(Float::max(-dot(&masked(x, true), &along) / length_sq, zero), one)
+                } else {
+                    zero
+                };
+                let mut coords = *x;
+                for (coord, &to) in coords.iter_mut().zip(y.iter()) {
+                    *coord = *coord + t * (to - *coord);
+                }
+                consider(coords);
+            }
+        }
+        min_dist
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        Float::min(
+            self.a.min_max_distance_from_mbr(mbr),
+            Float::min(
+                self.b.min_max_distance_from_mbr(mbr),
+                self.c.min_max_distance_from_mbr(mbr),
+            ),
+        )
+    }
+
+    fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        self.a.contained_by_mbr(mbr) && self.b.contained_by_mbr(mbr) && self.c.contained_by_mbr(mbr)
+    }
+
+    fn overlapped_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
+        !clip_polygon(self.corners(), mbr).is_empty()
+    }
+
+    // the clipped triangle is convex, so its area is the sum of a fan of triangles
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+        let clipped = clip_polygon(self.corners(), mbr);
+        clipped
+            .iter()
+            .zip(clipped.iter().skip(1))
+            .skip(1)
+            .fold(Zero::zero(), |area, (&b, &c)| {
+                area + Triangle {
+                    a: Point { coords: clipped[0] },
+                    b: Point { coords: b },
+                    c: Point { coords: c },
+                }
+                .area()
+            })
+    }
+
+    fn is_finite(&self) -> bool {
+        self.a.is_finite() && self.b.is_finite() && self.c.is_finite()
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, DIM>> {
+        vec![LeafPart::Hull(self.corners(), Zero::zero())]
+    }
+
+    // The ray either crosses the triangle's plane, or lies in it and enters through an edge
+    fn ray_entry(&self, ray: &Ray<P, DIM>) -> Option<P> {
+        if ray_touches(&self.closest_point(&ray.origin), &ray.origin) {
+            return Some(Zero::zero());
+        }
+        let mut entry = self
+            .edges()
+            .iter()
+            .filter_map(|edge| edge.ray_entry(ray))
+            .min_by_key(|&t| OrderedFloat(t));
+        if DIM >= 3 {
+            entry = min_entry(entry, self.plane_crossing(ray));
+        }
+        entry
+    }
+}
+
+impl<P: FP, const DIM: usize> Triangle<P, DIM> {
+    fn corners(&self) -> Vec<[P; DIM]> {
+        vec![self.a.coords, self.b.coords, self.c.coords]
+    }
+
+    /// The closest point of the triangle, by the Voronoi region of the point.
+    ///
+    /// Described by Ericson, C. (2005). "Real-Time Collision Detection"
+    fn closest_point(&self, point: &Point<P, DIM>) -> Point<P, DIM> {
+        let zero: P = Zero::zero();
+        let (a, b, c, p) = (
+            &self.a.coords,
+            &self.b.coords,
+            &self.c.coords,
+            &point.coords,
+        );
+        let (ab, ac) = (sub(b, a), sub(c, a));
+        let at = |u: P, v: P| {
+            let mut coords = *a;
+            for (coord, &ab, &ac) in izip!(coords.iter_mut(), ab.iter(), ac.iter()) {
+                *coord = *coord + u * ab + v * ac;
+            }
+            Point { coords }
+        };
+        let ap = sub(p, a);
+        let (d1, d2) = (dot(&ab, &ap), dot(&ac, &ap));
+        if d1 <= zero && d2 <= zero {
+            return self.a.clone();
+        }
+        let bp = sub(p, b);
+        let (d3, d4) = (dot(&ab, &bp), dot(&ac, &bp));
+        if d3 >= zero && d4 <= d3 {
+            return self.b.clone();
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= zero && d1 >= zero && d3 <= zero {
+            return at(d1 / (d1 - d3), zero);
+        }
+        let cp = sub(p, c);
+        let (d5, d6) = (dot(&ab, &cp), dot(&ac, &cp));
+        if d6 >= zero && d5 <= d6 {
+            return self.c.clone();
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= zero && d2 >= zero && d6 <= zero {
+            return at(zero, d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+            let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return at(P::one() - w, w);
+        }
+        let sum = va + vb + vc;
+        if sum <= zero {
+            // degenerate, so the closest point is on an edge
+            return self
+                .edges()
+                .iter()
+                .map(|edge| {
+                    let (x, y) = (&edge.x.coords, &edge.y.coords);
+                    let xy = sub(y, x);
+                    let len_sq = dot(&xy, &xy);
+                    let t = if len_sq > zero {
+                        Float::min(Float::max(dot(&sub(p, x), &xy) / len_sq, zero), One::one())
+                    } else {
+                        zero
+                    };
+                    let mut coords = *x;
+                    for (coord, &d) in coords.iter_mut().zip(xy.iter()) {
+                        *coord += t * d;
+                    }
+                    Point { coords }
+                })
+                .min_by_key(|closest| OrderedFloat(closest.distance_from_point(point)))
+                .unwrap_or_else(|| unreachable!("a triangle has 3 edges"));
+        }
+        at(vb / sum, vc / sum)
+    }
+
+    /// Where the ray crosses the triangle's plane inside the triangle, solving `a + u * ab + v * ac = origin + t * direction`
+    fn plane_crossing(&self, ray: &Ray<P, DIM>) -> Option<P> {
+        let zero: P = Zero::zero();
+        let tolerance: P = FromPrimitive::from_usize(64).unwrap();
+        let (ab, ac) = (
+            sub(&self.b.coords, &self.a.coords),
+            sub(&self.c.coords, &self.a.coords),
+        );
+        let mut back = ray.direction;
+        for coord in back.iter_mut() {
+            *coord = -*coord;
+        }
+        let ao = sub(&ray.origin.coords, &self.a.coords);
+        // normal equations of the least squares solution, solved by Cramer's rule
+        let basis = [&ab, &ac, &back];
+        let gram: Vec<[P; 3]> = basis
+            .iter()
+            .map(|&row| [dot(row, &ab), dot(row, &ac), dot(row, &back)])
+            .collect();
+        let rhs = [dot(&ab, &ao), dot(&ac, &ao), dot(&back, &ao)];
+        let det3 = |m: [[P; 3]; 3]| {
+            m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+                - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+                + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+        };
+        let matrix = [gram[0], gram[1], gram[2]];
+        let det = det3(matrix);
+        let scale = gram[0][0] * gram[1][1] * gram[2][2];
+        if det <= tolerance * Float::epsilon() * scale {
+            // parallel to the plane
+            return None;
+        }
+        let solve = |column: usize| {
+            let mut replaced = matrix;
+            for (row, &r) in replaced.iter_mut().zip(rhs.iter()) {
+                row[column] = r;
+            }
+            det3(replaced) / det
+        };
+        let (u, v, t) = (solve(0), solve(1), solve(2));
+        if u < zero || v < zero || u + v > One::one() || t < zero {
+            return None;
+        }
+        let mut crossing = self.a.clone();
+        for (coord, &ab, &ac) in izip!(crossing.coords.iter_mut(), ab.iter(), ac.iter()) {
+            *coord = *coord + u * ab + v * ac;
+        }
+        if ray_touches(&crossing, &ray.point_at(t)) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+impl<P: FP> MbrLeafGeometry<P, 2> for Polygon<P> {
+    fn dim(&self) -> usize {
+        2
+    }
+
+    fn area(&self) -> P {
+        self.holes().iter().fold(
+            Float::abs(ring_area(
+                self.exterior().iter().map(|vertex| vertex.coords),
+            )),
+            |area, hole| area - Float::abs(ring_area(hole.iter().map(|vertex| vertex.coords))),
+        )
+    }
+
+    fn min_for_axis(&self, dim: usize) -> P {
+        self.mbr().min_for_axis(dim)
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        self.mbr().max_for_axis(dim)
+    }
+
+    fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, 2>) {
+        self.mbr().expand_mbr_to_fit(mbr);
+    }
+
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, 2>) -> P {
+        self.mbr().distance_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, 2>) -> P {
+        if self.contains_point(point) {
+            return Zero::zero();
+        }
+        self.edges()
+            .map(|edge| edge.distance_from_point(point))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, 2>) -> P {
+        // the farthest point of a polygon is always one of its exterior vertices
+        self.exterior()
+            .iter()
+            .map(|vertex| vertex.distance_from_point(point))
+            .fold(Zero::zero(), Float::max)
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, 2>) -> P {
+        if self.touches_mbr(mbr) {
+            return Zero::zero();
+        }
+        self.edges()
+            .map(|edge| edge.min_distance_from_mbr(mbr))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, 2>) -> P {
+        self.exterior()
+            .iter()
+            .map(|vertex| vertex.min_max_distance_from_mbr(mbr))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn contained_by_mbr(&self, mbr: &Rect<P, 2>) -> bool {
+        self.mbr().contained_by_mbr(mbr)
+    }
+
+    // Like rects, polygons only overlap an mbr that reaches their interior
+    fn overlapped_by_mbr(&self, mbr: &Rect<P, 2>) -> bool {
+        self.interior_overlaps_mbr(mbr)
+    }
+
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, 2>) -> P {
+        self.holes().iter().fold(
+            Float::abs(ring_area(clip_polygon(coords(self.exterior()), mbr))),
+            |area, hole| area - Float::abs(ring_area(clip_polygon(coords(hole), mbr))),
+        )
+    }
+
+    fn ray_entry(&self, ray: &Ray<P, 2>) -> Option<P> {
+        if self.contains_point(&ray.origin) {
+            return Some(Zero::zero());
+        }
+        self.edges()
+            .filter_map(|edge| edge.ray_entry(ray))
+            .min_by_key(|&t| OrderedFloat(t))
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, 2>> {
+        self.edges().flat_map(|edge| edge.parts()).collect()
+    }
+}
+
+impl<P: FP> MbrLeafGeometry<P, 2> for MultiPolygon<P> {
+    fn dim(&self) -> usize {
+        2
+    }
+
+    fn area(&self) -> P {
+        self.polygons()
+            .iter()
+            .fold(Zero::zero(), |area, polygon| area + polygon.area())
+    }
+
+    fn min_for_axis(&self, dim: usize) -> P {
+        self.mbr().min_for_axis(dim)
+    }
+
+    fn max_for_axis(&self, dim: usize) -> P {
+        self.mbr().max_for_axis(dim)
+    }
+
+    fn expand_mbr_to_fit(&self, mbr: &mut Rect<P, 2>) {
+        self.mbr().expand_mbr_to_fit(mbr);
+    }
+
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, 2>) -> P {
+        self.mbr().distance_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, 2>) -> P {
+        self.polygons()
+            .iter()
+            .map(|polygon| polygon.distance_from_point(point))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn max_distance_from_point(&self, point: &Point<P, 2>) -> P {
+        self.polygons()
+            .iter()
+            .map(|polygon| polygon.max_distance_from_point(point))
+            .fold(Zero::zero(), Float::max)
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, 2>) -> P {
+        self.polygons()
+            .iter()
+            .map(|polygon| polygon.min_distance_from_mbr(mbr))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, 2>) -> P {
+        self.polygons()
+            .iter()
+            .map(|polygon| polygon.min_max_distance_from_mbr(mbr))
+            .fold(Bounded::max_value(), Float::min)
+    }
+
+    fn contained_by_mbr(&self, mbr: &Rect<P, 2>) -> bool {
+        self.mbr().contained_by_mbr(mbr)
+    }
+
+    fn overlapped_by_mbr(&self, mbr: &Rect<P, 2>) -> bool {
+        self.polygons()
+            .iter()
+            .any(|polygon| polygon.overlapped_by_mbr(mbr))
+    }
+
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, 2>) -> P {
+        self.polygons().iter().fold(Zero::zero(), |area, polygon| {
+            area + polygon.area_overlapped_with_mbr(mbr)
+        })
+    }
+
+    fn ray_entry(&self, ray: &Ray<P, 2>) -> Option<P> {
+        self.polygons()
+            .iter()
+            .filter_map(|polygon| polygon.ray_entry(ray))
+            .min_by_key(|&t| OrderedFloat(t))
+    }
+
+    fn parts(&self) -> Vec<LeafPart<P, 2>> {
+        self.polygons()
+            .iter()
+            .flat_map(|polygon| polygon.parts())
+            .collect()
+    }
+}
+
+impl<P: FP> PolygonLeafGeometry<P> for Point<P, 2> {}
+
+impl<P: FP> PolygonLeafGeometry<P> for Rect<P, 2> {}
+
+impl<P: FP> PolygonLeafGeometry<P> for LineSegment<P, 2> {
+    fn contained_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        polygon.contains_segment(self)
+    }
+
+    fn overlapped_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        polygon.touches_segment(self)
+    }
+}
+
+impl<P: FP> PolygonLeafGeometry<P> for LineString<P, 2> {
+    fn contained_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        self.segments()
+            .all(|segment| polygon.contains_segment(&segment))
+    }
+
+    fn overlapped_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        self.segments()
+            .any(|segment| polygon.touches_segment(&segment))
+    }
+}
+
+impl<P: FP> PolygonLeafGeometry<P> for Polygon<P> {
+    fn contained_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        polygon.contains_polygon(self)
+    }
+
+    fn overlapped_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        polygon.interior_overlaps_polygon(self)
+    }
+}
+
+impl<P: FP> PolygonLeafGeometry<P> for MultiPolygon<P> {
+    fn contained_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        self.polygons()
+            .iter()
+            .all(|member| polygon.contains_polygon(member))
+    }
+
+    fn overlapped_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        self.polygons()
+            .iter()
+            .any(|member| polygon.interior_overlaps_polygon(member))
+    }
+}
+
+// A 2-dimensional triangle is a polygon
+impl<P: FP> PolygonLeafGeometry<P> for Triangle<P, 2> {
+    fn contained_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        polygon.contains_polygon(&Polygon::new(self.corners(), vec![]))
+    }
+
+    fn overlapped_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        polygon.interior_overlaps_polygon(&Polygon::new(self.corners(), vec![]))
+    }
+}
+
+impl<P: FP> PolygonLeafGeometry<P> for Sphere<P, 2> {}
+
+impl<P: FP> PolygonLeafGeometry<P> for Capsule<P, 2> {}
+
+// Custom shapes fall back to their mbr
+impl<P: FP> PolygonLeafGeometry<P> for Shapes<P, 2> {
+    fn contained_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        match *self {
+            Shapes::Point(ref point) => point.contained_by_polygon(polygon),
+            Shapes::LineSegment(ref linesegment) => linesegment.contained_by_polygon(polygon),
+            Shapes::Rect(ref rect) => rect.contained_by_polygon(polygon),
+            Shapes::LineString(ref linestring) => linestring.contained_by_polygon(polygon),
+            Shapes::Custom(ref custom) => polygon.contains_mbr(&extents_of(custom.as_ref())),
+        }
+    }
+
+    fn overlapped_by_polygon(&self, polygon: &Polygon<P>) -> bool {
+        match *self {
+            Shapes::Point(ref point) => point.overlapped_by_polygon(polygon),
+            Shapes::LineSegment(ref linesegment) => linesegment.overlapped_by_polygon(polygon),
+            Shapes::Rect(ref rect) => rect.overlapped_by_polygon(polygon),
+            Shapes::LineString(ref linestring) => linestring.overlapped_by_polygon(polygon),
+            Shapes::Custom(ref custom) => {
+                extents_of(custom.as_ref()).overlapped_by_polygon(polygon)
+            }
+        }
+    }
+}
+
+fn extents_of<P: Scalar, const DIM: usize, G>(geometry: &G) -> Rect<P, DIM>
+where
+    G: MbrLeafGeometry<P, DIM> + ?Sized,
+{
+    let mut mbr = Rect::max_inverted();
+    geometry.expand_mbr_to_fit(&mut mbr);
+    mbr
+}
+
+fn coords<P: FP, const DIM: usize>(vertices: &[Point<P, DIM>]) -> Vec<[P; DIM]> {
+    vertices.iter().map(|vertex| vertex.coords).collect()
+}
+
+/// The signed area of a ring, by the shoelace formula. Positive when counter-clockwise
+fn ring_area<P: FP, I>(ring: I) -> P
+where
+    I: IntoIterator<Item = [P; 2]>,
+    I::IntoIter: Clone,
+{
+    let two: P = FromPrimitive::from_usize(2).unwrap();
+    let ring = ring.into_iter();
+    let doubled = ring
+        .clone()
+        .zip(ring.cycle().skip(1))
+        .fold(Zero::zero(), |area: P, (a, b)| {
+            area + a[0] * b[1] - b[0] * a[1]
+        });
+    doubled / two
+}
+
+/// Clip a convex or planar polygon to the mbr, one face at a time.
+/// Points on a face are kept, so a polygon touching the mbr clips to its touching points
+///
+/// Algorithm described by Sutherland, I. E.; Hodgman, G. W. (1974). "Reentrant Polygon Clipping"
+fn clip_polygon<P: FP, const DIM: usize>(
+    mut clipped: Vec<[P; DIM]>,
+    mbr: &Rect<P, DIM>,
+) -> Vec<[P; DIM]> {
+    for (axis, &(lo, hi)) in mbr.iter().enumerate() {
+        clipped = clip_to_face(&clipped, axis, lo, P::one());
+        clipped = clip_to_face(&clipped, axis, hi, -P::one());
+    }
+    clipped
+}
+
+/// Clip the polygon to the side of the face along the axis that sign points to, keeping points on the face
+fn clip_to_face<P: FP, const DIM: usize>(
+    clipped: &[[P; DIM]],
+    axis: usize,
+    face: P,
+    sign: P,
+) -> Vec<[P; DIM]> {
+    // positive when inside the face
+    let inside = |coords: &[P; DIM]| (coords[axis] - face) * sign;
+    let mut next = Vec::with_capacity(clipped.len() + 2);
+    let len = clipped.len();
+    for (i, cur) in clipped.iter().enumerate() {
+        let prev = &clipped[(i + len - 1) % len];
+        let (d_prev, d_cur) = (inside(prev), inside(cur));
+        if (d_prev >= Zero::zero()) != (d_cur >= Zero::zero()) {
+            let t = d_prev / (d_prev - d_cur);
+            let mut crossing = *prev;
+            for (coord, &to) in crossing.iter_mut().zip(cur.iter()) {
+                *coord = *coord + t * (to - *coord);
+            }
+            // exactly on the face, so later faces at the same coordinate keep it
+            crossing[axis] = face;
+            next.push(crossing);
+        }
+        if d_cur >= Zero::zero() {
+            next.push(*cur);
+        }
+    }
+    next
+}
+
+/// The shortest distance between a point, segment or triangle and the mbr
+fn hull_distance_from_mbr<P: FP, const DIM: usize>(corners: &[[P; DIM]], mbr: &Rect<P, DIM>) -> P {
+    match *corners {
+        [x] => mbr.distance_from_point(&Point::new(x)),
+        [x, y] => LineSegment::new(x, y).min_distance_from_mbr(mbr),
+        [a, b, c] => Triangle::new(a, b, c).min_distance_from_mbr(mbr),
+        _ => unreachable!("hulls have 1 to 3 corners"),
+    }
+}
+
+/// The shortest distance between the convex hulls of two sets of corners.
+/// The closest pair lies inside some face of each hull, so each pair of faces is solved over their affine hulls
+/// and kept when the solution lies inside both. Distances within rounding of 0 are taken as touching
+fn hull_distance<P: FP, const DIM: usize>(a: &[[P; DIM]], b: &[[P; DIM]]) -> P {
+    let zero: P = Zero::zero();
+    let tolerance: P = FromPrimitive::from_usize(64).unwrap();
+    let face = |corners: &[[P; DIM]], bits: usize| -> Vec<[P; DIM]> {
+        corners
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| bits & (1 << i) != 0)
+            .map(|(_, &corner)| corner)
+            .collect()
+    };
+    let inside = |weights: &[P]| {
+        weights.iter().all(|&w| w >= zero)
+            && weights.iter().fold(zero, |sum, &w| sum + w) <= One::one()
+    };
+    let mut min_dist: P = Bounded::max_value();
+    for bits in 1..1usize << a.len() {
+        for other_bits in 1..1usize << b.len() {
+            let (face, other_face) = (face(a, bits), face(b, other_bits));
+            // from the first corner of each face towards its others, the other face's reversed
+            let directions: Vec<[P; DIM]> = face[1..]
+                .iter()
+                .map(|corner| sub(corner, &face[0]))
+                .chain(
+                    other_face[1..]
+                        .iter()
+                        .map(|corner| sub(&other_face[0], corner)),
+                )
+                .collect();
+            let offset = sub(&face[0], &other_face[0]);
+            let weights = match least_squares(&directions, &offset) {
+                Some(weights) => weights,
+                None => continue,
+            };
+            let (along, other_along) = weights.split_at(face.len() - 1);
+            if !inside(along) || !inside(other_along) {
+                continue;
+            }
+            let mut gap = offset;
+            for (direction, &w) in directions.iter().zip(weights.iter()) {
+                for (coord, &d) in gap.iter_mut().zip(direction.iter()) {
+                    *coord += w * d;
+                }
+            }
+            min_dist = Float::min(min_dist, dot(&gap, &gap).sqrt());
+        }
+    }
+    let scale = a
+        .iter()
+        .chain(b.iter())
+        .flat_map(|corner| corner.iter())
+        .fold(zero, |scale, &coord| Float::max(scale, Float::abs(coord)));
+    if min_dist <= tolerance * Float::epsilon() * scale {
+        zero
+    } else {
+        min_dist
+    }
+}
+
+/// The weights minimising `|offset + sum(weight * direction)|`, solving the normal equations by Gauss-Jordan elimination.
+/// None when the directions are linearly dependent
+fn least_squares<P: FP, const DIM: usize>(
+    directions: &[[P; DIM]],
+    offset: &[P; DIM],
+) -> Option<Vec<P>> {
+    let tolerance: P = FromPrimitive::from_usize(64).unwrap();
+    let n = directions.len();
+    let mut rows: Vec<Vec<P>> = directions
+        .iter()
+        .map(|u| {
+            let mut row: Vec<P> = directions.iter().map(|v| dot(u, v)).collect();
+            row.push(-dot(u, offset));
+            row
+        })
+        .collect();
+    let scale = rows
+        .iter()
+        .enumerate()
+        .fold(Zero::zero(), |scale, (i, row)| Float::max(scale, row[i]));
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by_key(|&row| OrderedFloat(Float::abs(rows[row][col])))
+            .unwrap_or_else(|| unreachable!("the pivot column is in range"));
+        if Float::abs(rows[pivot][col]) <= tolerance * Float::epsilon() * scale {
+            return None;
+        }
+        rows.swap(col, pivot);
+        let pivot_row = rows[col].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i != col {
+                let factor = row[col] / pivot_row[col];
+                for (value, &p) in row.iter_mut().zip(pivot_row.iter()) {
+                    *value = *value - factor * p;
+                }
+            }
+        }
+    }
+    Some(
+        rows.iter()
+            .enumerate()
+            .map(|(i, row)| row[n] / row[i])
+            .collect(),
+    )
+}
+
+/// Whether two 2-dimensional segments share any point, ends included.
+/// Crossings are found by orientation so rounding can't hide them
+fn segments_touch<P: FP, const DIM: usize>(a: &[[P; DIM]], b: &[[P; DIM]]) -> bool {
+    let zero = P::zero();
+    let cross = |o: &[P; DIM], p: &[P; DIM], q: &[P; DIM]| {
+        (p[0] - o[0]) * (q[1] - o[1]) - (p[1] - o[1]) * (q[0] - o[0])
+    };
+    let on_segment = |segment: &[[P; DIM]], z: &[P; DIM]| {
+        let (x, y) = (&segment[0], &segment[1]);
+        cross(x, y, z) == zero
+            && (0..2).all(|axis| {
+                Float::min(x[axis], y[axis]) <= z[axis] && z[axis] <= Float::max(x[axis], y[axis])
+            })
+    };
+    let straddles = |u: P, v: P| (u > zero && v < zero) || (u < zero && v > zero);
+    (straddles(cross(&a[0], &a[1], &b[0]), cross(&a[0], &a[1], &b[1]))
+        && straddles(cross(&b[0], &b[1], &a[0]), cross(&b[0], &b[1], &a[1])))
+        || on_segment(a, &b[0])
+        || on_segment(a, &b[1])
+        || on_segment(b, &a[0])
+        || on_segment(b, &a[1])
+}
+
+fn sub<P: FP, const DIM: usize>(a: &[P; DIM], b: &[P; DIM]) -> [P; DIM] {
+    let mut diff = *a;
+    for (coord, &b) in diff.iter_mut().zip(b.iter()) {
+        *coord = *coord - b;
+    }
+    diff
+}
+
+fn dot<P: FP, const DIM: usize>(a: &[P; DIM], b: &[P; DIM]) -> P {
+    izip!(a.iter(), b.iter()).fold(Zero::zero(), |sum, (&a, &b)| sum + a * b)
+}
+
+fn min_entry<P: FP>(a: Option<P>, b: Option<P>) -> Option<P> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Float::min(a, b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// The volume of an n-dimensional ball, by V(n) = V(n - 2) * 2 * pi * r^2 / n
+fn ball_volume<P: FP>(dim: usize, radius: P) -> P {
+    let two: P = FromPrimitive::from_usize(2).unwrap();
+    let pi: P = FromPrimitive::from_f64(std::f64::consts::PI).unwrap();
+    // V(0) and V(1)
+    let (mut before, mut volume): (P, P) = (One::one(), two * radius);
+    if dim == 0 {
+        return before;
+    }
+    for n in 2..=dim {
+        let next = before * two * pi * radius * radius / FromPrimitive::from_usize(n).unwrap();
+        before = volume;
+        volume = next;
+    }
+    volume
+}
+
+/// The smallest t where the ray touches the ball, if it does
+fn ball_ray_entry<P: FP, const DIM: usize>(
+    center: &Point<P, DIM>,
+    radius: P,
+    ray: &Ray<P, DIM>,
+) -> Option<P> {
+    let offset = sub(&ray.origin.coords, &center.coords);
+    let a = dot(&ray.direction, &ray.direction);
+    let b = dot(&offset, &ray.direction);
+    let c = dot(&offset, &offset) - radius * radius;
+    if c <= Zero::zero() {
+        return Some(Zero::zero());
+    }
+    let discriminant = b * b - a * c;
+    if b > Zero::zero() || discriminant < Zero::zero() {
+        // starting outside and pointing away, or missing entirely
+        return None;
+    }
+    Some((-b - discriminant.sqrt()) / a)
+}
+
+/// Estimate the shared area by the overlap of the leaf's extents, scaled by how much of them the leaf fills.
+/// Exact when the leaf is contained by the mbr or misses it, otherwise only an approximation
+fn estimated_area_overlapped<P: FP, const DIM: usize, G>(geometry: &G, mbr: &Rect<P, DIM>) -> P
+where
+    G: MbrLeafGeometry<P, DIM>,
+{
+    if geometry.contained_by_mbr(mbr) {
+        return geometry.area();
+    }
+    if !geometry.overlapped_by_mbr(mbr) {
+        return Zero::zero();
+    }
+    let mut extents = Rect::max_inverted();
+    geometry.expand_mbr_to_fit(&mut extents);
+    let extents_area = extents.area();
+    if extents_area <= Zero::zero() {
+        return Zero::zero();
+    }
+    extents.area_overlapped_with_mbr(mbr) * geometry.area() / extents_area
+}
+
+/// Whether two points are close enough to count as a ray hit, allowing for rounding relative to their magnitude
+fn ray_touches<P: FP, const DIM: usize>(a: &Point<P, DIM>, b: &Point<P, DIM>) -> bool {
+    let tolerance: P = FromPrimitive::from_usize(64).unwrap();
+    let (dist_sq, scale) = izip!(a.deref(), b.deref()).fold(
+        (Zero::zero(), One::one()),
+        |(dist_sq, scale): (P, P), (&x, &y)| {
+            (
+                dist_sq + pow(x - y, 2),
+                Float::max(scale, Float::max(Float::abs(x), Float::abs(y))),
+            )
+        },
+    );
+    dist_sq <= pow(tolerance * Float::epsilon() * scale, 2)
+}
+
+/// The squared distance between the centers of the extents and the mbr
+fn distance_sq_from_mbr_center<P: Scalar, const DIM: usize, E>(
+    extents: E,
+    mbr: &Rect<P, DIM>,
+) -> P::Measure
+where
+    E: Iterator<Item = (P, P)>,
+{
+    let two: P::Measure = FromPrimitive::from_usize(2).unwrap();
+    izip!(mbr.deref(), extents).fold(Zero::zero(), |distance, (&(x1, y1), (x2, y2))| {
+        let center = (x1.to_measure() + y1.to_measure()) / two;
+        distance + pow(center - (x2.to_measure() + y2.to_measure()) / two, 2)
+    })
+}
+
+/// MINMAXDIST for any geometry where every combination of its per-axis extents is part of the geometry.
+///
+/// For each axis, the closest face is paired with the farthest faces of the remaining axes.
+/// Distances to a face are measured from the nearest extent along its axis, so the extents may describe either a point or a rect
+fn min_max_distance_from_extents<P: FP, const DIM: usize, E>(extents: E, mbr: &Rect<P, DIM>) -> P
+where
+    E: Iterator<Item = (P, P)>,
+{
+    let mut near = [P::zero(); DIM];
+    let mut far = [P::zero(); DIM];
+    for (near, far, (x, y), &(lo, hi)) in
+        izip!(near.iter_mut(), far.iter_mut(), extents, mbr.deref())
+    {
+        let distance_to = |face: P| {
+            if face < x {
+                x - face
+            } else if y < face {
+                face - y
+            } else {
+                Zero::zero()
+            }
+        };
+        *near = pow(Float::min(distance_to(lo), distance_to(hi)), 2);
+        *far = pow(Float::max(distance_to(lo), distance_to(hi)), 2);
+    }
+    let far_sum = far.iter().fold(P::zero(), |sum, &far| sum + far);
+    izip!(near.iter(), far.iter())
+        .map(|(&near, &far)| far_sum - far + near)
+        .fold(Bounded::max_value(), Float::min)
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{
+        Capsule, GeometryError, LineSegment, MultiPolygon, Point, Polygon, Ray, Rect, Shapes,
+        Sphere, Triangle,
+    };
+    use std::ops::Deref;
+
+    const ONE: [f64; 3] = [1.0f64, 1.0f64, 1.0f64];
+    const ZERO: [f64; 3] = [0.0f64, 0.0f64, 0.0f64];
+    const NEG_ONE: [f64; 3] = [-1.0f64, -1.0f64, -1.0f64];
+    const NEG_TWO: [f64; 3] = [-2.0f64, -2.0f64, -2.0f64];
+
+    // distance of [0.5, 0.5, 0.5]
+    const EXPECTED_DISTANCE: f64 = 0.86602540378f64;
+
+    #[test]
+    fn point() {
+        let point: Point<f64, 3> = Point::new([0.0; 3]);
+        for i in point.deref() {
+            assert_relative_eq!(0.0f64, i);
+        }
+
+        let zero: Shapes<f64, 3> = Shapes::Point(Point::from_slice(&ZERO));
+        let one: Shapes<f64, 3> = Shapes::Point(Point::from_slice(&ONE));
+        let neg_one: Shapes<f64, 3> = Shapes::Point(Point::from_slice(&NEG_ONE));
+
+        // Shape tests
+        // dim
+        assert_eq!(ZERO.len(), zero.dim());
+        // area
+        assert_relative_eq!(0.0f64, zero.area());
+        // min/max for axis
+        for (i, item) in ZERO.iter().enumerate() {
+            assert_relative_eq!(*item, zero.min_for_axis(i));
+            assert_relative_eq!(*item, zero.max_for_axis(i));
+        }
+        let mut bounding_mbr = Rect::max_inverted();
+        // expand_mbr_to_fit
+        zero.expand_mbr_to_fit(&mut bounding_mbr);
+        one.expand_mbr_to_fit(&mut bounding_mbr);
+        for (i, (x, y)) in izip!(&ZERO, &ONE).enumerate() {
+            assert_relative_eq!(*x, bounding_mbr.min_for_axis(i));
+            assert_relative_eq!(*y, bounding_mbr.max_for_axis(i));
+        }
+
+        // distance_from_mbr_center
+        assert_relative_eq!(
+            EXPECTED_DISTANCE,
+            zero.distance_from_mbr_center(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+        assert_relative_eq!(
+            EXPECTED_DISTANCE * EXPECTED_DISTANCE,
+            zero.distance_sq_from_mbr_center(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+
+        // distance_from_point
+        let zero_point = Point::from_slice(&ZERO);
+        assert_relative_eq!(0.0f64, zero.distance_from_point(&zero_point));
+        assert_relative_eq!(
+            3.0f64.sqrt(),
+            one.distance_from_point(&zero_point),
+            max_relative = 0.00000001
+        );
+
+        // min_distance_from_mbr
+        assert_relative_eq!(0.0f64, one.min_distance_from_mbr(&bounding_mbr));
+        assert_relative_eq!(
+            3.0f64.sqrt(),
+            neg_one.min_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+
+        // min_max_distance_from_mbr
+        assert_relative_eq!(
+            2.0f64.sqrt(),
+            zero.min_max_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+        assert_relative_eq!(
+            3.0f64,
+            neg_one.min_max_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+
+        // contained_by_mbr
+        assert!(zero.contained_by_mbr(&bounding_mbr));
+        assert!(one.contained_by_mbr(&bounding_mbr));
+        assert!(!neg_one.contained_by_mbr(&bounding_mbr));
+
+        // overlapped_by_mbr
+        assert!(zero.overlapped_by_mbr(&bounding_mbr));
+        assert!(one.overlapped_by_mbr(&bounding_mbr));
         assert!(!neg_one.overlapped_by_mbr(&bounding_mbr));
 
         // area_overlapped_with_mbr
@@ -386,6 +2215,50 @@ mod tests {
             neg_one_one.distance_from_mbr_center(&bounding_mbr),
             max_relative = 0.00000001
         );
+        assert_relative_eq!(
+            EXPECTED_DISTANCE * EXPECTED_DISTANCE,
+            neg_one_one.distance_sq_from_mbr_center(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+
+        // distance_from_point
+        let zero_point = Point::from_slice(&ZERO);
+        assert_relative_eq!(0.0f64, neg_one_one.distance_from_point(&zero_point));
+        assert_relative_eq!(
+            3.0f64.sqrt(),
+            neg_two_neg_one.distance_from_point(&zero_point),
+            max_relative = 0.00000001
+        );
+        // closest to the middle of the segment
+        let off_segment = Point::new([1.0f64, 0.0f64, 0.0f64]);
+        assert_relative_eq!(
+            (2.0f64 / 3.0f64).sqrt(),
+            zero_one.distance_from_point(&off_segment),
+            max_relative = 0.00000001
+        );
+
+        // min_distance_from_mbr
+        assert_relative_eq!(0.0f64, neg_one_one.min_distance_from_mbr(&bounding_mbr));
+        assert_relative_eq!(
+            3.0f64.sqrt(),
+            neg_two_neg_one.min_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+        // passes diagonally by the mbr's (0, 1) edge without touching it
+        let passing: Shapes<f64, 3> =
+            Shapes::LineSegment(LineSegment::new([-1.0, 1.5, 0.5], [0.5, 3.0, 0.5]));
+        assert_relative_eq!(
+            1.5f64 / 2.0f64.sqrt(),
+            passing.min_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+
+        // min_max_distance_from_mbr
+        assert_relative_eq!(
+            3.0f64,
+            neg_two_neg_one.min_max_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
 
         // contained_by_mbr
         assert!(zero_one.contained_by_mbr(&bounding_mbr));
@@ -396,6 +2269,18 @@ mod tests {
         assert!(zero_one.overlapped_by_mbr(&bounding_mbr));
         assert!(neg_one_one.overlapped_by_mbr(&bounding_mbr));
         assert!(!neg_two_neg_one.overlapped_by_mbr(&bounding_mbr));
+        // crosses the mbr with both ends outside of it
+        let through = LineSegment::new([-1.0f64, 0.5f64, 0.5f64], [2.0f64, 0.5f64, 0.5f64]);
+        assert!(through.overlapped_by_mbr(&bounding_mbr));
+        // the segment's own mbr overlaps, but the segment passes by the corner
+        let diagonal = LineSegment::new([0.5f64, 2.0f64, 0.5f64], [2.0f64, 0.5f64, 0.5f64]);
+        assert!(!diagonal.overlapped_by_mbr(&bounding_mbr));
+        // just touching the corner
+        let touching = LineSegment::new([0.0f64, 2.0f64, 0.5f64], [2.0f64, 0.0f64, 0.5f64]);
+        assert!(touching.overlapped_by_mbr(&bounding_mbr));
+        // parallel to a face, outside of it
+        let parallel = LineSegment::new([-1.0f64, 0.5f64, 1.5f64], [2.0f64, 0.5f64, 1.5f64]);
+        assert!(!parallel.overlapped_by_mbr(&bounding_mbr));
 
         // area_overlapped_with_mbr
         assert_relative_eq!(0.0f64, zero_one.area_overlapped_with_mbr(&bounding_mbr));
@@ -409,11 +2294,11 @@ mod tests {
         let g_neg_two = [-2.0f64, -2.0f64, -2.0f64];
 
         // contained
-        let zero_one = Rect::from_corners(g_zero.clone(), g_one.clone());
+        let zero_one = Rect::from_corners(g_zero, g_one);
         // overlapped
-        let neg_one_one = Rect::from_corners(g_neg_one.clone(), g_one.clone());
+        let neg_one_one = Rect::from_corners(g_neg_one, g_one);
         // outside
-        let neg_two_neg_one = Rect::from_corners(g_neg_two.clone(), g_neg_one.clone());
+        let neg_two_neg_one = Rect::from_corners(g_neg_two, g_neg_one);
 
         // Shape tests
         // dim
@@ -442,6 +2327,50 @@ mod tests {
             neg_one_one.distance_from_mbr_center(&bounding_mbr),
             max_relative = 0.00000001
         );
+        assert_relative_eq!(
+            EXPECTED_DISTANCE * EXPECTED_DISTANCE,
+            neg_one_one.distance_sq_from_mbr_center(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+
+        // distance_from_point
+        let half_point = Point::new([0.5f64, 0.5f64, 0.5f64]);
+        assert_relative_eq!(0.0f64, zero_one.distance_from_point(&half_point));
+        assert_relative_eq!(
+            (3.0f64 * 2.5f64 * 2.5f64).sqrt(),
+            neg_two_neg_one.distance_from_point(&Point::from_slice(&[1.5f64; 3])),
+            max_relative = 0.00000001
+        );
+
+        // min_distance_from_mbr
+        assert_relative_eq!(0.0f64, neg_one_one.min_distance_from_mbr(&bounding_mbr));
+        assert_relative_eq!(
+            3.0f64.sqrt(),
+            neg_two_neg_one.min_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+
+        // min_max_distance_from_mbr
+        assert_relative_eq!(
+            3.0f64,
+            neg_two_neg_one.min_max_distance_from_mbr(&bounding_mbr),
+            max_relative = 0.00000001
+        );
+        // an object rests on the face at x = 0, at most 1 away along x and, being within y = [0, 4], 1 away along y
+        let centered = Rect::from_corners([1.0f64, 1.0f64], [3.0f64, 3.0f64]);
+        let around = Rect::from_corners([0.0f64, 0.0f64], [4.0f64, 4.0f64]);
+        assert_relative_eq!(
+            2.0f64.sqrt(),
+            centered.min_max_distance_from_mbr(&around),
+            max_relative = 0.00000001
+        );
+        // off center, the near face along x is 1 away and the far face along y 2 away
+        let off_center = Rect::from_corners([1.0f64, 1.0f64], [1.5f64, 2.0f64]);
+        assert_relative_eq!(
+            5.0f64.sqrt(),
+            off_center.min_max_distance_from_mbr(&around),
+            max_relative = 0.00000001
+        );
 
         // contained_by_mbr
         assert!(zero_one.contained_by_mbr(&bounding_mbr));
@@ -457,4 +2386,663 @@ mod tests {
         assert_relative_eq!(1.0f64, zero_one.area_overlapped_with_mbr(&bounding_mbr));
         assert_relative_eq!(1.0f64, neg_one_one.area_overlapped_with_mbr(&bounding_mbr));
     }
+
+    #[test]
+    fn ray_entry() {
+        let ray = Ray::new(NEG_TWO, ONE);
+        let diagonal = Ray::new(ZERO, [1.0f64, 2.0f64, 3.0f64]);
+
+        // points on and off the ray
+        assert_relative_eq!(1.0f64, Point::new(NEG_ONE).ray_entry(&ray).unwrap());
+        assert_relative_eq!(3.0f64, Point::new(ONE).ray_entry(&ray).unwrap());
+        assert_relative_eq!(
+            0.1f64,
+            Point::new([0.1f64, 0.2f64, 0.3f64])
+                .ray_entry(&diagonal)
+                .unwrap()
+        );
+        assert!(Point::new([0.0f64, 0.0f64, 1.0f64])
+            .ray_entry(&ray)
+            .is_none());
+        // behind the origin
+        assert!(Point::new([-3.0f64; 3]).ray_entry(&ray).is_none());
+        assert_relative_eq!(0.0f64, Point::new(NEG_TWO).ray_entry(&ray).unwrap());
+
+        // crossing, parallel and skew segments
+        let crossing = LineSegment::new([0.0f64, -1.0f64, 0.0f64], [0.0f64, 1.0f64, 0.0f64]);
+        assert_relative_eq!(2.0f64, crossing.ray_entry(&ray).unwrap());
+        let collinear = LineSegment::new(ONE, [0.5f64; 3]);
+        assert_relative_eq!(2.5f64, collinear.ray_entry(&ray).unwrap());
+        let skew = LineSegment::new([0.0f64, -1.0f64, 0.5f64], [0.0f64, 1.0f64, 0.5f64]);
+        assert!(skew.ray_entry(&ray).is_none());
+        let behind = LineSegment::new([-3.0f64, -4.0f64, -3.0f64], [-3.0f64, 4.0f64, -3.0f64]);
+        assert!(behind.ray_entry(&ray).is_none());
+        let degenerate = LineSegment::new(ZERO, ZERO);
+        assert_relative_eq!(2.0f64, degenerate.ray_entry(&ray).unwrap());
+
+        // entering, starting inside and missing rects
+        let zero_one = Rect::from_corners(ZERO, ONE);
+        assert_relative_eq!(2.0f64, zero_one.ray_entry(&ray).unwrap());
+        assert_relative_eq!(0.0f64, zero_one.ray_entry(&diagonal).unwrap());
+        let neg_two_neg_one = Rect::from_corners(NEG_TWO, NEG_ONE);
+        assert!(neg_two_neg_one.ray_entry(&diagonal).is_none());
+        let axis_aligned = Ray::new([0.5f64, 0.5f64, -5.0f64], [0.0f64, 0.0f64, 2.0f64]);
+        assert_relative_eq!(2.5f64, zero_one.ray_entry(&axis_aligned).unwrap());
+        assert!(neg_two_neg_one.ray_entry(&axis_aligned).is_none());
+
+        let shape = Shapes::LineSegment(crossing);
+        assert_relative_eq!(2.0f64, shape.ray_entry(&ray).unwrap());
+    }
+
+    #[test]
+    fn max_distance_from_point() {
+        let origin = Point::new(ZERO);
+        assert_relative_eq!(
+            3.0f64.sqrt(),
+            Point::new(ONE).max_distance_from_point(&origin)
+        );
+        // the segment's far end, not the far corner of its mbr
+        let segment = LineSegment::new([1.0f64, 0.0f64, 0.0f64], [0.0f64, 2.0f64, 0.0f64]);
+        assert_relative_eq!(2.0f64, segment.max_distance_from_point(&origin));
+        let rect = Rect::from_corners(NEG_ONE, [2.0f64, 1.0f64, 1.0f64]);
+        assert_relative_eq!(6.0f64.sqrt(), rect.max_distance_from_point(&origin));
+        assert_relative_eq!(
+            2.0f64,
+            Shapes::LineSegment(segment).max_distance_from_point(&origin)
+        );
+    }
+
+    #[test]
+    fn polygon() {
+        let square = |lo: f64, hi: f64| vec![[lo, lo], [hi, lo], [hi, hi], [lo, hi]];
+        let holed = Polygon::new(square(0.0f64, 4.0f64), vec![square(1.0f64, 2.0f64)]);
+        assert_eq!(2, holed.dim());
+        assert_relative_eq!(15.0f64, holed.area());
+        assert_relative_eq!(4.0f64, holed.max_for_axis(1));
+
+        assert!(holed.contains_point(&Point::new([0.5f64, 0.5f64])));
+        assert!(!holed.contains_point(&Point::new([1.5f64, 1.5f64])));
+        assert!(holed.contains_point(&Point::new([1.0f64, 1.5f64])));
+        assert!(holed.contains_point(&Point::new([4.0f64, 4.0f64])));
+        assert!(!holed.contains_point(&Point::new([5.0f64, 0.0f64])));
+
+        assert_relative_eq!(
+            0.0f64,
+            holed.distance_from_point(&Point::new([3.0f64, 3.0f64]))
+        );
+        assert_relative_eq!(
+            0.5f64,
+            holed.distance_from_point(&Point::new([1.5f64, 1.5f64]))
+        );
+        assert_relative_eq!(
+            2.0f64,
+            holed.distance_from_point(&Point::new([6.0f64, 0.0f64]))
+        );
+        assert_relative_eq!(
+            32.0f64.sqrt(),
+            holed.max_distance_from_point(&Point::new([0.0f64, 0.0f64]))
+        );
+
+        let in_hole = Rect::from_corners([1.2f64, 1.2f64], [1.8f64, 1.8f64]);
+        assert!(!holed.overlapped_by_mbr(&in_hole));
+        assert_relative_eq!(0.2f64, holed.min_distance_from_mbr(&in_hole));
+        assert!(holed.overlapped_by_mbr(&Rect::from_corners([3.0f64, 3.0f64], [5.0f64, 5.0f64])));
+        assert!(holed.overlapped_by_mbr(&Rect::from_corners([0.5f64, 0.5f64], [0.6f64, 0.6f64])));
+        assert!(holed.overlapped_by_mbr(&Rect::from_corners([-1.0f64, -1.0f64], [5.0f64, 5.0f64])));
+        assert!(!holed.overlapped_by_mbr(&Rect::from_corners([5.0f64, 5.0f64], [6.0f64, 6.0f64])));
+        // touching the boundary only reaches the interior's edge, like a rect
+        let touching = Rect::from_corners([4.0f64, 1.0f64], [5.0f64, 2.0f64]);
+        assert!(!holed.overlapped_by_mbr(&touching));
+        assert!(holed.touches_mbr(&touching));
+        assert_relative_eq!(0.0f64, holed.min_distance_from_mbr(&touching));
+        assert!(!holed.overlapped_by_mbr(&Rect::from_corners([1.0f64, 1.5f64], [1.5f64, 1.5f64])));
+        assert!(holed.overlapped_by_mbr(&Rect::from_corners([0.5f64, 1.5f64], [1.5f64, 1.5f64])));
+        assert!(holed.overlapped_by_mbr(&Rect::from_corners([3.0f64, 3.0f64], [3.0f64, 3.0f64])));
+        assert!(!holed.overlapped_by_mbr(&Rect::from_corners([4.0f64, 4.0f64], [4.0f64, 4.0f64])));
+
+        // polygons against polygons, where the hole has to be accounted for
+        let hole_filler = Polygon::new(square(1.0f64, 2.0f64), vec![]);
+        assert!(!holed.interior_overlaps_polygon(&hole_filler));
+        assert!(!holed.contains_polygon(&hole_filler));
+        assert!(!hole_filler.overlapped_by_polygon(&holed));
+        let over_hole = Polygon::new(square(0.5f64, 2.5f64), vec![]);
+        assert!(holed.interior_overlaps_polygon(&over_hole));
+        assert!(!holed.contains_polygon(&over_hole));
+        let beside_hole = Polygon::new(square(2.5f64, 3.5f64), vec![]);
+        assert!(holed.contains_polygon(&beside_hole));
+        assert!(beside_hole.contained_by_polygon(&holed));
+        assert!(holed.interior_overlaps_polygon(&holed));
+        assert!(holed.contains_polygon(&holed));
+
+        // segments only have to touch
+        let across_hole = LineSegment::new([1.2f64, 1.5f64], [1.8f64, 1.5f64]);
+        assert!(!across_hole.overlapped_by_polygon(&holed));
+        let to_hole = LineSegment::new([1.5f64, 1.5f64], [1.5f64, 2.0f64]);
+        assert!(to_hole.overlapped_by_polygon(&holed));
+        assert!(!to_hole.contained_by_polygon(&holed));
+        let path = LineString::new(vec![[0.5f64, 0.5f64], [3.5f64, 0.5f64], [3.5f64, 3.5f64]]);
+        assert!(path.contained_by_polygon(&holed));
+        assert!(
+            !LineString::new(vec![[0.5f64, 0.5f64], [1.5f64, 1.5f64]]).contained_by_polygon(&holed)
+        );
+        assert!(holed.contained_by_mbr(&Rect::from_corners([0.0f64, 0.0f64], [4.0f64, 4.0f64])));
+        assert!(!holed.contained_by_mbr(&Rect::from_corners([0.0f64, 0.0f64], [3.0f64, 4.0f64])));
+
+        assert_relative_eq!(
+            3.0f64,
+            holed.area_overlapped_with_mbr(&Rect::from_corners([0.0f64, 0.0f64], [2.0f64, 2.0f64]))
+        );
+        assert_relative_eq!(
+            1.0f64,
+            holed.area_overlapped_with_mbr(&Rect::from_corners(
+                [-1.0f64, -1.0f64],
+                [1.0f64, 1.0f64]
+            ))
+        );
+        assert_relative_eq!(0.0f64, holed.area_overlapped_with_mbr(&in_hole));
+
+        // concave, so clipping and containment can't rely on the corners alone
+        let u_shape = Polygon::new(
+            vec![
+                [0.0f64, 0.0f64],
+                [3.0f64, 0.0f64],
+                [3.0f64, 3.0f64],
+                [2.0f64, 3.0f64],
+                [2.0f64, 1.0f64],
+                [1.0f64, 1.0f64],
+                [1.0f64, 3.0f64],
+                [0.0f64, 3.0f64],
+            ],
+            vec![],
+        );
+        assert_relative_eq!(7.0f64, u_shape.area());
+        assert_relative_eq!(
+            2.0f64,
+            u_shape
+                .area_overlapped_with_mbr(&Rect::from_corners([0.5f64, 0.5f64], [2.5f64, 2.0f64]))
+        );
+        assert!(!u_shape.contains_mbr(&Rect::from_corners([0.5f64, 0.5f64], [2.5f64, 2.0f64])));
+        assert!(u_shape.contains_mbr(&Rect::from_corners([0.5f64, 0.2f64], [2.5f64, 0.8f64])));
+        assert!(u_shape.contains_mbr(&Rect::from_corners([0.0f64, 0.0f64], [1.0f64, 3.0f64])));
+        assert!(u_shape.contains_mbr(&Rect::from_corners([1.0f64, 1.0f64], [2.0f64, 1.0f64])));
+        assert!(!u_shape.contains_mbr(&Rect::from_corners([1.2f64, 1.2f64], [1.8f64, 1.8f64])));
+        // degenerate rects have no interior for the notch to cross
+        assert!(!u_shape.contains_mbr(&Rect::from_corners([0.5f64, 2.0f64], [2.5f64, 2.0f64])));
+        assert!(u_shape.contains_mbr(&Rect::from_corners([0.5f64, 0.5f64], [2.5f64, 0.5f64])));
+        assert!(u_shape.contains_mbr(&Rect::from_corners([0.0f64, 1.0f64], [3.0f64, 1.0f64])));
+        assert!(!u_shape.contains_mbr(&Rect::from_corners([1.5f64, 2.0f64], [1.5f64, 2.0f64])));
+        assert!(!holed.contains_mbr(&Rect::from_corners([0.5f64, 1.5f64], [3.5f64, 1.5f64])));
+
+        let ray = Ray::new([-1.0f64, 0.5f64], [1.0f64, 0.0f64]);
+        assert_relative_eq!(1.0f64, holed.ray_entry(&ray).unwrap());
+        let ray = Ray::new([1.5f64, 1.5f64], [1.0f64, 0.0f64]);
+        assert_relative_eq!(0.5f64, holed.ray_entry(&ray).unwrap());
+        let ray = Ray::new([0.5f64, 0.5f64], [0.0f64, 1.0f64]);
+        assert_relative_eq!(0.0f64, holed.ray_entry(&ray).unwrap());
+        let ray = Ray::new([5.0f64, 0.5f64], [1.0f64, 0.0f64]);
+        assert_eq!(None, holed.ray_entry(&ray));
+
+        // the closing vertex may be repeated
+        let mut closed = square(0.0f64, 1.0f64);
+        closed.push([0.0f64, 0.0f64]);
+        assert_eq!(4, Polygon::new(closed, vec![]).exterior().len());
+        assert_eq!(
+            Err(GeometryError::TooFewVertices),
+            Polygon::try_new(
+                vec![[0.0f64, 0.0f64], [1.0f64, 1.0f64], [0.0f64, 0.0f64]],
+                vec![]
+            )
+        );
+        assert_eq!(
+            Err(GeometryError::NonFiniteCoordinate),
+            Polygon::try_new(
+                vec![[0.0f64, 0.0f64], [1.0f64, f64::NAN], [0.0f64, 1.0f64]],
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn multi_polygon() {
+        let square = |lo: f64, hi: f64| vec![[lo, lo], [hi, lo], [hi, hi], [lo, hi]];
+        let multi = MultiPolygon::new(vec![
+            Polygon::new(square(0.0f64, 1.0f64), vec![]),
+            Polygon::new(square(2.0f64, 4.0f64), vec![square(2.5f64, 3.5f64)]),
+        ]);
+        assert_relative_eq!(4.0f64, multi.area());
+        assert_relative_eq!(4.0f64, multi.max_for_axis(0));
+        assert!(multi.contains_point(&Point::new([0.5f64, 0.5f64])));
+        assert!(!multi.contains_point(&Point::new([1.5f64, 1.5f64])));
+        assert!(!multi.contains_point(&Point::new([3.0f64, 3.0f64])));
+        assert_relative_eq!(
+            0.5f64,
+            multi.distance_from_point(&Point::new([1.5f64, 0.5f64]))
+        );
+        let between = Rect::from_corners([1.2f64, 1.2f64], [1.8f64, 1.8f64]);
+        assert!(!multi.overlapped_by_mbr(&between));
+        assert!(multi.overlapped_by_mbr(&Rect::from_corners([0.5f64, 0.5f64], [2.5f64, 2.5f64])));
+        assert_relative_eq!(
+            0.75f64,
+            multi.area_overlapped_with_mbr(&Rect::from_corners([0.5f64, 0.0f64], [2.5f64, 2.5f64]))
+        );
+        let ray = Ray::new([1.5f64, 0.5f64], [1.0f64, 1.0f64]);
+        assert_relative_eq!(1.5f64, multi.ray_entry(&ray).unwrap());
+        assert_eq!(
+            Err(GeometryError::Empty),
+            MultiPolygon::<f64>::try_new(vec![])
+        );
+    }
+
+    #[test]
+    fn sphere() {
+        let sphere = Sphere::new(ZERO, 1.0f64);
+        assert_relative_eq!(4.0f64 / 3.0f64 * std::f64::consts::PI, sphere.area());
+        assert_relative_eq!(
+            std::f64::consts::PI * 4.0f64,
+            Sphere::new([0.0f64; 2], 2.0f64).area()
+        );
+        assert_relative_eq!(2.0f64, Sphere::new([0.0f64; 1], 1.0f64).area());
+        assert_relative_eq!(-1.0f64, sphere.min_for_axis(2));
+        assert_relative_eq!(
+            2.0f64,
+            sphere.distance_from_point(&Point::new([3.0f64, 0.0f64, 0.0f64]))
+        );
+        assert_relative_eq!(
+            4.0f64,
+            sphere.max_distance_from_point(&Point::new([3.0f64, 0.0f64, 0.0f64]))
+        );
+
+        // the mbr overlaps the sphere's extents, but not the sphere
+        let corner = Rect::from_corners([0.8f64; 3], [2.0f64; 3]);
+        assert!(!sphere.overlapped_by_mbr(&corner));
+        assert_relative_eq!(
+            0.8f64 * 3.0f64.sqrt() - 1.0f64,
+            sphere.min_distance_from_mbr(&corner)
+        );
+        assert!(sphere.overlapped_by_mbr(&Rect::from_corners(
+            [0.5f64, 0.5f64, -1.0f64],
+            [2.0f64, 2.0f64, 1.0f64]
+        )));
+        assert!(sphere.overlapped_by_mbr(&Rect::from_corners(
+            [1.0f64, 0.0f64, 0.0f64],
+            [2.0f64, 0.0f64, 0.0f64]
+        )));
+        assert!(sphere.contained_by_mbr(&Rect::from_corners(NEG_ONE, ONE)));
+        assert!(!sphere.contained_by_mbr(&Rect::from_corners(NEG_ONE, [1.0f64, 1.0f64, 0.9f64])));
+        assert_relative_eq!(
+            sphere.area(),
+            sphere.area_overlapped_with_mbr(&Rect::from_corners(NEG_TWO, ONE))
+        );
+        assert_relative_eq!(0.0f64, sphere.area_overlapped_with_mbr(&corner));
+
+        assert_relative_eq!(
+            2.0f64,
+            sphere
+                .ray_entry(&Ray::new(
+                    [-3.0f64, 0.0f64, 0.0f64],
+                    [1.0f64, 0.0f64, 0.0f64]
+                ))
+                .unwrap()
+        );
+        assert_relative_eq!(
+            0.0f64,
+            sphere
+                .ray_entry(&Ray::new(
+                    [0.5f64, 0.0f64, 0.0f64],
+                    [1.0f64, 0.0f64, 0.0f64]
+                ))
+                .unwrap()
+        );
+        assert_eq!(
+            None,
+            sphere.ray_entry(&Ray::new(
+                [-3.0f64, 2.0f64, 0.0f64],
+                [1.0f64, 0.0f64, 0.0f64]
+            ))
+        );
+        assert_eq!(
+            None,
+            sphere.ray_entry(&Ray::new(
+                [-3.0f64, 0.0f64, 0.0f64],
+                [-1.0f64, 0.0f64, 0.0f64]
+            ))
+        );
+        assert_eq!(
+            Err(GeometryError::NegativeRadius),
+            Sphere::try_new(ZERO, -1.0f64)
+        );
+    }
+
+    #[test]
+    fn capsule() {
+        let capsule = Capsule::new(ZERO, [2.0f64, 0.0f64, 0.0f64], 1.0f64);
+        let pi = std::f64::consts::PI;
+        assert_relative_eq!(4.0f64 / 3.0f64 * pi + 2.0f64 * pi, capsule.area());
+        assert_relative_eq!(3.0f64, capsule.max_for_axis(0));
+        assert_relative_eq!(-1.0f64, capsule.min_for_axis(1));
+        assert_relative_eq!(
+            1.0f64,
+            capsule.distance_from_point(&Point::new([1.0f64, 2.0f64, 0.0f64]))
+        );
+
+        // past the rounded end, inside the capsule's extents
+        let end = Rect::from_corners([2.6f64, 0.6f64, 0.6f64], [3.0f64, 1.0f64, 1.0f64]);
+        assert!(!capsule.overlapped_by_mbr(&end));
+        assert!(capsule.min_distance_from_mbr(&end) > 0.0f64);
+        assert!(capsule.overlapped_by_mbr(&Rect::from_corners(
+            [1.0f64, 0.9f64, -0.1f64],
+            [1.5f64, 2.0f64, 0.1f64]
+        )));
+        assert!(
+            capsule.contained_by_mbr(&Rect::from_corners([-1.0f64; 3], [3.0f64, 1.0f64, 1.0f64]))
+        );
+        assert!(
+            !capsule.contained_by_mbr(&Rect::from_corners([-1.0f64; 3], [2.9f64, 1.0f64, 1.0f64]))
+        );
+
+        let ray_entry =
+            |origin: [f64; 3], direction: [f64; 3]| capsule.ray_entry(&Ray::new(origin, direction));
+        assert_relative_eq!(
+            2.0f64,
+            ray_entry([1.0f64, -3.0f64, 0.0f64], [0.0f64, 1.0f64, 0.0f64]).unwrap()
+        );
+        assert_relative_eq!(
+            2.0f64,
+            ray_entry([-3.0f64, 0.0f64, 0.0f64], [1.0f64, 0.0f64, 0.0f64]).unwrap()
+        );
+        let through_side = 3.0f64 - 0.75f64.sqrt();
+        assert_relative_eq!(
+            through_side,
+            ray_entry([1.0f64, -3.0f64, 0.5f64], [0.0f64, 1.0f64, 0.0f64]).unwrap()
+        );
+        assert_relative_eq!(
+            through_side,
+            ray_entry([2.5f64, -3.0f64, 0.0f64], [0.0f64, 1.0f64, 0.0f64]).unwrap()
+        );
+        assert_relative_eq!(
+            0.0f64,
+            ray_entry([1.0f64, 0.5f64, 0.0f64], [0.0f64, 1.0f64, 0.0f64]).unwrap()
+        );
+        assert_eq!(
+            None,
+            ray_entry([3.5f64, -3.0f64, 0.0f64], [0.0f64, 1.0f64, 0.0f64])
+        );
+        assert_eq!(
+            None,
+            ray_entry([1.0f64, 3.0f64, 0.0f64], [0.0f64, 1.0f64, 0.0f64])
+        );
+    }
+
+    #[test]
+    fn triangle() {
+        let triangle = Triangle::new(ZERO, [2.0f64, 0.0f64, 0.0f64], [0.0f64, 2.0f64, 0.0f64]);
+        assert_relative_eq!(2.0f64, triangle.area());
+        assert_relative_eq!(2.0f64, triangle.max_for_axis(1));
+        assert_relative_eq!(0.0f64, triangle.max_for_axis(2));
+
+        let slab = |lo: [f64; 2], hi: [f64; 2]| {
+            Rect::from_corners([lo[0], lo[1], -1.0f64], [hi[0], hi[1], 1.0f64])
+        };
+        assert!(triangle.overlapped_by_mbr(&slab([0.9f64, 0.9f64], [2.0f64, 2.0f64])));
+        // beyond the hypotenuse, inside the triangle's extents
+        assert!(!triangle.overlapped_by_mbr(&slab([1.1f64, 1.1f64], [2.0f64, 2.0f64])));
+        assert!(
+            !triangle.overlapped_by_mbr(&Rect::from_corners([0.0f64, 0.0f64, 0.1f64], [1.0f64; 3]))
+        );
+        // touching a corner, and degenerate mbrs
+        assert!(triangle.overlapped_by_mbr(&Rect::from_corners(
+            [2.0f64, -1.0f64, 0.0f64],
+            [3.0f64, 0.0f64, 1.0f64]
+        )));
+        assert!(triangle.overlapped_by_mbr(&Rect::from_corners(
+            [0.5f64, 0.5f64, 0.0f64],
+            [0.5f64, 0.5f64, 0.0f64]
+        )));
+        assert!(!triangle.overlapped_by_mbr(&Rect::from_corners(
+            [0.5f64, 0.5f64, 0.1f64],
+            [0.5f64, 0.5f64, 0.1f64]
+        )));
+        assert!(triangle.contained_by_mbr(&slab([0.0f64, 0.0f64], [2.0f64, 2.0f64])));
+
+        assert_relative_eq!(
+            1.0f64,
+            triangle.area_overlapped_with_mbr(&slab([0.0f64, 0.0f64], [1.0f64, 1.0f64]))
+        );
+        assert_relative_eq!(
+            0.5f64,
+            triangle.area_overlapped_with_mbr(&slab([1.0f64, 0.0f64], [2.0f64, 1.0f64]))
+        );
+        assert_relative_eq!(
+            0.0f64,
+            triangle.area_overlapped_with_mbr(&slab([1.1f64, 1.1f64], [2.0f64, 2.0f64]))
+        );
+
+        assert_relative_eq!(
+            1.0f64,
+            triangle.distance_from_point(&Point::new([0.5f64, 0.5f64, 1.0f64]))
+        );
+        assert_relative_eq!(
+            1.0f64,
+            triangle.distance_from_point(&Point::new([3.0f64, 0.0f64, 0.0f64]))
+        );
+        assert_relative_eq!(
+            2.0f64.sqrt(),
+            triangle.distance_from_point(&Point::new([2.0f64, 2.0f64, 0.0f64]))
+        );
+        assert_relative_eq!(
+            1.0f64,
+            triangle.min_distance_from_mbr(&Rect::from_corners(
+                [0.2f64, 0.2f64, 1.0f64],
+                [0.4f64, 0.4f64, 2.0f64]
+            ))
+        );
+        assert_relative_eq!(
+            1.0f64,
+            triangle.min_distance_from_mbr(&slab([3.0f64, -1.0f64], [4.0f64, 1.0f64]))
+        );
+
+        let ray_entry = |origin: [f64; 3], direction: [f64; 3]| {
+            triangle.ray_entry(&Ray::new(origin, direction))
+        };
+        assert_relative_eq!(
+            2.0f64,
+            ray_entry([0.5f64, 0.5f64, 2.0f64], [0.0f64, 0.0f64, -1.0f64]).unwrap()
+        );
+        assert_relative_eq!(
+            1.0f64,
+            ray_entry([0.5f64, 0.5f64, 2.0f64], [0.0f64, 0.0f64, -2.0f64]).unwrap()
+        );
+        // in the triangle's plane
+        assert_relative_eq!(
+            1.5f64,
+            ray_entry([3.0f64, 0.5f64, 0.0f64], [-1.0f64, 0.0f64, 0.0f64]).unwrap()
+        );
+        assert_relative_eq!(
+            0.0f64,
+            ray_entry([0.5f64, 0.5f64, 0.0f64], [0.0f64, 0.0f64, 1.0f64]).unwrap()
+        );
+        assert_eq!(
+            None,
+            ray_entry([0.5f64, 0.5f64, 2.0f64], [0.0f64, 0.0f64, 1.0f64])
+        );
+        assert_eq!(
+            None,
+            ray_entry([1.5f64, 1.5f64, 2.0f64], [0.0f64, 0.0f64, -1.0f64])
+        );
+
+        let flat = Triangle::new([0.0f64, 0.0f64], [2.0f64, 0.0f64], [0.0f64, 2.0f64]);
+        assert_relative_eq!(2.0f64, flat.area());
+        assert_relative_eq!(
+            1.0f64,
+            flat.ray_entry(&Ray::new([-1.0f64, 0.5f64], [1.0f64, 0.0f64]))
+                .unwrap()
+        );
+        assert!(!flat.overlapped_by_mbr(&Rect::from_corners([1.1f64, 1.1f64], [2.0f64, 2.0f64])));
+        // collinear corners
+        let sliver = Triangle::new(ZERO, ONE, [2.0f64; 3]);
+        assert_relative_eq!(0.0f64, sliver.area());
+        assert_relative_eq!(
+            1.0f64,
+            sliver.distance_from_point(&Point::new([3.0f64, 2.0f64, 2.0f64]))
+        );
+
+        // closest across the interiors of both, away from the triangle's edges and the mbr's corners
+        let wide = Triangle::new(
+            [5.0f64, 0.0f64, -10.0f64, -10.0f64],
+            [5.0f64, 0.0f64, 10.0f64, -10.0f64],
+            [5.0f64, 0.0f64, 0.0f64, 10.0f64],
+        );
+        let mbr = Rect::from_corners(
+            [0.0f64, -1.0f64, 0.0f64, 0.0f64],
+            [1.0f64, 1.0f64, 1.0f64, 1.0f64],
+        );
+        assert_relative_eq!(4.0f64, wide.min_distance_from_mbr(&mbr));
+        // 64 dimensions has as many mbr corners as a u64 has values
+        let high = Triangle::new([2.0f64; 64], [3.0f64; 64], [4.0f64; 64]);
+        assert_relative_eq!(
+            8.0f64,
+            high.min_distance_from_mbr(&Rect::from_corners([0.0f64; 64], [1.0f64; 64]))
+        );
+        // the triangle's interior against the mbr's edge from (1, 1, 0) to (1, 1, 1), across the plane x + y = 4
+        let leaning = Triangle::new(
+            [4.0f64, 0.0f64, -5.0f64],
+            [0.0f64, 4.0f64, -5.0f64],
+            [2.0f64, 2.0f64, 5.0f64],
+        );
+        assert_relative_eq!(
+            2.0f64.sqrt(),
+            leaning.min_distance_from_mbr(&Rect::from_corners(ZERO, ONE))
+        );
+
+        // never farther than any point of the triangle, so nearest neighbour pruning can't skip it
+        let mut seed = 17u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as f64 / (1u64 << 31) as f64 * 4.0f64 - 2.0f64
+        };
+        for _ in 0..200 {
+            let mut corner = || [next(), next(), next()];
+            let triangle = Triangle::new(corner(), corner(), corner());
+            let mbr = Rect::from_corners(corner(), corner());
+            let distance = triangle.min_distance_from_mbr(&mbr);
+            let mut sampled = f64::MAX;
+            for i in 0..=40 {
+                for j in 0..=(40 - i) {
+                    let (u, v) = (i as f64 / 40.0f64, j as f64 / 40.0f64);
+                    let mut coords = triangle.a.coords;
+                    let (b, c) = (triangle.b.deref(), triangle.c.deref());
+                    for (coord, &b, &c) in izip!(coords.iter_mut(), b, c) {
+                        *coord += u * (b - *coord) + v * (c - *coord);
+                    }
+                    sampled = sampled.min(mbr.distance_from_point(&Point::new(coords)));
+                }
+            }
+            assert!(distance <= sampled + 1e-12f64, "{} > {}", distance, sampled);
+            assert!(sampled - distance < 0.1f64, "{} is far below {}", distance, sampled);
+        }
+    }
+
+    #[test]
+    fn leaf_parts() {
+        let sphere = Sphere::new(ZERO, 1.0f64);
+        let capsule = Capsule::new([3.0f64, -1.0f64, 0.0f64], [3.0f64, 1.0f64, 0.0f64], 0.5f64);
+        let part = |geometry: &dyn MbrLeafGeometry<f64, 3>| geometry.parts().remove(0);
+        assert_relative_eq!(1.5f64, part(&sphere).distance(&part(&capsule)));
+        assert_relative_eq!(
+            0.5f64,
+            part(&capsule).distance(&part(&Rect::from_corners(
+                [4.0f64, -1.0f64, -1.0f64],
+                [5.0f64; 3]
+            )))
+        );
+        assert_relative_eq!(
+            0.0f64,
+            part(&sphere).distance(&part(&Point::new([0.5f64, 0.5f64, 0.5f64])))
+        );
+
+        // the closest pair lies inside both triangles, which sit in planes that are 1 apart in 5 dimensions
+        let flat = LeafPart::Hull(
+            vec![
+                [-1.0f64, -1.0f64, 0.0f64, 0.0f64, 0.0f64],
+                [2.0f64, -1.0f64, 0.0f64, 0.0f64, 0.0f64],
+                [-1.0f64, 2.0f64, 0.0f64, 0.0f64, 0.0f64],
+            ],
+            0.0f64,
+        );
+        let skew = LeafPart::Hull(
+            vec![
+                [0.0f64, 0.0f64, -1.0f64, -1.0f64, 1.0f64],
+                [0.0f64, 0.0f64, 2.0f64, -1.0f64, 1.0f64],
+                [0.0f64, 0.0f64, -1.0f64, 2.0f64, 1.0f64],
+            ],
+            0.0f64,
+        );
+        assert_relative_eq!(1.0f64, flat.distance(&skew));
+        assert_relative_eq!(1.0f64, skew.distance(&flat));
+    }
+
+    #[test]
+    fn custom_shapes() {
+        let sphere = Sphere::new(ZERO, 1.0f64);
+        let custom: Shapes<f64, 3> = Shapes::Custom(Box::new(sphere.clone()));
+        assert_relative_eq!(sphere.area(), custom.area());
+        assert_relative_eq!(-1.0f64, custom.min_for_axis(2));
+        assert_relative_eq!(
+            1.0f64,
+            custom.distance_from_point(&Point::new([2.0f64, 0.0f64, 0.0f64]))
+        );
+        assert!(custom.overlapped_by_mbr(&Rect::from_corners([0.5f64; 3], ONE)));
+        assert!(!custom.overlapped_by_mbr(&Rect::from_corners([0.6f64; 3], ONE)));
+        let mut mbr = Rect::max_inverted();
+        custom.expand_mbr_to_fit(&mut mbr);
+        assert_eq!(Rect::from_corners(NEG_ONE, ONE), mbr);
+
+        // equality needs the same concrete type
+        assert_eq!(custom, custom.clone());
+        assert_ne!(custom, Shapes::Custom(Box::new(Sphere::new(ZERO, 2.0f64))));
+        assert_ne!(
+            custom,
+            Shapes::Custom(Box::new(Rect::from_corners(NEG_ONE, ONE)))
+        );
+        assert_ne!(custom, Shapes::Rect(Rect::from_corners(NEG_ONE, ONE)));
+        if let Shapes::Custom(ref custom) = custom {
+            assert_eq!(
+                Some(&sphere),
+                custom.as_any().downcast_ref::<Sphere<f64, 3>>()
+            );
+        }
+    }
+
+    #[test]
+    fn integer_coordinates() {
+        let everything = Rect::from_corners([i64::MIN; 3], [i64::MAX; 3]);
+        assert_relative_eq!(2.0f64.powi(192), everything.area());
+        let tile = Rect::from_corners([0u16, 0u16], [10u16, 20u16]);
+        assert_relative_eq!(200.0f64, tile.area());
+        assert_relative_eq!(
+            25.0f64,
+            tile.area_overlapped_with_mbr(&Rect::from_corners([5u16, 15u16], [30u16, 30u16]))
+        );
+        assert_relative_eq!(
+            0.0f64,
+            tile.area_overlapped_with_mbr(&Rect::from_corners([11u16, 0u16], [30u16, 30u16]))
+        );
+        // differences that would underflow as u16
+        assert_relative_eq!(
+            5.0f64,
+            tile.distance_from_point(&Point::new([13u16, 24u16]))
+        );
+        assert_relative_eq!(
+            5.0f64,
+            Point::new([0u16, 0u16]).distance_from_point(&Point::new([3u16, 4u16]))
+        );
+        assert_relative_eq!(
+            5.0f64,
+            tile.min_distance_from_mbr(&Rect::from_corners([13u16, 24u16], [20u16, 30u16]))
+        );
+        assert!(tile.contained_by_mbr(&Rect::max()));
+        assert!(!Point::new([11u16, 0u16]).overlapped_by_mbr(&tile));
+        let ray = Ray::new([-1.0f64, 5.0f64], [1.0f64, 0.0f64]);
+        assert_relative_eq!(1.0f64, tile.ray_entry(&ray).unwrap());
+    }
 }
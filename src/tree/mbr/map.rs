@@ -5,36 +5,78 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::fmt::{self, Debug};
 use std::mem;
 use std::ops::Deref;
 use std::rc::Rc;
 use std::slice::{Iter as SliceIter, IterMut as SliceIterMut};
+use std::vec::IntoIter as VecIntoIter;
 
-use crate::geometry::Rect;
+use ordered_float::OrderedFloat;
+
+use crate::geometry::{Point, Ray, Rect};
+use crate::tree::mbr::bulk;
+use crate::tree::mbr::entry::find_path;
 use crate::tree::mbr::index::{IndexInsert, IndexRemove};
-use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode, MbrQuery, MbrRectQuery, RTreeNode};
-use crate::FP;
+use crate::tree::mbr::{
+    MbrLeaf, MbrLeafGeometry, MbrNode, MbrNodeChildren, MbrQuery, MbrRectQuery, NodeChildren,
+    RTreeNode,
+};
+use crate::Scalar;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Errors returned by `MbrMap::try_insert` and `MbrMap::try_bulk_load`. Each variant returns ownership of the rejected geometry and item
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertError<LG, T> {
+    /// The geometry has a NaN or infinite coordinate
+    NonFiniteGeometry(LG, T),
+}
+
+impl<LG, T> fmt::Display for InsertError<LG, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InsertError::NonFiniteGeometry(..) => write!(f, "geometry coordinates must be finite"),
+        }
+    }
+}
+
+impl<LG: Debug, T: Debug> Error for InsertError<LG, T> {}
 
 /// The generic container interface for spatial maps. Will, at the very least, be able to support R, R+, R*, and X trees
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound(
+            deserialize = "NODE: MbrNodeChildren + Deserialize<'de>, I: Deserialize<'de>, R: Deserialize<'de>"
+        ),
+        try_from = "crate::serde_tree::RawMbrMap<NODE, I, R>"
+    )
+)]
 pub struct MbrMap<NODE, I, R> {
-    insert_index: I,
-    remove_index: R,
-    root: NODE,
-    len: usize,
+    pub(crate) insert_index: I,
+    pub(crate) remove_index: R,
+    pub(crate) root: NODE,
+    pub(crate) len: usize,
 }
 
-impl<P: FP, const DIM: usize, LG, I, R, T> MbrMap<RTreeNode<P, DIM, LG, T>, I, R>
+impl<P: Scalar, const DIM: usize, LG, I, R, T, NODE> MbrMap<NODE, I, R>
 where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
     LG: MbrLeafGeometry<P, DIM>,
-    I: IndexInsert<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
-    R: IndexRemove<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>, I>,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
 {
     /// Create a new MbrMap with the given insert and remove indexes
-    pub fn new(insert_index: I, remove_index: R) -> MbrMap<RTreeNode<P, DIM, LG, T>, I, R> {
+    pub fn new(insert_index: I, remove_index: R) -> MbrMap<NODE, I, R> {
         let new_root = insert_index.new_leaves();
         MbrMap {
-            insert_index: insert_index,
-            remove_index: remove_index,
+            insert_index,
+            remove_index,
             root: new_root,
             len: 0,
         }
@@ -49,16 +91,91 @@ where
         self.len += 1;
     }
 
-    /// Remove all items whose shapes are accepted by the query. Returns removed entries.
-    pub fn remove<Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>>(
+    /// Insert an item, returning the child indexes leading from the root to its leaf
+    pub(crate) fn insert_at(&mut self, geometry: LG, item: T) -> Vec<usize> {
+        let (root, path) = self.insert_index.insert_into_root_at(
+            mem::replace(&mut self.root, self.insert_index.new_no_alloc_leaves()),
+            MbrLeaf::new(geometry, item),
+        );
+        self.root = root;
+        self.len += 1;
+        path
+    }
+
+    /// Remove the leaf at the child indexes leading to it from the root
+    pub(crate) fn remove_at(&mut self, path: &[usize]) -> (LG, T) {
+        let (root, leaf) = self.remove_index.remove_at_path(
+            mem::replace(&mut self.root, self.insert_index.new_no_alloc_leaves()),
+            &self.insert_index,
+            path,
+        );
+        self.root = root;
+        self.len -= 1;
+        leaf.extract()
+    }
+
+    /// Insert an item, handing the geometry and item back if the geometry has a non-finite coordinate.
+    /// Such geometry would otherwise poison the mbrs of every node above it
+    pub fn try_insert(&mut self, geometry: LG, item: T) -> Result<(), InsertError<LG, T>> {
+        if !geometry.is_finite() {
+            return Err(InsertError::NonFiniteGeometry(geometry, item));
+        }
+        self.insert(geometry, item);
+        Ok(())
+    }
+
+    /// Bulk load the items, packing the whole tree with Sort-Tile-Recursive.
+    /// Entries already in the map are repacked along with the new ones.
+    /// Much faster than inserting each item and produces a tree with less overlap.
+    /// Like `insert`, the geometry isn't checked, see `try_bulk_load`
+    pub fn bulk_load<ITER: IntoIterator<Item = (LG, T)>>(&mut self, items: ITER) {
+        let leaves = items
+            .into_iter()
+            .map(|(geometry, item)| MbrLeaf::new(geometry, item))
+            .collect();
+        self.repack(leaves);
+    }
+
+    /// Bulk load the items, handing back the first geometry and item with a non-finite coordinate.
+    /// On error the map is left unchanged and the rest of the items are dropped
+    pub fn try_bulk_load<ITER: IntoIterator<Item = (LG, T)>>(
         &mut self,
-        query: Q,
-    ) -> Vec<(LG, T)> {
+        items: ITER,
+    ) -> Result<(), InsertError<LG, T>> {
+        let items = items.into_iter();
+        let mut leaves = Vec::with_capacity(items.size_hint().0);
+        for (geometry, item) in items {
+            if !geometry.is_finite() {
+                return Err(InsertError::NonFiniteGeometry(geometry, item));
+            }
+            leaves.push(MbrLeaf::new(geometry, item));
+        }
+        self.repack(leaves);
+        Ok(())
+    }
+
+    /// Pack the leaves and every entry already in the map into a new root
+    fn repack(&mut self, mut leaves: Vec<MbrLeaf<P, DIM, LG, T>>) {
+        leaves.reserve(self.len);
+        bulk::collect_leaves(
+            mem::replace(&mut self.root, self.insert_index.new_no_alloc_leaves()),
+            &mut leaves,
+        );
+        self.len = leaves.len();
+        self.root = bulk::str_pack(
+            leaves,
+            self.insert_index.preferred_min(),
+            self.insert_index.max(),
+        );
+    }
+
+    /// Remove all items whose shapes are accepted by the query. Returns removed entries.
+    pub fn remove<Q: MbrQuery<P, DIM, LG, T, NODE>>(&mut self, query: Q) -> Vec<(LG, T)> {
         self.retain(query, |_| false)
     }
 
     /// Remove all items whose shapes are accepted by the query and where f(&T) returns false. Returns removed entries
-    pub fn retain<Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>, F: FnMut(&T) -> bool>(
+    pub fn retain<Q: MbrQuery<P, DIM, LG, T, NODE>, F: FnMut(&T) -> bool>(
         &mut self,
         query: Q,
         f: F,
@@ -78,6 +195,44 @@ where
         removed_extract
     }
 
+    /// Remove the first entry whose geometry equals `geometry` and whose item equals `item`.
+    /// Only levels whose mbr contains the geometry are searched, stopping at the first match. Returns the removed entry
+    pub fn remove_one(&mut self, geometry: &LG, item: &T) -> Option<(LG, T)>
+    where
+        LG: PartialEq,
+        T: PartialEq,
+    {
+        let mut path = Vec::new();
+        if find_path(&self.root, geometry, &mut |other| other == item, &mut path) {
+            Some(self.remove_at(&path))
+        } else {
+            None
+        }
+    }
+
+    /// Move the first entry whose geometry equals `old` and whose item is accepted by `matcher` to the `new` geometry.
+    /// The geometry is swapped in place when it still fits inside its leaf level's mbr,
+    /// only tightening the mbrs along the path to that level. Otherwise the entry is removed and reinserted.
+    /// Returns false if no entry matched
+    pub fn update_geometry<F: FnMut(&T) -> bool>(
+        &mut self,
+        old: &LG,
+        mut matcher: F,
+        new: LG,
+    ) -> bool
+    where
+        LG: PartialEq,
+    {
+        let (new, path) = match update_in_place(&mut self.root, old, &mut matcher, new) {
+            UpdateResult::Updated => return true,
+            UpdateResult::NotFound(_) => return false,
+            UpdateResult::DoesNotFit(new, path) => (new, path),
+        };
+        let (_, item) = self.remove_at(&path);
+        self.insert(new, item);
+        true
+    }
+
     /// Whether the map is empty
     pub fn is_empty(&self) -> bool {
         self.len == 0
@@ -95,69 +250,323 @@ where
     }
 
     /// Iter for the map
-    pub fn iter(&self) -> Iter<P, DIM, LG, T, MbrRectQuery<P, DIM>> {
+    pub fn iter(&self) -> Iter<'_, P, DIM, LG, T, MbrRectQuery<P, DIM>, NODE> {
         Iter::new(MbrRectQuery::Overlaps(Rect::max()), &self.root)
     }
 
     /// IterMut for the map
-    pub fn iter_mut(&mut self) -> IterMut<P, DIM, LG, T, MbrRectQuery<P, DIM>> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, P, DIM, LG, T, MbrRectQuery<P, DIM>, NODE> {
         IterMut::new(MbrRectQuery::Overlaps(Rect::max()), &mut self.root)
     }
 
     /// Iter for the map with a given query
-    pub fn iter_query<Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>>(
+    pub fn iter_query<Q: MbrQuery<P, DIM, LG, T, NODE>>(
         &self,
         query: Q,
-    ) -> Iter<P, DIM, LG, T, Q> {
+    ) -> Iter<'_, P, DIM, LG, T, Q, NODE> {
         Iter::new(query, &self.root)
     }
 
     /// IterMut for the map with a given query
-    pub fn iter_query_mut<Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>>(
+    pub fn iter_query_mut<Q: MbrQuery<P, DIM, LG, T, NODE>>(
         &mut self,
         query: Q,
-    ) -> IterMut<P, DIM, LG, T, Q> {
+    ) -> IterMut<'_, P, DIM, LG, T, Q, NODE> {
         IterMut::new(query, &mut self.root)
     }
+
+    /// Find the k items closest to the point, ordered by increasing distance.
+    pub fn nearest<'tree>(
+        &'tree self,
+        point: &Point<P, DIM>,
+        k: usize,
+    ) -> Vec<(&'tree LG, &'tree T)>
+    where
+        P: 'tree,
+    {
+        self.iter_nearest(point)
+            .take(k)
+            .map(|(_, geometry, item)| (geometry, item))
+            .collect()
+    }
+
+    /// Iter for the map in order of increasing distance from the point
+    pub fn iter_nearest(&self, point: &Point<P, DIM>) -> NearestIter<'_, P, DIM, LG, T, NODE> {
+        NearestIter::new(point.clone(), &self.root)
+    }
+
+    /// Iter for the map in order of increasing ray parameter t, over every entry the ray touches.
+    /// Only levels whose mbr the ray passes through are visited
+    pub fn raycast(&self, ray: &Ray<P::Measure, DIM>) -> RaycastIter<'_, P, DIM, LG, T, NODE> {
+        RaycastIter::new(ray.clone(), &self.root)
+    }
+
+    /// The first entry the ray touches, with its ray parameter t
+    pub fn raycast_first<'tree>(
+        &'tree self,
+        ray: &Ray<P::Measure, DIM>,
+    ) -> Option<(P::Measure, &'tree LG, &'tree T)>
+    where
+        P: 'tree,
+    {
+        self.raycast(ray).next()
+    }
+
+    /// Empty the map, iterating over the removed entries. The tree is detached in O(1) and walked once.
+    /// Entries that aren't iterated are dropped with the iterator
+    pub fn drain(&mut self) -> IntoIter<P, DIM, LG, T, NODE> {
+        let root = mem::replace(&mut self.root, self.insert_index.new_leaves());
+        let len = mem::replace(&mut self.len, 0);
+        IntoIter::new(root, len)
+    }
+
+    /// Remove all items whose shapes are accepted by the query, iterating over the removed entries.
+    /// Costs the same as `remove`, as the remaining tree is condensed
+    pub fn drain_query<Q: MbrQuery<P, DIM, LG, T, NODE>>(
+        &mut self,
+        query: Q,
+    ) -> VecIntoIter<(LG, T)> {
+        self.remove(query).into_iter()
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, I, R, T, NODE> IntoIterator for MbrMap<NODE, I, R>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM>,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    type Item = (LG, T);
+    type IntoIter = IntoIter<P, DIM, LG, T, NODE>;
+
+    fn into_iter(self) -> IntoIter<P, DIM, LG, T, NODE> {
+        IntoIter::new(self.root, self.len)
+    }
+}
+
+impl<'tree, P: Scalar, const DIM: usize, LG, I, R, T, NODE> IntoIterator
+    for &'tree MbrMap<NODE, I, R>
+where
+    P: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    type Item = (&'tree LG, &'tree T);
+    type IntoIter = Iter<'tree, P, DIM, LG, T, MbrRectQuery<P, DIM>, NODE>;
+
+    fn into_iter(self) -> Iter<'tree, P, DIM, LG, T, MbrRectQuery<P, DIM>, NODE> {
+        self.iter()
+    }
+}
+
+impl<'tree, P: Scalar, const DIM: usize, LG, I, R, T, NODE> IntoIterator
+    for &'tree mut MbrMap<NODE, I, R>
+where
+    P: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    type Item = (&'tree LG, &'tree mut T);
+    type IntoIter = IterMut<'tree, P, DIM, LG, T, MbrRectQuery<P, DIM>, NODE>;
+
+    fn into_iter(self) -> IterMut<'tree, P, DIM, LG, T, MbrRectQuery<P, DIM>, NODE> {
+        self.iter_mut()
+    }
+}
+
+/// Inserts each entry in turn, the same as calling `insert` for each
+impl<P: Scalar, const DIM: usize, LG, I, R, T, NODE> Extend<(LG, T)> for MbrMap<NODE, I, R>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM>,
+    I: IndexInsert<P, DIM, LG, T, NODE>,
+    R: IndexRemove<P, DIM, LG, T, NODE, I>,
+{
+    fn extend<ITER: IntoIterator<Item = (LG, T)>>(&mut self, items: ITER) {
+        for (geometry, item) in items {
+            self.insert(geometry, item);
+        }
+    }
 }
 
 type LeafIter<'tree, P, const DIM: usize, LG, T> = SliceIter<'tree, MbrLeaf<P, DIM, LG, T>>;
 
+/// Owning iterator over every entry of a map, in no particular order
+pub struct IntoIter<P: Scalar, const DIM: usize, LG, T, NODE = RTreeNode<P, DIM, LG, T>>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    level_stack: Vec<VecIntoIter<NODE>>,
+    leaf_iter: VecIntoIter<MbrLeaf<P, DIM, LG, T>>,
+    len: usize,
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> IntoIter<P, DIM, LG, T, NODE>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    /// Constructor
+    fn new(root: NODE, len: usize) -> IntoIter<P, DIM, LG, T, NODE> {
+        IntoIter {
+            level_stack: vec![vec![root].into_iter()],
+            leaf_iter: Vec::new().into_iter(),
+            len,
+        }
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> Iterator for IntoIter<P, DIM, LG, T, NODE>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    type Item = (LG, T);
+
+    fn next(&mut self) -> Option<(LG, T)> {
+        loop {
+            if let Some(leaf) = self.leaf_iter.next() {
+                self.len -= 1;
+                return Some(leaf.extract());
+            }
+            let node = loop {
+                match self.level_stack.last_mut()?.next() {
+                    Some(node) => break node,
+                    None => {
+                        self.level_stack.pop();
+                    }
+                }
+            };
+            match node.into_children() {
+                NodeChildren::Leaves(children) => self.leaf_iter = children.into_iter(),
+                NodeChildren::Levels(children) => self.level_stack.push(children.into_iter()),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> ExactSizeIterator for IntoIter<P, DIM, LG, T, NODE>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+}
+
+/// Outcome of `update_in_place`. Hands the new geometry back when it wasn't used,
+/// along with the child indexes leading to the matching leaf when it didn't fit
+enum UpdateResult<LG> {
+    Updated,
+    NotFound(LG),
+    DoesNotFit(LG, Vec<usize>),
+}
+
+/// Search the levels containing `old` for a matching leaf and swap in the new geometry if the leaf's level still contains it.
+/// Mbrs are only recalculated for the levels on the path to an updated leaf
+fn update_in_place<P: Scalar, const DIM: usize, LG, T, NODE, F>(
+    node: &mut NODE,
+    old: &LG,
+    matcher: &mut F,
+    new: LG,
+) -> UpdateResult<LG>
+where
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+    LG: MbrLeafGeometry<P, DIM> + PartialEq,
+    F: FnMut(&T) -> bool,
+{
+    let fits = new.contained_by_mbr(node.mbr());
+    let mut mbr = Rect::max_inverted();
+    match node.children_mut() {
+        NodeChildren::Leaves(children) => {
+            let index = match children
+                .iter()
+                .position(|leaf| leaf.geometry == *old && matcher(&leaf.item))
+            {
+                Some(index) => index,
+                None => return UpdateResult::NotFound(new),
+            };
+            if !fits {
+                return UpdateResult::DoesNotFit(new, vec![index]);
+            }
+            children[index].geometry = new;
+            for child in children.iter() {
+                child.expand_mbr_to_fit(&mut mbr);
+            }
+        }
+        NodeChildren::Levels(children) => {
+            let searched = children
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, child)| old.contained_by_mbr(child.mbr()))
+                .try_fold(new, |new, (index, child)| {
+                    match update_in_place(child, old, matcher, new) {
+                        UpdateResult::NotFound(unused) => Ok(unused),
+                        UpdateResult::DoesNotFit(new, mut path) => {
+                            path.insert(0, index);
+                            Err(UpdateResult::DoesNotFit(new, path))
+                        }
+                        UpdateResult::Updated => Err(UpdateResult::Updated),
+                    }
+                });
+            match searched {
+                Ok(unused) => return UpdateResult::NotFound(unused),
+                Err(UpdateResult::Updated) => (),
+                Err(does_not_fit) => return does_not_fit,
+            }
+            for child in children.iter() {
+                child.expand_mbr_to_fit(&mut mbr);
+            }
+        }
+    }
+    *node.mbr_mut() = mbr;
+    UpdateResult::Updated
+}
+
 /// Iterate through all `MbrNode::Leaves` matching a query
-struct LevelIter<'tree, P: FP, const DIM: usize, LG, T, Q>
+struct LevelIter<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE = RTreeNode<P, DIM, LG, T>>
 where
+    P: 'tree,
     LG: 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     query: Rc<Q>,
-    root: &'tree RTreeNode<P, DIM, LG, T>,
-    level_stack: Vec<SliceIter<'tree, RTreeNode<P, DIM, LG, T>>>,
+    root: &'tree NODE,
+    level_stack: Vec<SliceIter<'tree, NODE>>,
     finished: bool,
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> LevelIter<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> LevelIter<'tree, P, DIM, LG, T, Q, NODE>
 where
+    P: 'tree,
     LG: MbrLeafGeometry<P, DIM> + 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     /// Constructor
-    fn new(
-        query: Rc<Q>,
-        root: &'tree RTreeNode<P, DIM, LG, T>,
-    ) -> LevelIter<'tree, P, DIM, LG, T, Q> {
+    fn new(query: Rc<Q>, root: &'tree NODE) -> LevelIter<'tree, P, DIM, LG, T, Q, NODE> {
         if root.is_empty() || !query.accept_level(root) {
             return LevelIter {
-                query: query,
-                root: root,
+                query,
+                root,
                 level_stack: Vec::with_capacity(0),
                 finished: true,
             };
         }
         LevelIter {
-            query: query,
-            root: root,
+            query,
+            root,
             level_stack: Vec::new(),
             finished: false,
         }
@@ -166,7 +575,7 @@ where
     /// Select the next matching leaves level
     fn next_leaves(
         &mut self,
-        mut m_iter: SliceIter<'tree, RTreeNode<P, DIM, LG, T>>,
+        mut m_iter: SliceIter<'tree, NODE>,
     ) -> Option<LeafIter<'tree, P, DIM, LG, T>> {
         let mut iter_node = m_iter.next();
         while let Some(node) = iter_node {
@@ -175,9 +584,9 @@ where
                 continue;
             }
             self.level_stack.push(m_iter);
-            match *node {
-                RTreeNode::Leaves { ref children, .. } => return Some(children.iter()),
-                RTreeNode::Level { ref children, .. } => {
+            match node.children() {
+                NodeChildren::Leaves(children) => return Some(children.iter()),
+                NodeChildren::Levels(children) => {
                     let next = self.next_leaves(children.iter());
                     if next.is_none() {
                         m_iter = self.level_stack.pop().unwrap();
@@ -192,11 +601,14 @@ where
     }
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> Iterator for LevelIter<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> Iterator
+    for LevelIter<'tree, P, DIM, LG, T, Q, NODE>
 where
+    P: 'tree,
     LG: MbrLeafGeometry<P, DIM> + 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     type Item = LeafIter<'tree, P, DIM, LG, T>;
 
@@ -205,19 +617,19 @@ where
             return None;
         }
         if self.level_stack.is_empty() {
-            match *self.root {
-                RTreeNode::Leaves { ref children, .. } => {
+            match self.root.children() {
+                NodeChildren::Leaves(children) => {
                     self.finished = true;
                     return Some(children.iter());
                 }
-                RTreeNode::Level { ref children, .. } => {
+                NodeChildren::Levels(children) => {
                     self.level_stack.push(children.iter());
                 }
             }
         }
         let mut m_iter = self.level_stack.pop().unwrap();
         let mut next = self.next_leaves(m_iter);
-        while let None = next {
+        while next.is_none() {
             if !self.level_stack.is_empty() {
                 m_iter = self.level_stack.pop().unwrap();
                 next = self.next_leaves(m_iter);
@@ -233,54 +645,55 @@ where
 type LeafIterMut<'tree, P, const DIM: usize, LG, T> = SliceIterMut<'tree, MbrLeaf<P, DIM, LG, T>>;
 
 /// Iterate mutably through all `MbrNode::Leaves` matching a query
-struct LevelIterMut<'tree, P: FP, const DIM: usize, LG, T, Q>
+struct LevelIterMut<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE = RTreeNode<P, DIM, LG, T>>
 where
+    P: 'tree,
     LG: 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     query: Rc<Q>,
-    root: &'tree mut RTreeNode<P, DIM, LG, T>,
-    level_stack: Vec<SliceIterMut<'tree, RTreeNode<P, DIM, LG, T>>>,
+    root: &'tree mut NODE,
+    level_stack: Vec<SliceIterMut<'tree, NODE>>,
     finished: bool,
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> LevelIterMut<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> LevelIterMut<'tree, P, DIM, LG, T, Q, NODE>
 where
+    P: 'tree,
     LG: MbrLeafGeometry<P, DIM> + 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     /// Constructor
-    fn new(
-        query: Rc<Q>,
-        root: &'tree mut RTreeNode<P, DIM, LG, T>,
-    ) -> LevelIterMut<'tree, P, DIM, LG, T, Q> {
+    fn new(query: Rc<Q>, root: &'tree mut NODE) -> LevelIterMut<'tree, P, DIM, LG, T, Q, NODE> {
         if root.is_empty() || !query.accept_level(root) {
             return LevelIterMut {
-                query: query,
-                root: root,
+                query,
+                root,
                 level_stack: Vec::with_capacity(0),
                 finished: true,
             };
         }
         LevelIterMut {
-            query: query,
-            root: root,
+            query,
+            root,
             level_stack: Vec::new(),
             finished: false,
         }
     }
 
-    unsafe fn unpack_root_lifetime(&mut self) -> &'tree mut RTreeNode<P, DIM, LG, T> {
-        let root: *mut RTreeNode<P, DIM, LG, T> = self.root;
+    unsafe fn unpack_root_lifetime(&mut self) -> &'tree mut NODE {
+        let root: *mut NODE = self.root;
         &mut *root
     }
 
     /// Select the next matching leaves level
     fn next_leaves(
         &mut self,
-        mut m_iter: SliceIterMut<'tree, RTreeNode<P, DIM, LG, T>>,
+        mut m_iter: SliceIterMut<'tree, NODE>,
     ) -> Option<LeafIterMut<'tree, P, DIM, LG, T>> {
         let mut iter_node = m_iter.next();
         while let Some(node) = iter_node {
@@ -289,13 +702,9 @@ where
                 continue;
             }
             self.level_stack.push(m_iter);
-            match *node {
-                RTreeNode::Leaves {
-                    ref mut children, ..
-                } => return Some(children.iter_mut()),
-                RTreeNode::Level {
-                    ref mut children, ..
-                } => {
+            match node.children_mut() {
+                NodeChildren::Leaves(children) => return Some(children.iter_mut()),
+                NodeChildren::Levels(children) => {
                     let next = self.next_leaves(children.iter_mut());
                     if next.is_none() {
                         m_iter = self.level_stack.pop().unwrap();
@@ -310,11 +719,14 @@ where
     }
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> Iterator for LevelIterMut<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> Iterator
+    for LevelIterMut<'tree, P, DIM, LG, T, Q, NODE>
 where
+    P: 'tree,
     LG: MbrLeafGeometry<P, DIM> + 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     type Item = LeafIterMut<'tree, P, DIM, LG, T>;
 
@@ -323,23 +735,19 @@ where
             return None;
         }
         if self.level_stack.is_empty() {
-            match unsafe { self.unpack_root_lifetime() } {
-                &mut RTreeNode::Leaves {
-                    ref mut children, ..
-                } => {
+            match unsafe { self.unpack_root_lifetime() }.children_mut() {
+                NodeChildren::Leaves(children) => {
                     self.finished = true;
                     return Some(children.iter_mut());
                 }
-                &mut RTreeNode::Level {
-                    ref mut children, ..
-                } => {
+                NodeChildren::Levels(children) => {
                     self.level_stack.push(children.iter_mut());
                 }
             }
         }
         let mut m_iter = self.level_stack.pop().unwrap();
         let mut next = self.next_leaves(m_iter);
-        while let None = next {
+        while next.is_none() {
             if !self.level_stack.is_empty() {
                 m_iter = self.level_stack.pop().unwrap();
                 next = self.next_leaves(m_iter);
@@ -353,31 +761,35 @@ where
 }
 
 /// Iter all `Leaf` items matching a query
-pub struct Iter<'tree, P: FP, const DIM: usize, LG, T, Q>
+pub struct Iter<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE = RTreeNode<P, DIM, LG, T>>
 where
+    P: 'tree,
     LG: 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     query: Rc<Q>,
-    level_iter: LevelIter<'tree, P, DIM, LG, T, Q>,
+    level_iter: LevelIter<'tree, P, DIM, LG, T, Q, NODE>,
     leaf_iter: Option<LeafIter<'tree, P, DIM, LG, T>>,
     finished: bool,
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> Iter<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> Iter<'tree, P, DIM, LG, T, Q, NODE>
 where
+    P: 'tree,
     LG: MbrLeafGeometry<P, DIM> + 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     /// Constructor
-    fn new(query: Q, root: &'tree RTreeNode<P, DIM, LG, T>) -> Iter<'tree, P, DIM, LG, T, Q> {
+    fn new(query: Q, root: &'tree NODE) -> Iter<'tree, P, DIM, LG, T, Q, NODE> {
         let rc_query = Rc::new(query);
         let level_iter = LevelIter::new(rc_query.clone(), root);
         Iter {
             query: rc_query,
-            level_iter: level_iter,
+            level_iter,
             leaf_iter: None,
             finished: false,
         }
@@ -399,11 +811,14 @@ where
     }
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> Iterator for Iter<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> Iterator
+    for Iter<'tree, P, DIM, LG, T, Q, NODE>
 where
+    P: 'tree,
     LG: MbrLeafGeometry<P, DIM> + 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     type Item = (&'tree LG, &'tree T);
 
@@ -420,10 +835,9 @@ where
         }
         let iter = self.leaf_iter.take().unwrap();
         let mut next = self.next_leaf(iter);
-        while let None = next {
-            let leaf_iter = self.level_iter.next();
-            if !leaf_iter.is_none() {
-                next = self.next_leaf(leaf_iter.unwrap());
+        while next.is_none() {
+            if let Some(leaf_iter) = self.level_iter.next() {
+                next = self.next_leaf(leaf_iter);
             } else {
                 self.finished = true;
                 break;
@@ -434,34 +848,35 @@ where
 }
 
 /// Mutably iterate all `Leaf` entries matching a query
-pub struct IterMut<'tree, P: FP, const DIM: usize, LG, T, Q>
+pub struct IterMut<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE = RTreeNode<P, DIM, LG, T>>
 where
+    P: 'tree,
     LG: 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     query: Rc<Q>,
-    level_iter: LevelIterMut<'tree, P, DIM, LG, T, Q>,
+    level_iter: LevelIterMut<'tree, P, DIM, LG, T, Q, NODE>,
     leaf_iter: Option<LeafIterMut<'tree, P, DIM, LG, T>>,
     finished: bool,
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> IterMut<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> IterMut<'tree, P, DIM, LG, T, Q, NODE>
 where
+    P: 'tree,
     LG: MbrLeafGeometry<P, DIM> + 'tree,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     /// Constructor
-    fn new(
-        query: Q,
-        root: &'tree mut RTreeNode<P, DIM, LG, T>,
-    ) -> IterMut<'tree, P, DIM, LG, T, Q> {
+    fn new(query: Q, root: &'tree mut NODE) -> IterMut<'tree, P, DIM, LG, T, Q, NODE> {
         let rc_query = Rc::new(query);
         let level_iter = LevelIterMut::new(rc_query.clone(), root);
         IterMut {
             query: rc_query,
-            level_iter: level_iter,
+            level_iter,
             leaf_iter: None,
             finished: false,
         }
@@ -487,11 +902,13 @@ where
     }
 }
 
-impl<'tree, P: FP, const DIM: usize, LG, T, Q> Iterator for IterMut<'tree, P, DIM, LG, T, Q>
+impl<'tree, P: Scalar, const DIM: usize, LG, T, Q, NODE> Iterator
+    for IterMut<'tree, P, DIM, LG, T, Q, NODE>
 where
     LG: MbrLeafGeometry<P, DIM>,
     T: 'tree,
-    Q: MbrQuery<P, DIM, LG, T, RTreeNode<P, DIM, LG, T>>,
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
 {
     type Item = (&'tree LG, &'tree mut T);
 
@@ -508,10 +925,9 @@ where
         }
         let iter = self.leaf_iter.take().unwrap();
         let mut next = self.next_leaf(iter);
-        while let None = next {
-            let leaf_iter = self.level_iter.next();
-            if !leaf_iter.is_none() {
-                next = self.next_leaf(leaf_iter.unwrap());
+        while next.is_none() {
+            if let Some(leaf_iter) = self.level_iter.next() {
+                next = self.next_leaf(leaf_iter);
             } else {
                 self.finished = true;
                 break;
@@ -520,3 +936,217 @@ where
         next
     }
 }
+
+/// A level or leaf waiting to be visited by a nearest neighbor search
+enum NearestEntry<'tree, P: Scalar, const DIM: usize, LG, T, NODE> {
+    Level(&'tree NODE),
+    Leaf(&'tree MbrLeaf<P, DIM, LG, T>),
+}
+
+/// Queue entry for a best-first traversal. Ordered so the closest entry is popped first from a `BinaryHeap`
+struct NearestCandidate<'tree, P: Scalar, const DIM: usize, LG, T, NODE> {
+    distance: OrderedFloat<P::Measure>,
+    entry: NearestEntry<'tree, P, DIM, LG, T, NODE>,
+}
+
+impl<'tree, P: Scalar, const DIM: usize, LG, T, NODE> NearestCandidate<'tree, P, DIM, LG, T, NODE>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM>,
+{
+    fn new(
+        point: &Point<P, DIM>,
+        entry: NearestEntry<'tree, P, DIM, LG, T, NODE>,
+    ) -> NearestCandidate<'tree, P, DIM, LG, T, NODE> {
+        let distance = match entry {
+            NearestEntry::Level(level) => level.distance_from_point(point),
+            NearestEntry::Leaf(leaf) => leaf.distance_from_point(point),
+        };
+        NearestCandidate::with_distance(distance, entry)
+    }
+
+    fn with_distance(
+        distance: P::Measure,
+        entry: NearestEntry<'tree, P, DIM, LG, T, NODE>,
+    ) -> NearestCandidate<'tree, P, DIM, LG, T, NODE> {
+        NearestCandidate {
+            distance: OrderedFloat(distance),
+            entry,
+        }
+    }
+}
+
+/// Iterate through all `Leaf` items in order of increasing distance from a point.
+///
+/// Levels and leaves share a single priority queue, as described by Hjaltason, G. R.; Samet, H. (1999). "Distance browsing in spatial databases"
+pub struct NearestIter<'tree, P: Scalar, const DIM: usize, LG, T, NODE = RTreeNode<P, DIM, LG, T>>
+where
+    P: 'tree,
+    LG: 'tree,
+    T: 'tree,
+{
+    point: Point<P, DIM>,
+    queue: BinaryHeap<NearestCandidate<'tree, P, DIM, LG, T, NODE>>,
+}
+
+impl<'tree, P: Scalar, const DIM: usize, LG, T, NODE> NearestIter<'tree, P, DIM, LG, T, NODE>
+where
+    P: 'tree,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    /// Constructor
+    fn new(point: Point<P, DIM>, root: &'tree NODE) -> NearestIter<'tree, P, DIM, LG, T, NODE> {
+        let mut queue = BinaryHeap::new();
+        if !root.is_empty() {
+            queue.push(NearestCandidate::new(&point, NearestEntry::Level(root)));
+        }
+        NearestIter { point, queue }
+    }
+}
+
+impl<'tree, P: Scalar, const DIM: usize, LG, T, NODE> Iterator
+    for NearestIter<'tree, P, DIM, LG, T, NODE>
+where
+    P: 'tree,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    type Item = (P::Measure, &'tree LG, &'tree T);
+
+    fn next(&mut self) -> Option<(P::Measure, &'tree LG, &'tree T)> {
+        while let Some(candidate) = self.queue.pop() {
+            let level = match candidate.entry {
+                NearestEntry::Leaf(leaf) => {
+                    return Some((candidate.distance.into_inner(), &leaf.geometry, &leaf.item))
+                }
+                NearestEntry::Level(level) => level,
+            };
+            match level.children() {
+                NodeChildren::Leaves(children) => {
+                    for leaf in children {
+                        self.queue
+                            .push(NearestCandidate::new(&self.point, NearestEntry::Leaf(leaf)));
+                    }
+                }
+                NodeChildren::Levels(children) => {
+                    for level in children {
+                        self.queue.push(NearestCandidate::new(
+                            &self.point,
+                            NearestEntry::Level(level),
+                        ));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Iterate through all `Leaf` items a ray touches, in order of increasing ray parameter t.
+///
+/// A best-first traversal like `NearestIter`, keyed on where the ray enters each level and leaf
+pub struct RaycastIter<'tree, P: Scalar, const DIM: usize, LG, T, NODE = RTreeNode<P, DIM, LG, T>>
+where
+    P: 'tree,
+    LG: 'tree,
+    T: 'tree,
+{
+    ray: Ray<P::Measure, DIM>,
+    queue: BinaryHeap<NearestCandidate<'tree, P, DIM, LG, T, NODE>>,
+}
+
+impl<'tree, P: Scalar, const DIM: usize, LG, T, NODE> RaycastIter<'tree, P, DIM, LG, T, NODE>
+where
+    P: 'tree,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    /// Constructor
+    fn new(
+        ray: Ray<P::Measure, DIM>,
+        root: &'tree NODE,
+    ) -> RaycastIter<'tree, P, DIM, LG, T, NODE> {
+        let mut iter = RaycastIter {
+            ray,
+            queue: BinaryHeap::new(),
+        };
+        if !root.is_empty() {
+            iter.push(root.ray_entry(&iter.ray), NearestEntry::Level(root));
+        }
+        iter
+    }
+
+    fn push(
+        &mut self,
+        entry_t: Option<P::Measure>,
+        entry: NearestEntry<'tree, P, DIM, LG, T, NODE>,
+    ) {
+        if let Some(t) = entry_t {
+            self.queue.push(NearestCandidate::with_distance(t, entry));
+        }
+    }
+}
+
+impl<'tree, P: Scalar, const DIM: usize, LG, T, NODE> Iterator
+    for RaycastIter<'tree, P, DIM, LG, T, NODE>
+where
+    P: 'tree,
+    LG: MbrLeafGeometry<P, DIM> + 'tree,
+    T: 'tree,
+    NODE: MbrNode<P, DIM> + MbrNodeChildren<Leaf = MbrLeaf<P, DIM, LG, T>>,
+{
+    type Item = (P::Measure, &'tree LG, &'tree T);
+
+    fn next(&mut self) -> Option<(P::Measure, &'tree LG, &'tree T)> {
+        while let Some(candidate) = self.queue.pop() {
+            let level = match candidate.entry {
+                NearestEntry::Leaf(leaf) => {
+                    return Some((candidate.distance.into_inner(), &leaf.geometry, &leaf.item))
+                }
+                NearestEntry::Level(level) => level,
+            };
+            match level.children() {
+                NodeChildren::Leaves(children) => {
+                    for leaf in children {
+                        self.push(leaf.ray_entry(&self.ray), NearestEntry::Leaf(leaf));
+                    }
+                }
+                NodeChildren::Levels(children) => {
+                    for level in children {
+                        self.push(level.ray_entry(&self.ray), NearestEntry::Level(level));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> PartialEq
+    for NearestCandidate<'_, P, DIM, LG, T, NODE>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> Eq for NearestCandidate<'_, P, DIM, LG, T, NODE> {}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> PartialOrd
+    for NearestCandidate<'_, P, DIM, LG, T, NODE>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> Ord for NearestCandidate<'_, P, DIM, LG, T, NODE> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed to turn the max heap into a min heap
+        other.distance.cmp(&self.distance)
+    }
+}
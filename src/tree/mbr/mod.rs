@@ -7,7 +7,11 @@
 
 //! Collection of minimum bounding rectangle spatial trees
 
-mod index;
+mod bulk;
+mod entry;
+mod frozen;
+pub mod index;
+mod join;
 mod leaf;
 mod leafgeometry;
 mod map;
@@ -17,18 +21,32 @@ mod query;
 use std::marker::PhantomData;
 use crate::tree::mbr::index::r::{Linear, Quadratic, RInsert, RRemove, SeedSplit};
 use crate::tree::mbr::index::rstar::RStarInsert;
-use crate::tree::mbr::index::{IndexInsert, MbrNodeSplit};
+pub use crate::tree::mbr::index::{IndexInsert, IndexRemove};
+use crate::tree::mbr::index::MbrNodeSplit;
+pub use crate::tree::mbr::entry::{Entry, OccupiedEntry, VacantEntry};
+pub use crate::tree::mbr::frozen::{
+    FrozenError, FrozenGeometry, FrozenIter, FrozenMap, FrozenNode, FrozenScalar, FROZEN_MAGIC,
+    FROZEN_VERSION,
+};
+pub use crate::tree::mbr::join::{JoinIter, PairsIter};
 pub use crate::tree::mbr::leaf::MbrLeaf;
-pub use crate::tree::mbr::leafgeometry::MbrLeafGeometry;
-pub use crate::tree::mbr::map::{Iter, IterMut, MbrMap};
-pub use crate::tree::mbr::node::{MbrNode, RTreeNode};
-pub use crate::tree::mbr::query::{MbrQuery, MbrRectQuery};
-use crate::FP;
+pub use crate::tree::mbr::leafgeometry::{
+    DynLeafGeometry, LeafPart, MbrLeafGeometry, PolygonLeafGeometry,
+};
+pub use crate::tree::mbr::map::{
+    InsertError, IntoIter, Iter, IterMut, MbrMap, NearestIter, RaycastIter,
+};
+pub use crate::tree::mbr::node::{MbrNode, MbrNodeChildren, NodeChildren, RTreeNode};
+pub use crate::tree::mbr::query::{
+    And, Filter, MbrPointQuery, MbrPolygonQuery, MbrQuery, MbrRectQuery, MbrSphereMode,
+    MbrSphereQuery, Not, Or,
+};
+use crate::Scalar;
 
 /// Convenience struct for creating a new R Tree
 ///
 /// Agorithms described by Guttman, A. (1984). "R-Trees: A Dynamic Index Structure for Spatial Searching"
-pub struct RTree<P: FP, const DIM: usize, LG, T> {
+pub struct RTree<P: Scalar, const DIM: usize, LG, T> {
     _p: PhantomData<P>,
     _lg: PhantomData<LG>,
     _t: PhantomData<T>,
@@ -47,7 +65,7 @@ pub type RLinearTree<P, const DIM: usize, LG, T> = MbrMap<
     RRemove<P, DIM, LG, T>,
 >;
 
-impl<P: FP, const DIM: usize, LG, T> RTree<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> RTree<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -80,6 +98,7 @@ where
         ))
     }
 
+    #[allow(clippy::type_complexity)]
     fn map_from_insert<S: MbrNodeSplit<P, DIM>>(
         insert: RInsert<P, DIM, LG, T, S>,
     ) -> MbrMap<RTreeNode<P, DIM, LG, T>, RInsert<P, DIM, LG, T, S>, RRemove<P, DIM, LG, T>> {
@@ -95,17 +114,18 @@ pub type RStarTree<P, const DIM: usize, LG, T> =
 /// Convenience struct for creating a new R* Tree
 ///
 /// Algorithms descibed by Beckmann, N.; Kriegel, H. P.; Schneider, R.; Seeger, B. (1990). "The R*-tree: an efficient and robust access method for points and rectangles".
-pub struct RStar<P: FP, const DIM: usize, LG, T> {
+pub struct RStar<P: Scalar, const DIM: usize, LG, T> {
     _p: PhantomData<P>,
     _lg: PhantomData<LG>,
     _t: PhantomData<T>,
 }
 
-impl<P: FP, const DIM: usize, LG, T> RStar<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> RStar<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
     /// Create a new R* tree with min and max children lengths set to 19 and 64, respectively
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> RStarTree<P, DIM, LG, T> {
         RStar::map_from_insert(RStarInsert::new())
     }
@@ -135,3 +155,48 @@ where
         MbrMap::new(rstar_insert, RRemove::with_min(min))
     }
 }
+
+/// Bulk loads a default R Tree with the Linear splitting algorithm.
+/// Panics on geometry with a non-finite coordinate, which `MbrMap::try_bulk_load` hands back instead
+impl<P: Scalar, const DIM: usize, LG, T> FromIterator<(LG, T)> for RLinearTree<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn from_iter<ITER: IntoIterator<Item = (LG, T)>>(items: ITER) -> RLinearTree<P, DIM, LG, T> {
+        let mut map = RTree::new_linear();
+        if let Err(error) = map.try_bulk_load(items) {
+            panic!("{}", error);
+        }
+        map
+    }
+}
+
+/// Bulk loads a default R Tree with the Quadratic splitting algorithm.
+/// Panics on geometry with a non-finite coordinate, which `MbrMap::try_bulk_load` hands back instead
+impl<P: Scalar, const DIM: usize, LG, T> FromIterator<(LG, T)> for RQuadraticTree<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn from_iter<ITER: IntoIterator<Item = (LG, T)>>(items: ITER) -> RQuadraticTree<P, DIM, LG, T> {
+        let mut map = RTree::new_quadratic();
+        if let Err(error) = map.try_bulk_load(items) {
+            panic!("{}", error);
+        }
+        map
+    }
+}
+
+/// Bulk loads a default R* Tree.
+/// Panics on geometry with a non-finite coordinate, which `MbrMap::try_bulk_load` hands back instead
+impl<P: Scalar, const DIM: usize, LG, T> FromIterator<(LG, T)> for RStarTree<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn from_iter<ITER: IntoIterator<Item = (LG, T)>>(items: ITER) -> RStarTree<P, DIM, LG, T> {
+        let mut map = RStar::new();
+        if let Err(error) = map.try_bulk_load(items) {
+            panic!("{}", error);
+        }
+        map
+    }
+}
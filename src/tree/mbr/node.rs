@@ -5,13 +5,15 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use crate::geometry::Rect;
+use crate::geometry::{Point, Rect};
 use std::fmt::Debug;
 use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry};
-use crate::FP;
+use crate::Scalar;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// A node in an MbrMap
-pub trait MbrNode<P: FP, const DIM: usize>: MbrLeafGeometry<P, DIM> {
+pub trait MbrNode<P: Scalar, const DIM: usize>: MbrLeafGeometry<P, DIM> {
     /// Create an empty leaf level
     fn new_leaves() -> Self;
 
@@ -38,22 +40,74 @@ pub trait MbrNode<P: FP, const DIM: usize>: MbrLeafGeometry<P, DIM> {
     fn is_empty(&self) -> bool;
 }
 
+/// A node's children. Either other levels or leaves
+#[derive(Debug)]
+pub enum NodeChildren<LEVELS, LEAVES> {
+    /// Children are other levels
+    Levels(LEVELS),
+    /// Children are leaves
+    Leaves(LEAVES),
+}
+
+/// Access to the children of a node, so the map and its iterators can walk any node layout.
+///
+/// The leaf type is an associated type so it can determine the rest of a map's type parameters
+pub trait MbrNodeChildren: Sized {
+    /// The leaf type stored in the tree
+    type Leaf;
+
+    /// Create a leaf level bounding the leaves
+    fn from_leaves(children: Vec<Self::Leaf>) -> Self;
+
+    /// Create a level bounding the levels
+    fn from_levels(children: Vec<Self>) -> Self;
+
+    /// Borrow the level's children
+    fn children(&self) -> NodeChildren<&[Self], &[Self::Leaf]>;
+
+    /// Mutably borrow the level's children
+    fn children_mut(&mut self) -> NodeChildren<&mut [Self], &mut [Self::Leaf]>;
+
+    /// Mutably borrow the level's children to add or remove them
+    fn children_vec_mut(&mut self) -> NodeChildren<&mut Vec<Self>, &mut Vec<Self::Leaf>>;
+
+    /// Consume the level, returning its children
+    fn into_children(self) -> NodeChildren<Vec<Self>, Vec<Self::Leaf>>;
+
+    /// Note that bulk loading split the level from its siblings along the axis.
+    /// Only levels that keep a split history need to record it
+    fn record_split(&mut self, _axis: usize) {}
+
+    /// Note that removal condensed the level, which holds at most max children unless its capacity was extended.
+    /// Only levels with an extended capacity need to shrink it
+    fn record_removal(&mut self, _max: usize) {}
+}
+
 /// Level node of a tree. Either contains other levels or leaves
 #[derive(Debug)]
-pub enum RTreeNode<P: FP, const DIM: usize, LG, T> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RTreeNode<P: Scalar, const DIM: usize, LG, T> {
     /// Contains only other levels
     Level {
+        #[cfg_attr(
+            feature = "serde",
+            serde(deserialize_with = "crate::serde_geometry::deserialize_mbr")
+        )]
         mbr: Rect<P, DIM>,
         children: Vec<RTreeNode<P, DIM, LG, T>>,
     },
     /// Contains only leaves
     Leaves {
+        #[cfg_attr(
+            feature = "serde",
+            serde(deserialize_with = "crate::serde_geometry::deserialize_mbr")
+        )]
         mbr: Rect<P, DIM>,
         children: Vec<MbrLeaf<P, DIM, LG, T>>,
     },
 }
 
-impl<P: FP, const DIM: usize, LG, T> MbrNode<P, DIM> for RTreeNode<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> MbrNode<P, DIM> for RTreeNode<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -111,7 +165,68 @@ where
     }
 }
 
-impl<P: FP, const DIM: usize, LG, T> MbrLeafGeometry<P, DIM> for RTreeNode<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> MbrNodeChildren for RTreeNode<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    type Leaf = MbrLeaf<P, DIM, LG, T>;
+
+    fn from_leaves(children: Vec<MbrLeaf<P, DIM, LG, T>>) -> RTreeNode<P, DIM, LG, T> {
+        let mut mbr = Rect::max_inverted();
+        for child in &children {
+            child.expand_mbr_to_fit(&mut mbr);
+        }
+        RTreeNode::Leaves { mbr, children }
+    }
+
+    fn from_levels(children: Vec<RTreeNode<P, DIM, LG, T>>) -> RTreeNode<P, DIM, LG, T> {
+        let mut mbr = Rect::max_inverted();
+        for child in &children {
+            child.expand_mbr_to_fit(&mut mbr);
+        }
+        RTreeNode::Level { mbr, children }
+    }
+
+    fn children(&self) -> NodeChildren<&[Self], &[MbrLeaf<P, DIM, LG, T>]> {
+        match *self {
+            RTreeNode::Level { ref children, .. } => NodeChildren::Levels(children),
+            RTreeNode::Leaves { ref children, .. } => NodeChildren::Leaves(children),
+        }
+    }
+
+    fn children_mut(&mut self) -> NodeChildren<&mut [Self], &mut [MbrLeaf<P, DIM, LG, T>]> {
+        match *self {
+            RTreeNode::Level {
+                ref mut children, ..
+            } => NodeChildren::Levels(children),
+            RTreeNode::Leaves {
+                ref mut children, ..
+            } => NodeChildren::Leaves(children),
+        }
+    }
+
+    fn children_vec_mut(
+        &mut self,
+    ) -> NodeChildren<&mut Vec<Self>, &mut Vec<MbrLeaf<P, DIM, LG, T>>> {
+        match *self {
+            RTreeNode::Level {
+                ref mut children, ..
+            } => NodeChildren::Levels(children),
+            RTreeNode::Leaves {
+                ref mut children, ..
+            } => NodeChildren::Leaves(children),
+        }
+    }
+
+    fn into_children(self) -> NodeChildren<Vec<Self>, Vec<MbrLeaf<P, DIM, LG, T>>> {
+        match self {
+            RTreeNode::Level { children, .. } => NodeChildren::Levels(children),
+            RTreeNode::Leaves { children, .. } => NodeChildren::Leaves(children),
+        }
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> MbrLeafGeometry<P, DIM> for RTreeNode<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -123,10 +238,26 @@ where
         self.mbr().expand_mbr_to_fit(mbr)
     }
 
-    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
         self.mbr().distance_from_mbr_center(mbr)
     }
 
+    fn distance_sq_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr().distance_sq_from_mbr_center(mbr)
+    }
+
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        self.mbr().distance_from_point(point)
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr().min_distance_from_mbr(mbr)
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr().min_max_distance_from_mbr(mbr)
+    }
+
     fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
         self.mbr().contained_by_mbr(mbr)
     }
@@ -135,11 +266,11 @@ where
         self.mbr().overlapped_by_mbr(mbr)
     }
 
-    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
         self.mbr().area_overlapped_with_mbr(mbr)
     }
 
-    fn area(&self) -> P {
+    fn area(&self) -> P::Measure {
         self.mbr().area()
     }
 
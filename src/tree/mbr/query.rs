@@ -5,29 +5,39 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use crate::geometry::Rect;
+use crate::geometry::{Point, Polygon, Rect};
 use std::fmt::Debug;
-use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode};
-use crate::FP;
+use std::ops::Deref;
+use num::Zero;
+use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode, PolygonLeafGeometry};
+use crate::{Scalar, FP};
 
 /// Query trait for navigating the tree
-pub trait MbrQuery<P: FP, const DIM: usize, LG, T, NODE> {
+pub trait MbrQuery<P: Scalar, const DIM: usize, LG, T, NODE> {
     /// Returns true if the leaf matches the query
     fn accept_leaf(&self, leaf: &MbrLeaf<P, DIM, LG, T>) -> bool;
     /// Returns true if the level matches the query
     fn accept_level(&self, level: &NODE) -> bool;
+    /// Returns true if every leaf under the level is guaranteed to match the query.
+    /// Negated queries rely on this to skip levels. Returning false is always safe
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        let _ = level;
+        false
+    }
 }
 
 /// Rect based query
 #[derive(Debug, Clone)]
-pub enum MbrRectQuery<P: FP, const DIM: usize> {
+pub enum MbrRectQuery<P: Scalar, const DIM: usize> {
     /// Matching leaves are ones that are completely contained by this rect
     ContainedBy(Rect<P, DIM>),
-    /// Matching leaves are ones that overlap this rect
+    /// Matching leaves are ones that overlap this rect, as decided by `MbrLeafGeometry::overlapped_by_mbr`.
+    /// Rects have to reach into the query, while points and lines on its boundary match
     Overlaps(Rect<P, DIM>),
 }
 
-impl<P: FP, const DIM: usize, LG, T, NODE> MbrQuery<P, DIM, LG, T, NODE> for MbrRectQuery<P, DIM>
+impl<P: Scalar, const DIM: usize, LG, T, NODE> MbrQuery<P, DIM, LG, T, NODE>
+    for MbrRectQuery<P, DIM>
 where
     LG: MbrLeafGeometry<P, DIM>,
     NODE: MbrNode<P, DIM>,
@@ -41,10 +51,258 @@ where
     }
 
     // Does this query accept the given level?
+    // Boundary inclusive, since points and degenerate leaves on a level's face still match a query that only touches it
     fn accept_level(&self, level: &NODE) -> bool {
+        let query = match *self {
+            MbrRectQuery::ContainedBy(ref query) => query,
+            MbrRectQuery::Overlaps(ref query) => query,
+        };
+        izip!(level.mbr().deref(), query.deref()).all(|(&(x1, y1), &(x2, y2))| x2 <= y1 && x1 <= y2)
+    }
+
+    // Does this query accept everything in the given level?
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        match *self {
+            MbrRectQuery::ContainedBy(ref query) => level.contained_by_mbr(query),
+            // leaves strictly inside the query overlap it, whatever their shape
+            MbrRectQuery::Overlaps(ref query) => izip!(level.mbr().deref(), query.deref())
+                .all(|(&(x1, y1), &(x2, y2))| x2 < x1 && y1 < y2),
+        }
+    }
+}
+
+/// Polygon based query for 2-dimensional trees.
+/// Leaves are matched by their exact shape through `PolygonLeafGeometry`.
+/// Leaves with an area only overlap the polygon by reaching its interior, while points and lines only have to touch it
+#[derive(Debug, Clone)]
+pub enum MbrPolygonQuery<P: FP> {
+    /// Matching leaves are ones that are completely contained by this polygon
+    ContainedBy(Polygon<P>),
+    /// Matching leaves are ones that overlap this polygon
+    Overlaps(Polygon<P>),
+}
+
+impl<P: FP> MbrPolygonQuery<P> {
+    fn polygon(&self) -> &Polygon<P> {
+        match *self {
+            MbrPolygonQuery::ContainedBy(ref polygon) => polygon,
+            MbrPolygonQuery::Overlaps(ref polygon) => polygon,
+        }
+    }
+}
+
+impl<P: FP, LG, T, NODE> MbrQuery<P, 2, LG, T, NODE> for MbrPolygonQuery<P>
+where
+    LG: PolygonLeafGeometry<P>,
+    NODE: MbrNode<P, 2>,
+{
+    fn accept_leaf(&self, leaf: &MbrLeaf<P, 2, LG, T>) -> bool {
         match *self {
-            MbrRectQuery::ContainedBy(ref query) => level.overlapped_by_mbr(query),
-            MbrRectQuery::Overlaps(ref query) => level.overlapped_by_mbr(query),
+            MbrPolygonQuery::ContainedBy(ref query) => leaf.geometry.contained_by_polygon(query),
+            MbrPolygonQuery::Overlaps(ref query) => leaf.geometry.overlapped_by_polygon(query),
+        }
+    }
+
+    // Boundary inclusive, as with MbrRectQuery
+    fn accept_level(&self, level: &NODE) -> bool {
+        self.polygon().touches_mbr(level.mbr())
+    }
+
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        self.polygon().contains_mbr(level.mbr())
+    }
+}
+
+/// How a `MbrSphereQuery` matches leaves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MbrSphereMode {
+    /// Matching leaves are ones that are completely contained by the sphere
+    Contains,
+    /// Matching leaves are ones that overlap the sphere
+    Overlaps,
+}
+
+/// Sphere based query. Matches leaves by their exact distance from the center, boundary inclusive
+#[derive(Debug, Clone)]
+pub struct MbrSphereQuery<P: Scalar, const DIM: usize> {
+    pub center: Point<P, DIM>,
+    pub radius: P::Measure,
+    pub mode: MbrSphereMode,
+}
+
+impl<P: Scalar, const DIM: usize> MbrSphereQuery<P, DIM> {
+    /// Matches leaves completely contained by the sphere
+    pub fn contains(center: Point<P, DIM>, radius: P::Measure) -> MbrSphereQuery<P, DIM> {
+        MbrSphereQuery {
+            center,
+            radius,
+            mode: MbrSphereMode::Contains,
+        }
+    }
+
+    /// Matches leaves that overlap the sphere
+    pub fn overlaps(center: Point<P, DIM>, radius: P::Measure) -> MbrSphereQuery<P, DIM> {
+        MbrSphereQuery {
+            center,
+            radius,
+            mode: MbrSphereMode::Overlaps,
         }
     }
 }
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> MbrQuery<P, DIM, LG, T, NODE>
+    for MbrSphereQuery<P, DIM>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM>,
+{
+    fn accept_leaf(&self, leaf: &MbrLeaf<P, DIM, LG, T>) -> bool {
+        match self.mode {
+            MbrSphereMode::Contains => {
+                leaf.geometry.max_distance_from_point(&self.center) <= self.radius
+            }
+            MbrSphereMode::Overlaps => {
+                leaf.geometry.distance_from_point(&self.center) <= self.radius
+            }
+        }
+    }
+
+    // Either mode needs a leaf that touches the sphere, so both use the MINDIST between the level and the center
+    fn accept_level(&self, level: &NODE) -> bool {
+        level.distance_from_point(&self.center) <= self.radius
+    }
+
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        level.max_distance_from_point(&self.center) <= self.radius
+    }
+}
+
+/// Point location (stabbing) query. Matches leaves whose geometry contains the point,
+/// or passes within the tolerance of it
+#[derive(Debug, Clone)]
+pub struct MbrPointQuery<P: Scalar, const DIM: usize> {
+    pub point: Point<P, DIM>,
+    pub tolerance: P::Measure,
+}
+
+impl<P: Scalar, const DIM: usize> MbrPointQuery<P, DIM> {
+    /// Matches leaves that contain the point
+    pub fn new(point: Point<P, DIM>) -> MbrPointQuery<P, DIM> {
+        MbrPointQuery::with_tolerance(point, Zero::zero())
+    }
+
+    /// Matches leaves within the tolerance of the point. Useful for points and segments, which rarely contain it exactly
+    pub fn with_tolerance(point: Point<P, DIM>, tolerance: P::Measure) -> MbrPointQuery<P, DIM> {
+        MbrPointQuery { point, tolerance }
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE> MbrQuery<P, DIM, LG, T, NODE>
+    for MbrPointQuery<P, DIM>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+    NODE: MbrNode<P, DIM>,
+{
+    fn accept_leaf(&self, leaf: &MbrLeaf<P, DIM, LG, T>) -> bool {
+        leaf.geometry.distance_from_point(&self.point) <= self.tolerance
+    }
+
+    // With no tolerance this is the level's mbr containing the point, boundary inclusive
+    fn accept_level(&self, level: &NODE) -> bool {
+        level.distance_from_point(&self.point) <= self.tolerance
+    }
+
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        level.max_distance_from_point(&self.point) <= self.tolerance
+    }
+}
+
+/// Matches leaves accepted by both queries
+#[derive(Debug, Clone)]
+pub struct And<Q1, Q2>(pub Q1, pub Q2);
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE, Q1, Q2> MbrQuery<P, DIM, LG, T, NODE> for And<Q1, Q2>
+where
+    Q1: MbrQuery<P, DIM, LG, T, NODE>,
+    Q2: MbrQuery<P, DIM, LG, T, NODE>,
+{
+    fn accept_leaf(&self, leaf: &MbrLeaf<P, DIM, LG, T>) -> bool {
+        self.0.accept_leaf(leaf) && self.1.accept_leaf(leaf)
+    }
+
+    fn accept_level(&self, level: &NODE) -> bool {
+        self.0.accept_level(level) && self.1.accept_level(level)
+    }
+
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        self.0.accept_whole_level(level) && self.1.accept_whole_level(level)
+    }
+}
+
+/// Matches leaves accepted by either query
+#[derive(Debug, Clone)]
+pub struct Or<Q1, Q2>(pub Q1, pub Q2);
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE, Q1, Q2> MbrQuery<P, DIM, LG, T, NODE> for Or<Q1, Q2>
+where
+    Q1: MbrQuery<P, DIM, LG, T, NODE>,
+    Q2: MbrQuery<P, DIM, LG, T, NODE>,
+{
+    fn accept_leaf(&self, leaf: &MbrLeaf<P, DIM, LG, T>) -> bool {
+        self.0.accept_leaf(leaf) || self.1.accept_leaf(leaf)
+    }
+
+    fn accept_level(&self, level: &NODE) -> bool {
+        self.0.accept_level(level) || self.1.accept_level(level)
+    }
+
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        self.0.accept_whole_level(level) || self.1.accept_whole_level(level)
+    }
+}
+
+/// Matches leaves the query rejects.
+///
+/// A level can only be skipped when the query is known to accept every leaf under it,
+/// so `Not` is only as selective as the wrapped query's `accept_whole_level`.
+/// `Filter` never accepts a whole level, so `Not(Filter(..))` visits every level
+#[derive(Debug, Clone)]
+pub struct Not<Q>(pub Q);
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE, Q> MbrQuery<P, DIM, LG, T, NODE> for Not<Q>
+where
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+{
+    fn accept_leaf(&self, leaf: &MbrLeaf<P, DIM, LG, T>) -> bool {
+        !self.0.accept_leaf(leaf)
+    }
+
+    fn accept_level(&self, level: &NODE) -> bool {
+        !self.0.accept_whole_level(level)
+    }
+
+    fn accept_whole_level(&self, level: &NODE) -> bool {
+        !self.0.accept_level(level)
+    }
+}
+
+/// Matches leaves accepted by the query whose items also pass the predicate.
+///
+/// The predicate can only be checked leaf by leaf, so `accept_whole_level` keeps its default of false
+/// and a negated `Filter` can't skip any level
+#[derive(Debug, Clone)]
+pub struct Filter<Q, F>(pub Q, pub F);
+
+impl<P: Scalar, const DIM: usize, LG, T, NODE, Q, F> MbrQuery<P, DIM, LG, T, NODE> for Filter<Q, F>
+where
+    Q: MbrQuery<P, DIM, LG, T, NODE>,
+    F: Fn(&T) -> bool,
+{
+    fn accept_leaf(&self, leaf: &MbrLeaf<P, DIM, LG, T>) -> bool {
+        self.0.accept_leaf(leaf) && (self.1)(&leaf.item)
+    }
+
+    fn accept_level(&self, level: &NODE) -> bool {
+        self.0.accept_level(level)
+    }
+}
@@ -8,9 +8,7 @@
 //! A collection of spatial trees
 
 pub mod mbr;
-mod xmbr;
-
-//pub mod xmbr;
+pub mod xmbr;
 
 // TODO: Figure this out later :/
 // pub trait SpatialMap<'tree, P, DIM, LG, LEVEL, T>
@@ -0,0 +1,580 @@
+// Copyright 2016 spatial-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::cmp;
+use std::marker::PhantomData;
+
+use num::{Float, FromPrimitive, One, Zero};
+use ordered_float::OrderedFloat;
+
+use crate::geometry::Rect;
+use crate::tree::mbr::index::rstar::Margin;
+use crate::tree::mbr::index::{Follow, IndexInsert, LeafPath, D_MAX};
+use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode};
+use crate::tree::xmbr::XTreeNode;
+use crate::Scalar;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const D_SPLIT_P: f32 = 0.40f32;
+const D_MIN_FANOUT_P: f32 = 0.35f32;
+const D_MAX_OVERLAP: f32 = 0.20f32;
+
+#[derive(Debug)]
+#[must_use]
+enum InsertResult<P: Scalar, const DIM: usize, LG, T> {
+    Ok,
+    Split(XTreeNode<P, DIM, LG, T>),
+}
+
+/// The split axis and the children split off
+type SplitResult<V> = (usize, Vec<V>);
+
+/// Set the axis in a split history bitset. Axes past `usize::BITS` are not tracked
+pub(crate) fn with_split_dim(split_dims: usize, axis: usize) -> usize {
+    split_dims | 1usize.checked_shl(axis as u32).unwrap_or(0)
+}
+
+/// The supernode size needed to hold len children, or `None` if a regular node will do
+pub(crate) fn super_node_size_for(len: usize, max: usize) -> Option<usize> {
+    if len > max {
+        Some((len + max - 1) / max * max)
+    } else {
+        None
+    }
+}
+
+fn mbr_of<P: Scalar, const DIM: usize, V: MbrLeafGeometry<P, DIM>>(children: &[V]) -> Rect<P, DIM> {
+    let mut mbr = Rect::max_inverted();
+    for child in children {
+        child.expand_mbr_to_fit(&mut mbr);
+    }
+    mbr
+}
+
+/// The mbrs of every prefix and suffix of the children. `prefix[i]` bounds `children[..=i]` and `suffix[i]` bounds `children[i..]`
+fn prefix_suffix_mbrs<P: Scalar, const DIM: usize, V: MbrLeafGeometry<P, DIM>>(
+    children: &[V],
+) -> (Vec<Rect<P, DIM>>, Vec<Rect<P, DIM>>) {
+    let mut prefix = Vec::with_capacity(children.len());
+    let mut mbr = Rect::max_inverted();
+    for child in children {
+        child.expand_mbr_to_fit(&mut mbr);
+        prefix.push(mbr.clone());
+    }
+    let mut suffix = Vec::with_capacity(children.len());
+    let mut mbr = Rect::max_inverted();
+    for child in children.iter().rev() {
+        child.expand_mbr_to_fit(&mut mbr);
+        suffix.push(mbr.clone());
+    }
+    suffix.reverse();
+    (prefix, suffix)
+}
+
+/// Volume of the intersection of two rectangles relative to the volume of their union.
+/// Every volume is measured relative to the rectangle bounding both, so high dimensional volumes can't overflow
+fn overlap_ratio<P: Scalar, const DIM: usize>(r1: &Rect<P, DIM>, r2: &Rect<P, DIM>) -> P::Measure {
+    let (r1, r2) = (r1.to_measure(), r2.to_measure());
+    let (overlap, v1, v2) = r1.iter().zip(r2.iter()).fold(
+        (One::one(), One::one(), One::one()),
+        |(overlap, v1, v2): (P::Measure, P::Measure, P::Measure), (&(x1, y1), &(x2, y2))| {
+            let bounds = Float::max(y1, y2) - Float::min(x1, x2);
+            if bounds <= Zero::zero() {
+                return (overlap, v1, v2);
+            }
+            let overlap_len = Float::max(Float::min(y1, y2) - Float::max(x1, x2), Zero::zero());
+            (
+                overlap * overlap_len / bounds,
+                v1 * (y1 - x1) / bounds,
+                v2 * (y2 - x2) / bounds,
+            )
+        },
+    );
+    let union = v1 + v2 - overlap;
+    if union <= Zero::zero() {
+        return Zero::zero();
+    }
+    overlap / union
+}
+
+fn sort_for_axis<P: Scalar, const DIM: usize, V: MbrLeafGeometry<P, DIM>>(
+    children: &mut [V],
+    axis: usize,
+    edge: usize,
+) {
+    if edge == 0 {
+        children.sort_by_key(|child| OrderedFloat(child.min_for_axis(axis).to_measure()));
+    } else {
+        children.sort_by_key(|child| OrderedFloat(child.max_for_axis(axis).to_measure()));
+    }
+}
+
+/// X-tree insertion.
+///
+/// Algorithms described by Berchtold, S.; Keim, D. A.; Kriegel, H. P. (1996). "The X-tree: An Index Structure for High-Dimensional Data"
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "crate::serde_tree::RawXInsert"))]
+pub struct XInsert<P: Scalar, const DIM: usize, LG, T> {
+    pub(crate) max: usize,
+    pub(crate) preferred_min: usize,
+    pub(crate) min_fanout: usize,
+    pub(crate) max_overlap: f32,
+    pub(crate) _p: PhantomData<P>,
+    pub(crate) _lg: PhantomData<LG>,
+    pub(crate) _t: PhantomData<T>,
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> XInsert<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    pub fn new() -> XInsert<P, DIM, LG, T> {
+        XInsert::new_with_options(D_MAX, D_SPLIT_P, D_MIN_FANOUT_P, D_MAX_OVERLAP)
+    }
+
+    pub fn new_with_max(max: usize) -> XInsert<P, DIM, LG, T> {
+        XInsert::new_with_options(max, D_SPLIT_P, D_MIN_FANOUT_P, D_MAX_OVERLAP)
+    }
+
+    pub fn new_with_options(
+        max: usize,
+        split_p: f32,
+        min_fanout_p: f32,
+        max_overlap: f32,
+    ) -> XInsert<P, DIM, LG, T> {
+        let preferred_min = cmp::max((max as f32 * split_p) as usize, 1);
+        let min_fanout = cmp::max((max as f32 * min_fanout_p) as usize, 1);
+        assert!(
+            max >= 2 * preferred_min,
+            "max({:?}) must be at least twice the min({:?})",
+            max,
+            preferred_min
+        );
+        XInsert {
+            max,
+            preferred_min,
+            min_fanout,
+            max_overlap,
+            _p: PhantomData,
+            _lg: PhantomData,
+            _t: PhantomData,
+        }
+    }
+
+    fn area_cost(
+        &self,
+        mbr: &Rect<P, DIM>,
+        leaf: &MbrLeaf<P, DIM, LG, T>,
+    ) -> (OrderedFloat<P::Measure>, OrderedFloat<P::Measure>) {
+        let mut expanded = mbr.clone();
+        leaf.expand_mbr_to_fit(&mut expanded);
+        let mbr_area = mbr.area();
+        let area_cost = expanded.area() - mbr_area;
+        (OrderedFloat(area_cost), OrderedFloat(mbr_area))
+    }
+
+    /// The index of the child to insert the leaf into
+    fn choose_subnode(
+        &self,
+        level: &[XTreeNode<P, DIM, LG, T>],
+        leaf: &MbrLeaf<P, DIM, LG, T>,
+    ) -> usize {
+        assert!(!level.is_empty(), "Level should not be empty!");
+        level
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, a)| (self.area_cost(a.mbr(), leaf), a.margin_key()))
+            .unwrap()
+            .0
+    }
+
+    fn insert_into_level(
+        &self,
+        level: &mut XTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+        path: &mut LeafPath,
+        follow: Follow,
+    ) -> InsertResult<P, DIM, LG, T> {
+        //I4
+        leaf.geometry.expand_mbr_to_fit(level.mbr_mut());
+        match *level {
+            //I2
+            XTreeNode::Leaves {
+                ref mut children, ..
+            } => {
+                path.pushed(follow, children.len());
+                children.push(leaf);
+            }
+            //I1
+            XTreeNode::Level {
+                ref mut children, ..
+            } => {
+                let index = self.choose_subnode(children, &leaf);
+                let child_follow = path.child(follow, index);
+                let insert_result =
+                    self.insert_into_level(&mut children[index], leaf, path, child_follow);
+                //I3
+                if let InsertResult::Split(child) = insert_result {
+                    path.pushed_split(follow, children.len());
+                    children.push(child);
+                }
+            }
+        }
+        if level.len() > level.super_node_size().unwrap_or(self.max) {
+            return self.handle_overflow(level, path, follow);
+        }
+        InsertResult::Ok
+    }
+
+    fn insert_following(
+        &self,
+        mut root: XTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+        path: &mut LeafPath,
+        follow: Follow,
+    ) -> XTreeNode<P, DIM, LG, T> {
+        if let InsertResult::Split(split) = self.insert_into_level(&mut root, leaf, path, follow) {
+            path.split_root(follow);
+            let mut mbr = root.mbr().clone();
+            split.expand_mbr_to_fit(&mut mbr);
+            root = XTreeNode::Level {
+                mbr,
+                split_dims: 0,
+                super_node_size: None,
+                children: vec![root, split],
+            };
+        }
+        root
+    }
+
+    /// The R* topological split. Chooses the axis with the smallest margin sum,
+    /// then the distribution along it with the least overlap.
+    /// Returns the split axis and the entries split off from the children
+    fn topological_split<V: MbrLeafGeometry<P, DIM>>(
+        &self,
+        children: &mut Vec<V>,
+    ) -> SplitResult<V> {
+        let min = self.preferred_min;
+        let mut best_axis = (0, None);
+        for axis in 0..DIM {
+            let mut margin: P::Measure = Zero::zero();
+            let mut best_distribution = None;
+            for edge in 0..2 {
+                sort_for_axis(children, axis, edge);
+                let (prefix, suffix) = prefix_suffix_mbrs(children);
+                for k in min..=(children.len() - min) {
+                    let (r1, r2) = (&prefix[k - 1], &suffix[k]);
+                    margin += r1.margin() + r2.margin();
+                    let key: (OrderedFloat<P::Measure>, OrderedFloat<P::Measure>) = (
+                        OrderedFloat(overlap_ratio(r1, r2)),
+                        OrderedFloat(r1.area() + r2.area()),
+                    );
+                    if best_distribution
+                        .as_ref()
+                        .map_or(true, |&(best, _, _)| key < best)
+                    {
+                        best_distribution = Some((key, edge, k));
+                    }
+                }
+            }
+            let margin: OrderedFloat<P::Measure> = OrderedFloat(margin);
+            if best_axis
+                .1
+                .as_ref()
+                .map_or(true, |&(best, _)| margin < best)
+            {
+                best_axis = (axis, Some((margin, best_distribution.unwrap())));
+            }
+        }
+        let (axis, (_, (_, edge, k))) = (best_axis.0, best_axis.1.unwrap());
+        sort_for_axis(children, axis, edge);
+        (axis, children.split_off(k))
+    }
+
+    /// Split along an axis every child has already been split along, which can separate the children without overlap.
+    /// Returns `None` if no such split leaves both sides with at least the minimum fanout
+    fn overlap_minimal_split<V: MbrLeafGeometry<P, DIM>, D: Fn(&V) -> usize>(
+        &self,
+        children: &mut Vec<V>,
+        split_dims: D,
+    ) -> Option<SplitResult<V>> {
+        let common_dims = children
+            .iter()
+            .fold(!0usize, |dims, child| dims & split_dims(child));
+        if common_dims == 0 || children.len() < 2 * self.min_fanout {
+            return None;
+        }
+        let half = children.len() / 2;
+        let mut best_key: Option<(OrderedFloat<P::Measure>, usize)> = None;
+        let mut best_split = (0, 0, 0);
+        for axis in (0..DIM).filter(|&axis| with_split_dim(0, axis) & common_dims != 0) {
+            for edge in 0..2 {
+                sort_for_axis(children, axis, edge);
+                let (prefix, suffix) = prefix_suffix_mbrs(children);
+                for k in self.min_fanout..=(children.len() - self.min_fanout) {
+                    let (r1, r2) = (&prefix[k - 1], &suffix[k]);
+                    // overlap along the split axis
+                    let lo = r1[axis].0.partial_max(r2[axis].0);
+                    let hi = r1[axis].1.partial_min(r2[axis].1);
+                    let overlap = Float::max(hi.to_measure() - lo.to_measure(), Zero::zero());
+                    let key = (OrderedFloat(overlap), cmp::max(k, half) - cmp::min(k, half));
+                    if best_key.map_or(true, |best| key < best) {
+                        best_key = Some(key);
+                        best_split = (axis, edge, k);
+                    }
+                }
+            }
+        }
+        match best_key {
+            Some((overlap, _)) if overlap.into_inner() <= Zero::zero() => {
+                let (axis, edge, k) = best_split;
+                sort_for_axis(children, axis, edge);
+                Some((axis, children.split_off(k)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Split the level in two. Directory levels whose best split overlaps too much try an overlap-minimal split,
+    /// and grow into supernodes when that isn't possible either
+    fn handle_overflow(
+        &self,
+        level: &mut XTreeNode<P, DIM, LG, T>,
+        path: &mut LeafPath,
+        follow: Follow,
+    ) -> InsertResult<P, DIM, LG, T> {
+        let max_overlap: P::Measure = FromPrimitive::from_f32(self.max_overlap).unwrap();
+        let split = match *level {
+            XTreeNode::Leaves {
+                ref mut mbr,
+                ref mut split_dims,
+                ref mut super_node_size,
+                ref mut children,
+            } => {
+                let (axis, split_children) = path.split(follow, children, |children| {
+                    self.topological_split(children)
+                });
+                *mbr = mbr_of(children);
+                *split_dims = with_split_dim(*split_dims, axis);
+                *super_node_size = super_node_size_for(children.len(), self.max);
+                XTreeNode::Leaves {
+                    mbr: mbr_of(&split_children),
+                    split_dims: *split_dims,
+                    super_node_size: super_node_size_for(split_children.len(), self.max),
+                    children: split_children,
+                }
+            }
+            XTreeNode::Level {
+                ref mut mbr,
+                ref mut split_dims,
+                ref mut super_node_size,
+                ref mut children,
+            } => {
+                let (axis, split_children) = path.split(follow, children, |children| {
+                    let (axis, mut split_children) = self.topological_split(children);
+                    if overlap_ratio(&mbr_of(children), &mbr_of(&split_children)) <= max_overlap {
+                        return (Some(axis), split_children);
+                    }
+                    children.append(&mut split_children);
+                    match self.overlap_minimal_split(children, |child| child.child.split_dims()) {
+                        Some((minimal_axis, minimal_children)) => {
+                            (Some(minimal_axis), minimal_children)
+                        }
+                        None => (None, split_children),
+                    }
+                });
+                let axis = match axis {
+                    Some(axis) => axis,
+                    None => {
+                        // extend the node into a supernode rather than split it
+                        *super_node_size = Some(super_node_size.unwrap_or(self.max) + self.max);
+                        return InsertResult::Ok;
+                    }
+                };
+                *mbr = mbr_of(children);
+                *split_dims = with_split_dim(*split_dims, axis);
+                *super_node_size = super_node_size_for(children.len(), self.max);
+                XTreeNode::Level {
+                    mbr: mbr_of(&split_children),
+                    split_dims: *split_dims,
+                    super_node_size: super_node_size_for(split_children.len(), self.max),
+                    children: split_children,
+                }
+            }
+        };
+        InsertResult::Split(split)
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> XTreeNode<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn margin_key(&self) -> OrderedFloat<P::Measure> {
+        OrderedFloat(self.mbr().margin())
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> IndexInsert<P, DIM, LG, T, XTreeNode<P, DIM, LG, T>>
+    for XInsert<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn insert_into_root(
+        &self,
+        root: XTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+    ) -> XTreeNode<P, DIM, LG, T> {
+        self.insert_following(root, leaf, &mut LeafPath::default(), Follow::Nothing)
+    }
+
+    fn insert_into_root_at(
+        &self,
+        root: XTreeNode<P, DIM, LG, T>,
+        leaf: MbrLeaf<P, DIM, LG, T>,
+    ) -> (XTreeNode<P, DIM, LG, T>, Vec<usize>) {
+        let mut path = LeafPath::default();
+        let root = self.insert_following(root, leaf, &mut path, Follow::Inserted(0));
+        (root, path.path)
+    }
+
+    fn preferred_min(&self) -> usize {
+        self.preferred_min
+    }
+
+    fn max(&self) -> usize {
+        self.max
+    }
+
+    fn new_leaves(&self) -> XTreeNode<P, DIM, LG, T> {
+        XTreeNode::new_leaves()
+    }
+
+    fn new_no_alloc_leaves(&self) -> XTreeNode<P, DIM, LG, T> {
+        XTreeNode::new_no_alloc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+    use crate::tree::mbr::index::r::RRemove;
+    use crate::tree::mbr::index::IndexRemove;
+    use crate::tree::mbr::{MbrNodeChildren, MbrRectQuery, NodeChildren};
+    use crate::tree::xmbr::XTree;
+
+    #[test]
+    fn split_history() {
+        assert_eq!(0b101, with_split_dim(with_split_dim(0, 0), 2));
+        assert_eq!(0, with_split_dim(0, usize::BITS as usize));
+    }
+
+    #[test]
+    fn super_node_size() {
+        assert_eq!(None, super_node_size_for(16, 16));
+        assert_eq!(Some(32), super_node_size_for(17, 16));
+        assert_eq!(Some(48), super_node_size_for(33, 16));
+    }
+
+    #[test]
+    fn bulk_loaded_split_history() {
+        fn check_levels<LG: MbrLeafGeometry<f64, 2>>(node: &XTreeNode<f64, 2, LG, usize>) {
+            if let NodeChildren::Levels(children) = node.children() {
+                for child in children {
+                    // STR cuts every node from its siblings along the first axis
+                    assert_eq!(1, child.split_dims() & 1);
+                    check_levels(child);
+                }
+            }
+        }
+
+        let mut map = XTree::new_with_max(4);
+        map.bulk_load((0..200).map(|i| (Point::new([(i % 13) as f64, (i / 13) as f64]), i)));
+        assert!(map.root.has_levels());
+        assert_eq!(0, map.root.split_dims());
+        check_levels(&map.root);
+    }
+
+    #[test]
+    fn overlap() {
+        let r1 = Rect::from_corners([0.0f64, 0.0f64], [2.0f64, 2.0f64]);
+        let r2 = Rect::from_corners([1.0f64, 0.0f64], [3.0f64, 2.0f64]);
+        let r3 = Rect::from_corners([2.0f64, 0.0f64], [3.0f64, 2.0f64]);
+        assert_relative_eq!(1.0f64 / 3.0f64, overlap_ratio(&r1, &r2));
+        assert_relative_eq!(0.0f64, overlap_ratio(&r1, &r3));
+        assert_relative_eq!(1.0f64, overlap_ratio(&r1, &r1));
+    }
+
+    #[test]
+    fn overlap_minimal_split() {
+        let insert = XInsert::new_with_max(20);
+        let directory = |split_dims| {
+            // 7 children on the left and 14 on the right can only be split without overlap
+            // below the preferred min of 8, but not below the min fanout of 7
+            let children = (0..21)
+                .map(|i| {
+                    let x = if i < 7 { 0.0f64 } else { 1.0f64 };
+                    let leaf =
+                        MbrLeaf::new(Rect::from_corners([x, 0.0f64], [x + 1.0f64, 1.0f64]), i);
+                    let mut child = XTreeNode::from_leaves(vec![leaf]);
+                    *child.split_dims_mut() = split_dims;
+                    child
+                })
+                .collect();
+            XTreeNode::from_levels(children)
+        };
+
+        let mut level = directory(with_split_dim(0, 0));
+        match insert.handle_overflow(&mut level, &mut LeafPath::default(), Follow::Nothing) {
+            InsertResult::Split(split) => {
+                let (left, right) = if level.len() < split.len() {
+                    (&level, &split)
+                } else {
+                    (&split, &level)
+                };
+                assert_eq!((7, 14), (left.len(), right.len()));
+                assert_relative_eq!(0.0f64, overlap_ratio(left.mbr(), right.mbr()));
+                assert_eq!(1, level.split_dims() & 1);
+            }
+            InsertResult::Ok => {
+                panic!("children split along the first axis must split without overlap")
+            }
+        }
+        assert!(!level.is_super());
+
+        // without a shared split axis, the only way out is a supernode
+        let mut level = directory(0);
+        assert!(matches!(
+            insert.handle_overflow(&mut level, &mut LeafPath::default(), Follow::Nothing),
+            InsertResult::Ok
+        ));
+        assert!(level.is_super());
+    }
+
+    #[test]
+    fn super_nodes() {
+        let insert = XInsert::new_with_max(4);
+        let remove = RRemove::with_min(insert.preferred_min());
+        let mut root = insert.new_leaves();
+        // identical rects can't be split without overlap
+        for i in 0..64 {
+            let leaf = MbrLeaf::new(Rect::from_corners([0.0f64, 0.0f64], [1.0f64, 1.0f64]), i);
+            root = insert.insert_into_root(root, leaf);
+        }
+        assert!(root.has_levels());
+        assert!(root.is_super());
+        assert!(root.len() <= root.super_node_size().unwrap());
+
+        let query = MbrRectQuery::Overlaps(Rect::max());
+        let (root, removed) = remove.remove_from_root(root, &insert, query, |&i| i < 4);
+        assert_eq!(60, removed.len());
+        assert!(!root.is_super());
+    }
+}
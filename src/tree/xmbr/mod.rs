@@ -5,7 +5,80 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-mod index;
+//! Collection of minimum bounding rectangle spatial trees with supernodes
+
+pub(crate) mod index;
 mod node;
 
+use std::marker::PhantomData;
+use crate::tree::mbr::index::IndexInsert;
+use crate::tree::mbr::{MbrLeafGeometry, MbrMap};
+use crate::tree::mbr::index::r::RRemove;
+use crate::tree::xmbr::index::x::XInsert;
 pub use crate::tree::xmbr::node::XTreeNode;
+use crate::Scalar;
+
+/// X Tree Type
+pub type XTreeMap<P, const DIM: usize, LG, T> =
+    MbrMap<XTreeNode<P, DIM, LG, T>, XInsert<P, DIM, LG, T>, RRemove<P, DIM, LG, T>>;
+
+/// Convenience struct for creating a new X Tree
+///
+/// Algorithms described by Berchtold, S.; Keim, D. A.; Kriegel, H. P. (1996). "The X-tree: An Index Structure for High-Dimensional Data"
+pub struct XTree<P: Scalar, const DIM: usize, LG, T> {
+    _p: PhantomData<P>,
+    _lg: PhantomData<LG>,
+    _t: PhantomData<T>,
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> XTree<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    /// Create a new X tree with min and max children lengths set to 25 and 64, respectively
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> XTreeMap<P, DIM, LG, T> {
+        XTree::map_from_insert(XInsert::new())
+    }
+
+    /// Create a new X tree with max children lengths as provided. min length will be set to 0.4 * max
+    pub fn new_with_max(max: usize) -> XTreeMap<P, DIM, LG, T> {
+        XTree::map_from_insert(XInsert::new_with_max(max))
+    }
+
+    /// Create a new X tree with options as provided. min children will be set to split_p * max.
+    /// Directory splits overlapping more than max_overlap are avoided, and nodes never split below min_fanout_p * max
+    pub fn new_with_options(
+        max: usize,
+        split_p: f32,
+        min_fanout_p: f32,
+        max_overlap: f32,
+    ) -> XTreeMap<P, DIM, LG, T> {
+        XTree::map_from_insert(XInsert::new_with_options(
+            max,
+            split_p,
+            min_fanout_p,
+            max_overlap,
+        ))
+    }
+
+    fn map_from_insert(x_insert: XInsert<P, DIM, LG, T>) -> XTreeMap<P, DIM, LG, T> {
+        let min = x_insert.preferred_min();
+        MbrMap::new(x_insert, RRemove::with_min(min))
+    }
+}
+
+/// Bulk loads a default X Tree.
+/// Panics on geometry with a non-finite coordinate, which `MbrMap::try_bulk_load` hands back instead
+impl<P: Scalar, const DIM: usize, LG, T> FromIterator<(LG, T)> for XTreeMap<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    fn from_iter<ITER: IntoIterator<Item = (LG, T)>>(items: ITER) -> XTreeMap<P, DIM, LG, T> {
+        let mut map = XTree::new();
+        if let Err(error) = map.try_bulk_load(items) {
+            panic!("{}", error);
+        }
+        map
+    }
+}
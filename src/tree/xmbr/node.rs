@@ -5,53 +5,110 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use crate::geometry::Rect;
+use crate::geometry::{Point, Rect};
 use std::fmt::Debug;
-use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode};
-use crate::FP;
+use crate::tree::mbr::{MbrLeaf, MbrLeafGeometry, MbrNode, MbrNodeChildren, NodeChildren};
+use crate::tree::xmbr::index::x::{super_node_size_for, with_split_dim};
+use crate::Scalar;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Level node of a tree. Either contains other levels or leaves
+///
+/// `split_dims` is the node's split history: a bitset of every axis the node has been split along.
+/// Axes past `usize::BITS` are not tracked.
+/// `super_node_size` is the extended capacity of a supernode, or `None` for a regular node
 #[derive(Debug)]
-pub enum XTreeNode<P: FP, const DIM: usize, LG, T> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum XTreeNode<P: Scalar, const DIM: usize, LG, T> {
     /// Contains only other levels
     Level {
+        #[cfg_attr(
+            feature = "serde",
+            serde(deserialize_with = "crate::serde_geometry::deserialize_mbr")
+        )]
         mbr: Rect<P, DIM>,
-        // TODO: Replace with bitset
-        split_dim: usize,
+        split_dims: usize,
         super_node_size: Option<usize>,
         children: Vec<XTreeNode<P, DIM, LG, T>>,
     },
     /// Contains only leaves
     Leaves {
+        #[cfg_attr(
+            feature = "serde",
+            serde(deserialize_with = "crate::serde_geometry::deserialize_mbr")
+        )]
         mbr: Rect<P, DIM>,
+        split_dims: usize,
         super_node_size: Option<usize>,
         children: Vec<MbrLeaf<P, DIM, LG, T>>,
     },
 }
 
-impl<P: FP, const DIM: usize, LG, T> XTreeNode<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> XTreeNode<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
+    /// Is the node a supernode?
     pub fn is_super(&self) -> bool {
+        self.super_node_size().is_some()
+    }
+
+    /// The extended capacity of a supernode
+    pub fn super_node_size(&self) -> Option<usize> {
         match *self {
             XTreeNode::Level {
                 super_node_size, ..
-            } => super_node_size.is_some(),
+            } => super_node_size,
             XTreeNode::Leaves {
                 super_node_size, ..
-            } => super_node_size.is_some(),
+            } => super_node_size,
+        }
+    }
+
+    /// Mutably borrow the extended capacity of a supernode
+    pub fn super_node_size_mut(&mut self) -> &mut Option<usize> {
+        match *self {
+            XTreeNode::Level {
+                ref mut super_node_size,
+                ..
+            } => super_node_size,
+            XTreeNode::Leaves {
+                ref mut super_node_size,
+                ..
+            } => super_node_size,
+        }
+    }
+
+    /// The bitset of axes the node has been split along
+    pub fn split_dims(&self) -> usize {
+        match *self {
+            XTreeNode::Level { split_dims, .. } => split_dims,
+            XTreeNode::Leaves { split_dims, .. } => split_dims,
+        }
+    }
+
+    /// Mutably borrow the bitset of axes the node has been split along
+    pub fn split_dims_mut(&mut self) -> &mut usize {
+        match *self {
+            XTreeNode::Level {
+                ref mut split_dims, ..
+            } => split_dims,
+            XTreeNode::Leaves {
+                ref mut split_dims, ..
+            } => split_dims,
         }
     }
 }
 
-impl<P: FP, const DIM: usize, LG, T> MbrNode<P, DIM> for XTreeNode<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> MbrNode<P, DIM> for XTreeNode<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
     fn new_leaves() -> XTreeNode<P, DIM, LG, T> {
         XTreeNode::Leaves {
             mbr: Rect::max_inverted(),
+            split_dims: 0,
             super_node_size: None,
             children: Vec::new(),
         }
@@ -60,6 +117,7 @@ where
     fn new_no_alloc() -> XTreeNode<P, DIM, LG, T> {
         XTreeNode::Leaves {
             mbr: Rect::max_inverted(),
+            split_dims: 0,
             super_node_size: None,
             children: Vec::with_capacity(0),
         }
@@ -105,7 +163,88 @@ where
     }
 }
 
-impl<P: FP, const DIM: usize, LG, T> MbrLeafGeometry<P, DIM> for XTreeNode<P, DIM, LG, T>
+impl<P: Scalar, const DIM: usize, LG, T> MbrNodeChildren for XTreeNode<P, DIM, LG, T>
+where
+    LG: MbrLeafGeometry<P, DIM>,
+{
+    type Leaf = MbrLeaf<P, DIM, LG, T>;
+
+    fn from_leaves(children: Vec<MbrLeaf<P, DIM, LG, T>>) -> XTreeNode<P, DIM, LG, T> {
+        let mut mbr = Rect::max_inverted();
+        for child in &children {
+            child.expand_mbr_to_fit(&mut mbr);
+        }
+        XTreeNode::Leaves {
+            mbr,
+            split_dims: 0,
+            super_node_size: None,
+            children,
+        }
+    }
+
+    fn from_levels(children: Vec<XTreeNode<P, DIM, LG, T>>) -> XTreeNode<P, DIM, LG, T> {
+        let mut mbr = Rect::max_inverted();
+        for child in &children {
+            child.expand_mbr_to_fit(&mut mbr);
+        }
+        XTreeNode::Level {
+            mbr,
+            split_dims: 0,
+            super_node_size: None,
+            children,
+        }
+    }
+
+    fn children(&self) -> NodeChildren<&[Self], &[MbrLeaf<P, DIM, LG, T>]> {
+        match *self {
+            XTreeNode::Level { ref children, .. } => NodeChildren::Levels(children),
+            XTreeNode::Leaves { ref children, .. } => NodeChildren::Leaves(children),
+        }
+    }
+
+    fn children_mut(&mut self) -> NodeChildren<&mut [Self], &mut [MbrLeaf<P, DIM, LG, T>]> {
+        match *self {
+            XTreeNode::Level {
+                ref mut children, ..
+            } => NodeChildren::Levels(children),
+            XTreeNode::Leaves {
+                ref mut children, ..
+            } => NodeChildren::Leaves(children),
+        }
+    }
+
+    fn children_vec_mut(
+        &mut self,
+    ) -> NodeChildren<&mut Vec<Self>, &mut Vec<MbrLeaf<P, DIM, LG, T>>> {
+        match *self {
+            XTreeNode::Level {
+                ref mut children, ..
+            } => NodeChildren::Levels(children),
+            XTreeNode::Leaves {
+                ref mut children, ..
+            } => NodeChildren::Leaves(children),
+        }
+    }
+
+    fn into_children(self) -> NodeChildren<Vec<Self>, Vec<MbrLeaf<P, DIM, LG, T>>> {
+        match self {
+            XTreeNode::Level { children, .. } => NodeChildren::Levels(children),
+            XTreeNode::Leaves { children, .. } => NodeChildren::Leaves(children),
+        }
+    }
+
+    fn record_split(&mut self, axis: usize) {
+        let split_dims = self.split_dims_mut();
+        *split_dims = with_split_dim(*split_dims, axis);
+    }
+
+    fn record_removal(&mut self, max: usize) {
+        let len = self.len();
+        *self.super_node_size_mut() = super_node_size_for(len, max);
+    }
+}
+
+impl<P: Scalar, const DIM: usize, LG, T> MbrLeafGeometry<P, DIM> for XTreeNode<P, DIM, LG, T>
 where
     LG: MbrLeafGeometry<P, DIM>,
 {
@@ -117,10 +256,22 @@ where
         self.mbr().expand_mbr_to_fit(mbr)
     }
 
-    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P {
+    fn distance_from_mbr_center(&self, mbr: &Rect<P, DIM>) -> P::Measure {
         self.mbr().distance_from_mbr_center(mbr)
     }
 
+    fn distance_from_point(&self, point: &Point<P, DIM>) -> P::Measure {
+        self.mbr().distance_from_point(point)
+    }
+
+    fn min_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr().min_distance_from_mbr(mbr)
+    }
+
+    fn min_max_distance_from_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
+        self.mbr().min_max_distance_from_mbr(mbr)
+    }
+
     fn contained_by_mbr(&self, mbr: &Rect<P, DIM>) -> bool {
         self.mbr().contained_by_mbr(mbr)
     }
@@ -129,11 +280,11 @@ where
         self.mbr().overlapped_by_mbr(mbr)
     }
 
-    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P {
+    fn area_overlapped_with_mbr(&self, mbr: &Rect<P, DIM>) -> P::Measure {
         self.mbr().area_overlapped_with_mbr(mbr)
     }
 
-    fn area(&self) -> P {
+    fn area(&self) -> P::Measure {
         self.mbr().area()
     }
 
@@ -67,14 +67,14 @@ mod tests {
     where
         T: PartialEq,
     {
-        fn contains_all(&self, that: &Vec<T>) -> bool;
+        fn contains_all(&self, that: &[T]) -> bool;
     }
 
     impl<T> ContainsAll<T> for Vec<T>
     where
         T: PartialEq,
     {
-        fn contains_all(&self, items: &Vec<T>) -> bool {
+        fn contains_all(&self, items: &[T]) -> bool {
             for item in items {
                 if !self.contains(item) {
                     return false;
@@ -0,0 +1,215 @@
+extern crate spatial;
+
+use spatial::geometry::{LineSegment, LineString, Point, Rect, Shapes};
+use spatial::tree::mbr::{FrozenError, FrozenMap, MbrRectQuery};
+use spatial::{RStar, XTree};
+
+fn grid_point(i: i32) -> Point<f32, 3> {
+    Point::new([(i % 10) as f32, (i / 10 % 10) as f32, (i / 100) as f32])
+}
+
+fn payload(bytes: &[u8]) -> i32 {
+    let mut le = [0u8; 4];
+    le.copy_from_slice(bytes);
+    i32::from_le_bytes(le)
+}
+
+#[test]
+fn frozen_rstar() {
+    let mut tree_map = RStar::new_with_max(16);
+    for i in 0..1000 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let bytes = tree_map
+        .freeze(|i, bytes| bytes.extend_from_slice(&i.to_le_bytes()))
+        .unwrap();
+
+    let frozen: FrozenMap<f32, 3, Point<f32, 3>> = FrozenMap::from_bytes(&bytes).unwrap();
+    assert_eq!(1000, frozen.len());
+    assert_eq!(1000, frozen.iter().count());
+
+    let query = MbrRectQuery::ContainedBy(Rect::from_corners(
+        [-0.5f32, -0.5f32, -0.5f32],
+        [4.5f32, 9.5f32, 9.5f32],
+    ));
+    let mut expected: Vec<i32> = tree_map
+        .iter_query(query.clone())
+        .map(|(_, &i)| i)
+        .collect();
+    let mut found: Vec<i32> = frozen
+        .iter_query(query)
+        .map(|entry| {
+            let (point, bytes) = entry.unwrap();
+            assert_eq!(grid_point(payload(bytes)).coords, point.coords);
+            payload(bytes)
+        })
+        .collect();
+    expected.sort();
+    found.sort();
+    assert_eq!(500, found.len());
+    assert_eq!(expected, found);
+}
+
+#[test]
+fn frozen_xtree_shapes() {
+    let mut tree_map = XTree::new_with_max(8);
+    for i in 0..200 {
+        let i_f64 = i as f64;
+        let shape = match i % 3 {
+            0 => Shapes::Point(Point::new([i_f64, 0.0f64])),
+            1 => Shapes::LineSegment(LineSegment::new([i_f64, 0.0f64], [i_f64, 1.0f64])),
+            _ => Shapes::Rect(Rect::from_corners(
+                [i_f64, 0.0f64],
+                [i_f64 + 0.5f64, 1.0f64],
+            )),
+        };
+        tree_map.insert(shape, format!("item {}", i));
+    }
+    let bytes = tree_map
+        .freeze(|item, bytes| bytes.extend_from_slice(item.as_bytes()))
+        .unwrap();
+    let frozen: FrozenMap<f64, 2, Shapes<f64, 2>> = FrozenMap::from_bytes(&bytes).unwrap();
+    let found: Vec<(Shapes<f64, 2>, &[u8])> = frozen
+        .iter_query(MbrRectQuery::Overlaps(Rect::from_corners(
+            [9.9f64, -1.0f64],
+            [10.1f64, 2.0f64],
+        )))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(1, found.len());
+    assert_eq!(b"item 10", found[0].1);
+    assert!(matches!(found[0].0, Shapes::LineSegment(_)));
+
+    tree_map.insert(
+        Shapes::Custom(Box::new(Point::new([1.0f64, 2.0f64]))),
+        "custom".to_string(),
+    );
+    assert_eq!(
+        Err(FrozenError::UnsupportedGeometry),
+        tree_map.freeze(|item, bytes| bytes.extend_from_slice(item.as_bytes()))
+    );
+}
+
+#[test]
+fn frozen_line_strings() {
+    let mut tree_map = RStar::new_with_max(4);
+    for i in 0..40 {
+        let i_f64 = i as f64;
+        let road = LineString::new(vec![
+            [i_f64, 0.0f64],
+            [i_f64 + 0.5f64, 1.0f64],
+            [i_f64, 2.0f64],
+        ]);
+        tree_map.insert(Shapes::LineString(road), i as u32);
+    }
+    let bytes = tree_map
+        .freeze(|item, bytes| bytes.extend_from_slice(&item.to_le_bytes()))
+        .unwrap();
+    let frozen: FrozenMap<f64, 2, Shapes<f64, 2>> = FrozenMap::from_bytes(&bytes).unwrap();
+    let found: Vec<(Shapes<f64, 2>, &[u8])> = frozen
+        .iter_query(MbrRectQuery::Overlaps(Rect::from_corners(
+            [7.4f64, 0.9f64],
+            [7.6f64, 1.1f64],
+        )))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(1, found.len());
+    assert_eq!(&7u32.to_le_bytes(), found[0].1);
+    let expected = tree_map.iter().find(|&(_, &i)| i == 7).unwrap().0;
+    assert_eq!(expected, &found[0].0);
+}
+
+#[test]
+fn frozen_integers() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..200i32 {
+        let min = [i % 20 * 4, i / 20 * 4];
+        tree_map.insert(Rect::from_corners(min, [min[0] + 2, min[1] + 2]), i);
+    }
+    let bytes = tree_map
+        .freeze(|i, bytes| bytes.extend_from_slice(&i.to_le_bytes()))
+        .unwrap();
+
+    let frozen: FrozenMap<i32, 2, Rect<i32, 2>> = FrozenMap::from_bytes(&bytes).unwrap();
+    assert_eq!(200, frozen.len());
+    let query = MbrRectQuery::Overlaps(Rect::from_corners([10, 10], [30, 17]));
+    let mut expected: Vec<i32> = tree_map
+        .iter_query(query.clone())
+        .map(|(_, &i)| i)
+        .collect();
+    let mut found: Vec<i32> = frozen
+        .iter_query(query)
+        .map(|entry| payload(entry.unwrap().1))
+        .collect();
+    expected.sort_unstable();
+    found.sort_unstable();
+    assert!(!expected.is_empty());
+    assert_eq!(expected, found);
+
+    assert_eq!(
+        Some(FrozenError::ScalarKindMismatch(1)),
+        FrozenMap::<u32, 2, Rect<u32, 2>>::from_bytes(&bytes).err()
+    );
+    assert_eq!(
+        Some(FrozenError::ScalarKindMismatch(1)),
+        FrozenMap::<f32, 2, Rect<f32, 2>>::from_bytes(&bytes).err()
+    );
+
+    let mut wide = RStar::new_with_max(8);
+    for i in 0..50i128 {
+        wide.insert(Point::new([i * (i128::MAX / 64), -i]), i as i32);
+    }
+    let bytes = wide
+        .freeze(|i, bytes| bytes.extend_from_slice(&i.to_le_bytes()))
+        .unwrap();
+    let frozen: FrozenMap<i128, 2, Point<i128, 2>> = FrozenMap::from_bytes(&bytes).unwrap();
+    let query = MbrRectQuery::ContainedBy(Rect::from_corners([0, -9], [i128::MAX, 0]));
+    assert_eq!(10, frozen.iter_query(query).count());
+}
+
+#[test]
+fn frozen_errors() {
+    let mut tree_map = RStar::new_with_max(16);
+    for i in 0..100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let bytes = tree_map
+        .freeze(|i, bytes| bytes.extend_from_slice(&i.to_le_bytes()))
+        .unwrap();
+
+    let open = |bytes: &[u8]| FrozenMap::<f32, 3, Point<f32, 3>>::from_bytes(bytes).err();
+    assert_eq!(Some(FrozenError::BadMagic), open(&bytes[1..]));
+    assert_eq!(Some(FrozenError::Truncated), open(&bytes[..20]));
+    assert_eq!(Some(FrozenError::Truncated), open(&bytes[..100]));
+    assert_eq!(
+        Some(FrozenError::DimensionMismatch(3)),
+        FrozenMap::<f32, 2, Point<f32, 2>>::from_bytes(&bytes).err()
+    );
+    assert_eq!(
+        Some(FrozenError::ScalarMismatch(4)),
+        FrozenMap::<f64, 3, Point<f64, 3>>::from_bytes(&bytes).err()
+    );
+    let mut integers = bytes.clone();
+    integers[7] = 1;
+    assert_eq!(Some(FrozenError::ScalarKindMismatch(1)), open(&integers));
+    let mut newer = bytes.clone();
+    newer[4] = 2;
+    assert_eq!(Some(FrozenError::UnsupportedVersion(2)), open(&newer));
+
+    // the tables are intact, so only queries reaching the missing leaf data fail
+    let truncated =
+        FrozenMap::<f32, 3, Point<f32, 3>>::from_bytes(&bytes[..bytes.len() - 1]).unwrap();
+    let last = truncated.iter().last().unwrap();
+    assert_eq!(Some(FrozenError::Truncated), last.err());
+}
+
+#[test]
+fn frozen_empty() {
+    let tree_map = RStar::<f32, 3, Point<f32, 3>, i32>::new();
+    let bytes = tree_map
+        .freeze(|i, bytes| bytes.extend_from_slice(&i.to_le_bytes()))
+        .unwrap();
+    let frozen: FrozenMap<f32, 3, Point<f32, 3>> = FrozenMap::from_bytes(&bytes).unwrap();
+    assert!(frozen.is_empty());
+    assert_eq!(0, frozen.iter().count());
+}
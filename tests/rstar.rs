@@ -1,8 +1,19 @@
 extern crate spatial;
 
-use spatial::geometry::{Point, Rect};
-use spatial::tree::mbr::MbrRectQuery;
-use spatial::RStar;
+use spatial::geometry::{
+    Capsule, GeometryError, LineSegment, LineString, Point, Polygon, Ray, Rect, Shapes, Sphere,
+    Triangle,
+};
+use spatial::tree::mbr::{
+    And, Entry, Filter, InsertError, MbrPointQuery, MbrPolygonQuery, MbrRectQuery, MbrSphereQuery,
+    Not, Or,
+};
+use spatial::tree::mbr::MbrLeafGeometry;
+use spatial::{RStar, RStarTree, RTree};
+
+fn grid_point(i: i32) -> Point<f32, 3> {
+    Point::new([(i % 10) as f32, (i / 10 % 10) as f32, (i / 100) as f32])
+}
 
 #[test]
 fn rstar_integration() {
@@ -45,3 +56,1411 @@ fn rstar_integration() {
     assert_eq!(32, tree_map.len());
     assert_eq!(tree_map.len(), tree_map.iter().count());
 }
+
+#[test]
+fn rstar_nearest() {
+    let mut tree_map = RStar::new_with_max(16);
+    for i in 0..64 {
+        let i_f32 = i as f32;
+        tree_map.insert(Point::new([i_f32; 3]), i);
+    }
+    let nearest: Vec<i32> = tree_map
+        .nearest(&Point::new([10.2f32; 3]), 3)
+        .into_iter()
+        .map(|(_, &i)| i)
+        .collect();
+    assert_eq!(vec![10, 11, 9], nearest);
+    assert_eq!(64, tree_map.nearest(&Point::new([0.0f32; 3]), 100).len());
+    assert!(tree_map.nearest(&Point::new([0.0f32; 3]), 0).is_empty());
+}
+
+#[test]
+fn rstar_iter_nearest() {
+    let mut tree_map = RStar::new_with_max(16);
+    for i in 0..64 {
+        let i_f32 = i as f32;
+        tree_map.insert(Point::new([i_f32, 0.0f32]), i);
+    }
+    let origin = Point::new([20.4f32, 0.0f32]);
+    let mut last_distance = 0.0f32;
+    for (distance, _, _) in tree_map.iter_nearest(&origin) {
+        assert!(last_distance <= distance);
+        last_distance = distance;
+    }
+    assert_eq!(64, tree_map.iter_nearest(&origin).count());
+
+    // stop at the first item matching a condition we can't express as a query
+    let (distance, _, &item) = tree_map
+        .iter_nearest(&origin)
+        .find(|&(_, _, &i)| i % 7 == 0)
+        .unwrap();
+    assert_eq!(21, item);
+    assert!((distance - 0.6f32).abs() < 0.0001f32);
+}
+
+#[test]
+fn rstar_bulk_load() {
+    let mut tree_map = RStar::new_with_max(16);
+    tree_map.insert(Point::new([-1.0f32; 3]), -1);
+    tree_map.bulk_load((0..1000).map(|i| (grid_point(i), i)));
+    assert_eq!(1001, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    let found = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [4.5f32, 9.5f32, 9.5f32],
+        )))
+        .count();
+    assert_eq!(500, found);
+
+    // the packed tree keeps working with the regular insert and remove algorithms
+    for i in 1000..1100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    assert_eq!(1101, tree_map.len());
+    let removed = tree_map.remove(MbrRectQuery::ContainedBy(Rect::from_corners(
+        [-0.5f32, -0.5f32, -0.5f32],
+        [9.5f32, 4.5f32, 10.5f32],
+    )));
+    assert_eq!(550, removed.len());
+    assert_eq!(551, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+}
+
+#[test]
+fn rstar_try_bulk_load() {
+    let mut tree_map = RStar::new_with_max(16);
+    tree_map.insert(Point::new([-1.0f32; 3]), -1);
+    let nan_point = Point {
+        coords: [1.0f32, f32::NAN, 1.0f32],
+    };
+    let items = (0..100)
+        .map(|i| (grid_point(i), i))
+        .chain(Some((nan_point, 100)))
+        .chain((101..200).map(|i| (grid_point(i), i)));
+    match tree_map.try_bulk_load(items) {
+        Err(InsertError::NonFiniteGeometry(_, item)) => assert_eq!(100, item),
+        Ok(()) => panic!("a NaN point should be rejected"),
+    }
+    // nothing was packed, so the map and its mbrs are untouched
+    assert_eq!(1, tree_map.len());
+    assert_eq!(1, tree_map.iter_query(MbrRectQuery::Overlaps(Rect::max())).count());
+
+    assert!(tree_map
+        .try_bulk_load((0..1000).map(|i| (grid_point(i), i)))
+        .is_ok());
+    assert_eq!(1001, tree_map.len());
+    let found = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [4.5f32, 9.5f32, 9.5f32],
+        )))
+        .count();
+    assert_eq!(500, found);
+}
+
+#[test]
+#[should_panic(expected = "geometry coordinates must be finite")]
+fn rstar_from_iter_rejects_non_finite() {
+    let _: RStarTree<f32, 3, Point<f32, 3>, i32> = (0..100)
+        .map(|i| (grid_point(i), i))
+        .chain(Some((
+            Point {
+                coords: [0.0f32, f32::INFINITY, 0.0f32],
+            },
+            100,
+        )))
+        .collect();
+}
+
+#[test]
+fn rstar_try_insert() {
+    assert_eq!(
+        Err(GeometryError::NonFiniteCoordinate),
+        Point::try_new([0.0f32, f32::NAN, 0.0f32]).map(|_| ())
+    );
+    assert_eq!(
+        Err(GeometryError::NonFiniteCoordinate),
+        LineSegment::try_new([0.0f32; 3], [f32::INFINITY, 0.0f32, 0.0f32]).map(|_| ())
+    );
+    assert_eq!(
+        Err(GeometryError::NonFiniteCoordinate),
+        Rect::try_from_corners([0.0f32; 3], [1.0f32, 1.0f32, f32::NEG_INFINITY]).map(|_| ())
+    );
+    let rect = Rect::try_new([(1.0f32, 0.0f32), (0.0f32, 1.0f32), (2.0f32, 1.0f32)]).unwrap();
+    assert_eq!(
+        [(0.0f32, 1.0f32), (0.0f32, 1.0f32), (1.0f32, 2.0f32)],
+        rect.edges
+    );
+
+    let mut tree_map = RStar::new_with_max(4);
+    for i in 0..64 {
+        assert!(tree_map.try_insert(Shapes::Point(grid_point(i)), i).is_ok());
+    }
+    // public fields and deserialization can still produce non-finite geometry
+    let nan_point = Point {
+        coords: [1.0f32, f32::NAN, 1.0f32],
+    };
+    let nan_segment = LineSegment {
+        x: Point::new([0.0f32; 3]),
+        y: nan_point.clone(),
+    };
+    match tree_map.try_insert(Shapes::Point(nan_point), 64) {
+        Err(InsertError::NonFiniteGeometry(_, item)) => assert_eq!(64, item),
+        Ok(()) => panic!("a NaN point should be rejected"),
+    }
+    assert!(tree_map
+        .try_insert(Shapes::LineSegment(nan_segment), 65)
+        .is_err());
+    assert_eq!(64, tree_map.len());
+    let found = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [9.5f32, 9.5f32, 0.5f32],
+        )))
+        .count();
+    assert_eq!(64, found);
+}
+
+#[test]
+fn rstar_remove_one() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..200 {
+        tree_map.insert(grid_point(i), i);
+    }
+    // a second item sharing the geometry of 42
+    tree_map.insert(grid_point(42), 1042);
+    assert_eq!(
+        None,
+        tree_map.remove_one(&grid_point(42), &43).map(|(_, i)| i)
+    );
+    assert_eq!(
+        None,
+        tree_map.remove_one(&grid_point(500), &500).map(|(_, i)| i)
+    );
+    assert_eq!(201, tree_map.len());
+
+    let (geometry, item) = tree_map.remove_one(&grid_point(42), &1042).unwrap();
+    assert_eq!(grid_point(42), geometry);
+    assert_eq!(1042, item);
+    assert_eq!(200, tree_map.len());
+    let remaining: Vec<i32> = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [1.5f32, 3.5f32, -0.5f32],
+            [2.5f32, 4.5f32, 0.5f32],
+        )))
+        .map(|(_, &i)| i)
+        .collect();
+    assert_eq!(vec![42], remaining);
+
+    for i in (0..200).rev() {
+        assert_eq!(
+            Some(i),
+            tree_map.remove_one(&grid_point(i), &i).map(|(_, i)| i)
+        );
+        assert_eq!(i as usize, tree_map.len());
+        assert_eq!(tree_map.len(), tree_map.iter().count());
+    }
+    assert!(tree_map.is_empty());
+}
+
+#[test]
+fn rstar_update_geometry() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..200 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let nudged = Point::new([3.25f32, 4.0f32, 1.0f32]);
+    assert!(tree_map.update_geometry(&grid_point(143), |&i| i == 143, nudged.clone()));
+    assert!(!tree_map.update_geometry(&grid_point(143), |&i| i == 143, grid_point(0)));
+    assert!(!tree_map.update_geometry(&grid_point(144), |&i| i == 143, grid_point(0)));
+
+    // far enough to leave its leaf level
+    let moved = Point::new([50.0f32, 50.0f32, 50.0f32]);
+    let mut matched = 0;
+    assert!(tree_map.update_geometry(
+        &grid_point(7),
+        |&i| {
+            matched += (i == 7) as usize;
+            i == 7
+        },
+        moved.clone()
+    ));
+    assert_eq!(1, matched);
+    assert_eq!(200, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+
+    let nearest: Vec<(Point<f32, 3>, i32)> = tree_map
+        .nearest(&Point::new([49.0f32; 3]), 1)
+        .into_iter()
+        .chain(tree_map.nearest(&Point::new([3.2f32, 4.0f32, 1.0f32]), 1))
+        .map(|(point, &i)| (point.clone(), i))
+        .collect();
+    assert_eq!(vec![(moved, 7), (nudged, 143)], nearest);
+
+    // every level still bounds its contents
+    let found = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [9.5f32, 9.5f32, 1.5f32],
+        )))
+        .count();
+    assert_eq!(199, found);
+    for i in (0..200).filter(|&i| i != 7) {
+        let geometry = tree_map
+            .iter()
+            .find(|&(_, &item)| item == i)
+            .map(|(point, _)| point.clone())
+            .unwrap();
+        assert!(tree_map.remove_one(&geometry, &i).is_some());
+    }
+    assert_eq!(1, tree_map.len());
+}
+
+#[test]
+fn rstar_entry() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..200 {
+        tree_map.insert(grid_point(i), i);
+    }
+    match tree_map.entry(grid_point(57)) {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(grid_point(57), *entry.key());
+            assert_eq!(57, *entry.get());
+            assert_eq!(57, entry.insert(570));
+        }
+        Entry::Vacant(_) => panic!("57 is in the map"),
+    }
+    *tree_map.entry(grid_point(57)).or_insert(0) += 1;
+    assert_eq!(
+        vec![571],
+        tree_map
+            .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+                [6.5f32, 4.5f32, -0.5f32],
+                [7.5f32, 5.5f32, 0.5f32],
+            )))
+            .map(|(_, &i)| i)
+            .collect::<Vec<i32>>()
+    );
+
+    // upsert new locations
+    for i in 150..250 {
+        tree_map
+            .entry(grid_point(i))
+            .and_modify(|item| *item = -*item)
+            .or_insert(i);
+    }
+    assert_eq!(250, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    for i in 0..250 {
+        let expected = match i {
+            57 => 571,
+            150..=199 => -i,
+            _ => i,
+        };
+        assert_eq!(expected, *tree_map.entry(grid_point(i)).or_default());
+    }
+
+    match tree_map.entry(grid_point(100)) {
+        Entry::Occupied(entry) => assert_eq!((grid_point(100), 100), entry.remove_entry()),
+        Entry::Vacant(_) => panic!("100 is in the map"),
+    }
+    assert!(matches!(tree_map.entry(grid_point(100)), Entry::Vacant(_)));
+    assert_eq!(249, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+}
+
+#[test]
+fn rstar_entry_follows_reinserts() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..500 {
+        let item = tree_map.entry(grid_point(i)).or_insert(i);
+        assert_eq!(i, *item);
+        *item = -i;
+    }
+    assert_eq!(500, tree_map.len());
+    assert!(tree_map.iter().all(|(point, &i)| *point == grid_point(-i)));
+
+    for i in (0..500).step_by(2) {
+        match tree_map.entry(grid_point(i)) {
+            Entry::Occupied(entry) => assert_eq!((grid_point(i), -i), entry.remove_entry()),
+            Entry::Vacant(_) => panic!("{} is in the map", i),
+        }
+    }
+    assert_eq!(250, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    assert!(tree_map
+        .iter()
+        .all(|(point, &i)| i % 2 != 0 && *point == grid_point(-i)));
+}
+
+#[test]
+fn rstar_owning_iterators() {
+    let mut tree_map: RStarTree<f32, 3, Point<f32, 3>, i32> =
+        (0..300).map(|i| (grid_point(i), i)).collect();
+    assert_eq!(300, tree_map.len());
+    tree_map.extend((300..400).map(|i| (grid_point(i), i)));
+    assert_eq!(400, tree_map.len());
+
+    for (_, item) in &mut tree_map {
+        *item *= 2;
+    }
+    assert_eq!(
+        (0..400).map(|i| i * 2).sum::<i32>(),
+        (&tree_map).into_iter().map(|(_, &i)| i).sum::<i32>()
+    );
+
+    let drained: Vec<(Point<f32, 3>, i32)> = tree_map
+        .drain_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [9.5f32, 9.5f32, 0.5f32],
+        )))
+        .collect();
+    assert_eq!(100, drained.len());
+    assert!(drained
+        .iter()
+        .all(|(point, i)| point.coords[2] == 0.0f32 && i % 2 == 0));
+    assert_eq!(300, tree_map.len());
+
+    let drain = tree_map.drain();
+    assert_eq!(300, drain.len());
+    let mut items: Vec<i32> = drain.map(|(_, i)| i / 2).collect();
+    items.sort_unstable();
+    assert_eq!((100..400).collect::<Vec<i32>>(), items);
+    assert!(tree_map.is_empty());
+    assert_eq!(0, tree_map.iter().count());
+
+    tree_map.extend((0..50).map(|i| (grid_point(i), i)));
+    let mut into_iter = tree_map.into_iter();
+    assert_eq!(50, into_iter.len());
+    into_iter.next();
+    assert_eq!((49, Some(49)), into_iter.size_hint());
+    assert_eq!(49, into_iter.count());
+}
+
+#[test]
+fn rstar_raycast() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..1000 {
+        tree_map.insert(Shapes::Point(grid_point(i)), i);
+    }
+    tree_map.insert(
+        Shapes::Rect(Rect::from_corners(
+            [2.5f32, 2.5f32, 4.5f32],
+            [3.5f32, 3.5f32, 6.5f32],
+        )),
+        -1,
+    );
+    tree_map.insert(
+        Shapes::LineSegment(LineSegment::new(
+            [3.0f32, 0.0f32, 7.5f32],
+            [3.0f32, 9.0f32, 7.5f32],
+        )),
+        -2,
+    );
+
+    // straight up the column at x = 3, y = 3
+    let ray = Ray::new([3.0f32, 3.0f32, -1.0f32], [0.0f32, 0.0f32, 2.0f32]);
+    let hits: Vec<(f32, i32)> = tree_map.raycast(&ray).map(|(t, _, &i)| (t, i)).collect();
+    let expected = vec![
+        (0.5f32, 33),
+        (1.0f32, 133),
+        (1.5f32, 233),
+        (2.0f32, 333),
+        (2.5f32, 433),
+        (2.75f32, -1),
+        (3.0f32, 533),
+        (3.5f32, 633),
+        (4.0f32, 733),
+        (4.25f32, -2),
+        (4.5f32, 833),
+        (5.0f32, 933),
+    ];
+    assert_eq!(expected.len(), hits.len());
+    for (&(expected_t, expected_i), &(t, i)) in expected.iter().zip(hits.iter()) {
+        assert!((expected_t - t).abs() < 0.0001f32);
+        assert_eq!(expected_i, i);
+    }
+    assert_eq!(Some(33), tree_map.raycast_first(&ray).map(|(_, _, &i)| i));
+
+    // a diagonal that threads through grid points
+    let diagonal = Ray::new([-1.0f32, -1.0f32, -1.0f32], [1.0f32, 1.0f32, 1.0f32]);
+    let hits: Vec<i32> = tree_map
+        .raycast(&diagonal)
+        .filter(|&(_, _, &i)| i >= 0)
+        .map(|(_, _, &i)| i)
+        .collect();
+    assert_eq!((0..10).map(|i| i * 111).collect::<Vec<i32>>(), hits);
+
+    let away = Ray::new([-1.0f32; 3], [-1.0f32, 0.0f32, 0.0f32]);
+    assert!(tree_map.raycast_first(&away).is_none());
+}
+
+#[test]
+fn rstar_segment_overlaps() {
+    let mut tree_map = RStar::new_with_max(8);
+    // diagonal roads across a 100 x 100 grid
+    for i in 0..100 {
+        let offset = i as f32;
+        tree_map.insert(
+            LineSegment::new([offset, 0.0f32], [offset + 10.0f32, 10.0f32]),
+            i,
+        );
+    }
+    // the query overlaps the mbr of every road from 39 to 49, but only 44 to 49 reach it
+    let mut found: Vec<i32> = tree_map
+        .iter_query(MbrRectQuery::Overlaps(Rect::from_corners(
+            [49.0f32, 4.0f32],
+            [60.0f32, 5.0f32],
+        )))
+        .map(|(_, &i)| i)
+        .filter(|&i| i < 50)
+        .collect();
+    found.sort_unstable();
+    assert_eq!(vec![44, 45, 46, 47, 48, 49], found);
+}
+
+#[test]
+fn rstar_query_combinators() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..1000 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let region_a = || {
+        MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [5.5f32, 5.5f32, 5.5f32],
+        ))
+    };
+    let region_b = || {
+        MbrRectQuery::Overlaps(Rect::from_corners(
+            [2.5f32, -0.5f32, -0.5f32],
+            [9.5f32, 2.5f32, 9.5f32],
+        ))
+    };
+    let in_a = |i: i32| i % 10 <= 5 && i / 10 % 10 <= 5 && i / 100 <= 5;
+    let in_b = |i: i32| i % 10 >= 3 && i / 10 % 10 <= 2;
+    let sorted = |mut items: Vec<i32>| {
+        items.sort_unstable();
+        items
+    };
+
+    let query = Filter(And(region_a(), Not(region_b())), |&i: &i32| i % 2 == 0);
+    let found = sorted(tree_map.iter_query(query).map(|(_, &i)| i).collect());
+    let expected: Vec<i32> = (0..1000)
+        .filter(|&i| in_a(i) && !in_b(i) && i % 2 == 0)
+        .collect();
+    assert_eq!(expected, found);
+
+    let found = sorted(
+        tree_map
+            .iter_query(Or(region_a(), region_b()))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    let expected: Vec<i32> = (0..1000).filter(|&i| in_a(i) || in_b(i)).collect();
+    assert_eq!(expected, found);
+
+    // everything outside of a region that covers the whole tree
+    let everything = MbrRectQuery::Overlaps(Rect::from_corners([-1.0f32; 3], [10.0f32; 3]));
+    assert_eq!(0, tree_map.iter_query(Not(everything.clone())).count());
+    assert_eq!(1000, tree_map.iter_query(Not(Not(everything))).count());
+
+    let removed = tree_map.remove(And(Not(region_a()), region_b()));
+    assert_eq!(
+        (0..1000).filter(|&i| !in_a(i) && in_b(i)).count(),
+        removed.len()
+    );
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+}
+
+#[test]
+fn rstar_sphere_query() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..1000 {
+        tree_map.insert(Shapes::Point(grid_point(i)), i);
+    }
+    tree_map.insert(
+        Shapes::LineSegment(LineSegment::new(
+            [3.0f32, 3.0f32, 3.0f32],
+            [3.0f32, 3.0f32, 9.0f32],
+        )),
+        -1,
+    );
+    tree_map.insert(
+        Shapes::Rect(Rect::from_corners(
+            [4.5f32, 4.5f32, 4.5f32],
+            [5.5f32, 5.5f32, 5.5f32],
+        )),
+        -2,
+    );
+    let center = Point::new([5.0f32, 5.0f32, 5.0f32]);
+    let within = |i: i32, radius: i32| {
+        let (x, y, z) = (i % 10 - 5, i / 10 % 10 - 5, i / 100 - 5);
+        x * x + y * y + z * z <= radius * radius
+    };
+    let sorted = |mut items: Vec<i32>| {
+        items.sort_unstable();
+        items
+    };
+
+    // the segment passes within sqrt(8) of the center, but its far end is sqrt(24) away
+    let found = sorted(
+        tree_map
+            .iter_query(MbrSphereQuery::overlaps(center.clone(), 3.0f32))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    let mut expected: Vec<i32> = (0..1000).filter(|&i| within(i, 3)).collect();
+    expected.splice(0..0, vec![-2, -1]);
+    assert_eq!(expected, found);
+
+    let found = sorted(
+        tree_map
+            .iter_query(MbrSphereQuery::contains(center.clone(), 3.0f32))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    let mut expected: Vec<i32> = (0..1000).filter(|&i| within(i, 3)).collect();
+    expected.insert(0, -2);
+    assert_eq!(expected, found);
+
+    // the half-unit rect's corners are sqrt(0.75) from the center
+    assert_eq!(
+        vec![-2],
+        tree_map
+            .iter_query(MbrSphereQuery::overlaps(center.clone(), 0.5f32))
+            .filter(|&(_, &i)| i < 0)
+            .map(|(_, &i)| i)
+            .collect::<Vec<i32>>()
+    );
+    assert_eq!(
+        0,
+        tree_map
+            .iter_query(MbrSphereQuery::contains(center.clone(), 0.85f32))
+            .filter(|&(_, &i)| i < 0)
+            .count()
+    );
+    let outside = (0..1000).filter(|&i| !within(i, 3)).count();
+    assert_eq!(
+        outside,
+        tree_map
+            .iter_query(Not(MbrSphereQuery::overlaps(center, 3.0f32)))
+            .count()
+    );
+}
+
+#[test]
+fn rstar_point_query() {
+    let mut tree_map = RStar::new_with_max(4);
+    // nested and neighbouring zones
+    for i in 0..10 {
+        let lo = i as f32;
+        tree_map.insert(
+            Shapes::Rect(Rect::from_corners([lo, lo], [lo + 1.0f32, lo + 1.0f32])),
+            i,
+        );
+        tree_map.insert(
+            Shapes::Rect(Rect::from_corners([-lo, -lo], [lo + 1.0f32, lo + 1.0f32])),
+            100 + i,
+        );
+    }
+    tree_map.insert(
+        Shapes::LineSegment(LineSegment::new([0.0f32, 4.0f32], [8.0f32, 0.0f32])),
+        -1,
+    );
+    tree_map.insert(Shapes::Point(Point::new([2.5f32, 2.5f32])), -2);
+    let sorted = |mut items: Vec<i32>| {
+        items.sort_unstable();
+        items
+    };
+
+    let found = sorted(
+        tree_map
+            .iter_query(MbrPointQuery::new(Point::new([2.5f32, 2.5f32])))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert_eq!(vec![-2, 2, 102, 103, 104, 105, 106, 107, 108, 109], found);
+
+    // on a shared corner, boundary inclusive
+    let found = sorted(
+        tree_map
+            .iter_query(MbrPointQuery::new(Point::new([3.0f32, 3.0f32])))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert_eq!(vec![2, 3, 102, 103, 104, 105, 106, 107, 108, 109], found);
+
+    // the segment passes through (4, 2), which is only found with a tolerance
+    let near_segment = Point::new([4.0f32, 2.0001f32]);
+    assert!(tree_map
+        .iter_query(MbrPointQuery::new(near_segment.clone()))
+        .all(|(_, &i)| i != -1));
+    assert!(tree_map
+        .iter_query(MbrPointQuery::with_tolerance(near_segment, 0.001f32))
+        .any(|(_, &i)| i == -1));
+
+    assert_eq!(
+        0,
+        tree_map
+            .iter_query(MbrPointQuery::new(Point::new([20.0f32, 0.0f32])))
+            .count()
+    );
+}
+
+#[test]
+fn rstar_join() {
+    // parcels in one tree, points and footprints in trees of another kind and height
+    let mut parcels = RStar::new_with_max(6);
+    for i in 0..50 {
+        let lo = grid_point(i * 2);
+        let corner = [lo[0] + 0.5f32, lo[1] + 0.5f32, lo[2] + 0.5f32];
+        parcels.insert(
+            Rect::from_corners(corner, [corner[0] + 1.5, corner[1] + 1.5, corner[2] + 1.5]),
+            i,
+        );
+    }
+    let mut points = RTree::new_linear_with_max(32);
+    for i in 0..300 {
+        points.insert(grid_point(i), format!("point {}", i));
+    }
+    let mut footprints = RTree::new_quadratic_with_max(4);
+    for i in 0..100 {
+        let lo = grid_point(i * 3 + 1);
+        let corner = [lo[0] + 0.25f32, lo[1] + 0.25f32, lo[2] + 0.25f32];
+        footprints.insert(
+            Shapes::Rect(Rect::from_corners(
+                corner,
+                [corner[0] + 0.5, corner[1] + 0.5, corner[2] + 0.5],
+            )),
+            i as u64,
+        );
+    }
+
+    let mut joined: Vec<(i32, String)> = parcels
+        .join(&points)
+        .map(|((_, &i), (_, name))| (i, name.clone()))
+        .collect();
+    joined.sort();
+    let mut expected = Vec::new();
+    for (parcel, &i) in parcels.iter() {
+        for (point, name) in points.iter() {
+            if point.overlapped_by_mbr(parcel) {
+                expected.push((i, name.clone()));
+            }
+        }
+    }
+    expected.sort();
+    assert!(!expected.is_empty());
+    assert_eq!(expected, joined);
+
+    let mut joined: Vec<(u64, i32)> = footprints
+        .join(&parcels)
+        .map(|((_, &i), (_, &j))| (i, j))
+        .collect();
+    joined.sort_unstable();
+    let mut expected = Vec::new();
+    for (footprint, &i) in footprints.iter() {
+        for (parcel, &j) in parcels.iter() {
+            if footprint.overlapped_by_mbr(parcel) {
+                expected.push((i, j));
+            }
+        }
+    }
+    expected.sort_unstable();
+    assert!(!expected.is_empty());
+    assert_eq!(expected, joined);
+
+    let empty: RStarTree<f32, 3, Point<f32, 3>, ()> = RStar::new();
+    assert_eq!(0, parcels.join(&empty).count());
+
+    // line strings and polygons are compared by their shapes, not just their mbrs
+    let mut districts = RStar::new_with_max(4);
+    districts.insert(
+        Polygon::new(
+            vec![[0.0f64, 0.0f64], [2.0f64, 0.0f64], [0.0f64, 2.0f64]],
+            vec![],
+        ),
+        0,
+    );
+    districts.insert(
+        Polygon::new(
+            vec![
+                [3.0f64, 0.0f64],
+                [6.0f64, 0.0f64],
+                [6.0f64, 3.0f64],
+                [3.0f64, 3.0f64],
+            ],
+            vec![vec![
+                [4.0f64, 1.0f64],
+                [5.0f64, 1.0f64],
+                [5.0f64, 2.0f64],
+                [4.0f64, 2.0f64],
+            ]],
+        ),
+        1,
+    );
+    let mut roads = RStar::new_with_max(4);
+    // alongside the triangle's long edge, inside its mbr
+    roads.insert(LineString::new(vec![[0.2f64, 1.9f64], [1.9f64, 0.2f64]]), 0);
+    // inside the triangle without touching its edges
+    roads.insert(LineString::new(vec![[0.5f64, 0.5f64], [0.8f64, 0.6f64]]), 1);
+    // inside the hole
+    roads.insert(LineString::new(vec![[4.2f64, 1.5f64], [4.8f64, 1.5f64]]), 2);
+    roads.insert(LineString::new(vec![[1.0f64, 3.0f64], [4.0f64, 0.5f64]]), 3);
+    // touching the triangle's long edge and the square's corner
+    roads.insert(LineString::new(vec![[1.0f64, 1.0f64], [3.0f64, 3.0f64]]), 4);
+    let mut joined: Vec<(i32, i32)> = roads
+        .join(&districts)
+        .map(|((_, &i), (_, &j))| (i, j))
+        .collect();
+    joined.sort_unstable();
+    assert_eq!(vec![(1, 0), (3, 1), (4, 0), (4, 1)], joined);
+}
+
+#[test]
+fn rstar_overlapping_pairs() {
+    let mut tree_map = RStar::new_with_max(5);
+    for i in 0..200 {
+        let lo = grid_point(i * 7 % 200);
+        let size = (i % 3) as f32 * 0.5f32 + 0.25f32;
+        tree_map.insert(
+            Rect::from_corners(
+                [lo[0], lo[1], lo[2]],
+                [lo[0] + size, lo[1] + size, lo[2] + size],
+            ),
+            i,
+        );
+    }
+    // identical geometries are still reported as a pair
+    tree_map.insert(Rect::from_corners([0.0f32; 3], [0.25f32; 3]), 200);
+
+    let brute_force = |epsilon: f32| {
+        let mut expected = Vec::new();
+        for (a, &i) in tree_map.iter() {
+            for (b, &j) in tree_map.iter() {
+                if i < j && a.min_distance_from_mbr(b) <= epsilon {
+                    expected.push((i, j));
+                }
+            }
+        }
+        expected.sort_unstable();
+        expected
+    };
+    let sorted = |mut found: Vec<(i32, i32)>| {
+        found.sort_unstable();
+        found
+    };
+
+    let expected = brute_force(0.0f32);
+    assert!(expected.contains(&(0, 200)));
+    let found = sorted(
+        tree_map
+            .overlapping_pairs()
+            .map(|((_, &i), (_, &j))| (i.min(j), i.max(j)))
+            .collect(),
+    );
+    assert_eq!(expected, found);
+
+    let expected = brute_force(0.5f32);
+    let found = sorted(
+        tree_map
+            .overlapping_pairs_within(0.5f32)
+            .map(|((_, &i), (_, &j))| (i.min(j), i.max(j)))
+            .collect(),
+    );
+    assert!(found.len() > brute_force(0.0f32).len());
+    assert_eq!(expected, found);
+
+    let mut points = RStar::new_with_max(8);
+    for i in 0..300 {
+        points.insert(grid_point(i), i);
+    }
+    // each point is within 1 of its axis neighbours
+    let neighbours = 9 * 10 * 3 + 10 * 9 * 3 + 10 * 10 * 2;
+    assert_eq!(neighbours, points.overlapping_pairs_within(1.0f32).count());
+    assert_eq!(0, points.overlapping_pairs().count());
+
+    // diagonal roads 0.3 apart share most of their mbrs, and only cross the road going the other way
+    let mut roads = RStar::new_with_max(4);
+    roads.insert(LineString::new(vec![[0.0f64, 0.0f64], [4.0f64, 4.0f64]]), 0);
+    roads.insert(LineString::new(vec![[0.3f64, 0.0f64], [4.3f64, 4.0f64]]), 1);
+    roads.insert(LineString::new(vec![[0.0f64, 4.0f64], [4.0f64, 0.0f64]]), 2);
+    let pairs = |epsilon: f64| {
+        sorted(
+            roads
+                .overlapping_pairs_within(epsilon)
+                .map(|((_, &i), (_, &j))| (i.min(j), i.max(j)))
+                .collect(),
+        )
+    };
+    assert_eq!(vec![(0, 2), (1, 2)], pairs(0.0f64));
+    assert_eq!(vec![(0, 2), (1, 2)], pairs(0.2f64));
+    assert_eq!(vec![(0, 1), (0, 2), (1, 2)], pairs(0.25f64));
+
+    // diagonal neighbours share their mbrs' corners without the spheres touching
+    let mut bubbles = RStar::new_with_max(6);
+    for i in 0..300 {
+        bubbles.insert(
+            Sphere::new(grid_point(i).coords, 0.3f32 + (i % 3) as f32 * 0.15f32),
+            i,
+        );
+    }
+    for epsilon in [0.0f32, 0.3f32] {
+        let mut expected = Vec::new();
+        for (a, &i) in bubbles.iter() {
+            for (b, &j) in bubbles.iter() {
+                if i < j && a.center.distance_from_point(&b.center) <= a.radius + b.radius + epsilon
+                {
+                    expected.push((i, j));
+                }
+            }
+        }
+        expected.sort_unstable();
+        let found = sorted(
+            bubbles
+                .overlapping_pairs_within(epsilon)
+                .map(|((_, &i), (_, &j))| (i.min(j), i.max(j)))
+                .collect(),
+        );
+        assert_eq!(expected, found);
+    }
+
+    let mut panes = RStar::new_with_max(4);
+    panes.insert(
+        Triangle::new(
+            [0.0f64, 0.0f64, 0.0f64],
+            [2.0f64, 0.0f64, 0.0f64],
+            [0.0f64, 2.0f64, 0.0f64],
+        ),
+        0,
+    );
+    // an edge piercing the first triangle
+    panes.insert(
+        Triangle::new(
+            [0.5f64, 0.5f64, -1.0f64],
+            [0.5f64, 0.5f64, 1.0f64],
+            [1.0f64, 0.3f64, 0.0f64],
+        ),
+        1,
+    );
+    // beyond the first triangle's long edge, inside its mbr
+    panes.insert(
+        Triangle::new(
+            [1.5f64, 1.5f64, -0.5f64],
+            [1.5f64, 1.5f64, 0.5f64],
+            [1.9f64, 1.9f64, 0.0f64],
+        ),
+        2,
+    );
+    let pairs = |epsilon: f64| {
+        sorted(
+            panes
+                .overlapping_pairs_within(epsilon)
+                .map(|((_, &i), (_, &j))| (i.min(j), i.max(j)))
+                .collect(),
+        )
+    };
+    assert_eq!(vec![(0, 1)], pairs(0.0f64));
+    assert_eq!(vec![(0, 1)], pairs(0.7f64));
+    assert_eq!(vec![(0, 1), (0, 2)], pairs(0.75f64));
+}
+
+#[test]
+fn rstar_polygon_query() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..400 {
+        tree_map.insert(
+            Point::new([(i % 20) as f64 * 0.5f64, (i / 20) as f64 * 0.5f64]),
+            i,
+        );
+    }
+    // a boundary with a lake, and a notch that cuts into the middle
+    let boundary = Polygon::new(
+        vec![
+            [0.25f64, 0.25f64],
+            [8.75f64, 0.25f64],
+            [8.75f64, 8.75f64],
+            [5.25f64, 8.75f64],
+            [5.25f64, 4.25f64],
+            [3.75f64, 4.25f64],
+            [3.75f64, 8.75f64],
+            [0.25f64, 8.75f64],
+        ],
+        vec![vec![
+            [1.25f64, 1.25f64],
+            [2.75f64, 1.25f64],
+            [2.75f64, 2.75f64],
+            [1.25f64, 2.75f64],
+        ]],
+    );
+    let sorted = |mut items: Vec<i32>| {
+        items.sort_unstable();
+        items
+    };
+    let expected = sorted(
+        tree_map
+            .iter()
+            .filter(|&(point, _)| boundary.contains_point(point))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    let found = sorted(
+        tree_map
+            .iter_query(MbrPolygonQuery::Overlaps(boundary.clone()))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert_eq!(17 * 17 - 9 - 3 * 9, expected.len());
+    assert_eq!(expected, found);
+    let found = sorted(
+        tree_map
+            .iter_query(MbrPolygonQuery::ContainedBy(boundary.clone()))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert_eq!(expected, found);
+    let outside = tree_map
+        .iter_query(Not(MbrPolygonQuery::Overlaps(boundary.clone())))
+        .count();
+    assert_eq!(tree_map.len() - expected.len(), outside);
+
+    // polygons are leaves too
+    let mut districts = RStar::new_with_max(4);
+    for i in 0..64 {
+        let (x, y) = ((i % 8) as f64, (i / 8) as f64);
+        districts.insert(
+            Polygon::new(
+                vec![[x, y], [x + 1.0f64, y], [x + 0.5f64, y + 1.0f64]],
+                vec![],
+            ),
+            i,
+        );
+    }
+    let found = sorted(
+        districts
+            .iter_query(MbrPointQuery::new(Point::new([2.5f64, 3.9f64])))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert_eq!(vec![26], found);
+    // the rect sits left of 26's slope with its top face on 34's base at y = 4.
+    // Like rects, polygons only overlap an mbr that reaches their interior, so resting on 34 doesn't match it
+    let found = sorted(
+        districts
+            .iter_query(MbrRectQuery::Overlaps(Rect::from_corners(
+                [2.1f64, 3.9f64],
+                [2.2f64, 4.0f64],
+            )))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert!(found.is_empty());
+    let (_, &nearest) = districts.nearest(&Point::new([-1.0f64, 0.0f64]), 1)[0];
+    assert_eq!(0, nearest);
+}
+
+#[test]
+fn rstar_polygon_query_polygons() {
+    let mut districts = RStar::new_with_max(4);
+    for i in 0..64 {
+        let (x, y) = ((i % 8) as f64, (i / 8) as f64);
+        districts.insert(
+            Polygon::new(
+                vec![[x, y], [x + 1.0f64, y], [x + 0.5f64, y + 1.0f64]],
+                vec![],
+            ),
+            i,
+        );
+    }
+    // an L whose mbr covers the query's notch, though the L itself stays out of it
+    districts.insert(
+        Polygon::new(
+            vec![
+                [0.0f64, 0.0f64],
+                [3.0f64, 0.0f64],
+                [3.0f64, 1.0f64],
+                [1.0f64, 1.0f64],
+                [1.0f64, 3.0f64],
+                [0.0f64, 3.0f64],
+            ],
+            vec![],
+        ),
+        100,
+    );
+    // a U with its notch between x = 1 and x = 2, above y = 1
+    let u_shape = Polygon::new(
+        vec![
+            [0.0f64, 0.0f64],
+            [3.5f64, 0.0f64],
+            [3.5f64, 3.0f64],
+            [2.0f64, 3.0f64],
+            [2.0f64, 1.0f64],
+            [1.0f64, 1.0f64],
+            [1.0f64, 3.0f64],
+            [0.0f64, 3.0f64],
+        ],
+        vec![],
+    );
+    let sorted = |mut items: Vec<i32>| {
+        items.sort_unstable();
+        items
+    };
+    // triangles in the notch or resting on the U's top only touch it
+    let found = sorted(
+        districts
+            .iter_query(MbrPolygonQuery::Overlaps(u_shape.clone()))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert_eq!(vec![0, 1, 2, 3, 8, 10, 11, 16, 18, 19, 100], found);
+    let found = sorted(
+        districts
+            .iter_query(MbrPolygonQuery::ContainedBy(u_shape))
+            .map(|(_, &i)| i)
+            .collect(),
+    );
+    assert_eq!(vec![0, 1, 2, 8, 10, 16, 18, 100], found);
+}
+
+#[test]
+fn rstar_query_touching_node_boundary() {
+    let mut tree_map = RStar::new_with_max(4);
+    for i in 0..121 {
+        tree_map.insert(Point::new([(i % 11) as f64, (i / 11) as f64]), i);
+    }
+    // nothing lies beyond x = 10, so every node holding a point on that line has it on its mbr's face
+    let touching = Rect::from_corners([10.0f64, -1.0f64], [20.0f64, 11.0f64]);
+    let expected: Vec<i32> = (0..11).map(|i| i * 11 + 10).collect();
+    let mut found: Vec<i32> = tree_map
+        .iter_query(MbrRectQuery::Overlaps(touching.clone()))
+        .map(|(_, &i)| i)
+        .collect();
+    found.sort_unstable();
+    assert_eq!(expected, found);
+    let mut found: Vec<i32> = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(touching.clone()))
+        .map(|(_, &i)| i)
+        .collect();
+    found.sort_unstable();
+    assert_eq!(expected, found);
+
+    // rect leaves have to reach into an Overlaps query, so cells ending at x = 10 don't match one starting there.
+    // ContainedBy is boundary inclusive, so the last column is contained by a query starting at x = 9
+    let mut cells = RStar::new_with_max(4);
+    for i in 0..100 {
+        let (x, y) = ((i % 10) as f64, (i / 10) as f64);
+        cells.insert(Rect::from_corners([x, y], [x + 1.0f64, y + 1.0f64]), i);
+    }
+    let touching = Rect::from_corners([10.0f64, -1.0f64], [20.0f64, 11.0f64]);
+    assert_eq!(0, cells.iter_query(MbrRectQuery::Overlaps(touching)).count());
+    let touching = Rect::from_corners([9.0f64, -1.0f64], [20.0f64, 11.0f64]);
+    let expected: Vec<i32> = (0..10).map(|i| i * 10 + 9).collect();
+    let mut found: Vec<i32> = cells
+        .iter_query(MbrRectQuery::ContainedBy(touching))
+        .map(|(_, &i)| i)
+        .collect();
+    found.sort_unstable();
+    assert_eq!(expected, found);
+}
+
+#[test]
+fn rstar_line_strings() {
+    let mut tree_map = RStar::new_with_max(4);
+    // zig-zag roads, each one a single leaf
+    for i in 0..50 {
+        let (x, y) = ((i % 10) as f32 * 10.0f32, (i / 10) as f32 * 10.0f32);
+        let road = LineString::new(vec![
+            [x, y],
+            [x + 4.0f32, y + 4.0f32],
+            [x + 8.0f32, y],
+            [x + 8.0f32, y + 8.0f32],
+        ]);
+        assert_eq!(3, road.segments().count());
+        tree_map.insert(Shapes::LineString(road), i);
+    }
+    tree_map.insert(Shapes::Point(Point::new([3.0f32, 1.0f32])), -1);
+    assert_eq!(51, tree_map.len());
+
+    // inside the first road's mbr, between its segments
+    let pocket = Rect::from_corners([3.5f32, 0.5f32], [4.5f32, 1.5f32]);
+    assert!(tree_map
+        .iter_query(MbrRectQuery::Overlaps(pocket.clone()))
+        .next()
+        .is_none());
+    let crossing = Rect::from_corners([3.5f32, 3.5f32], [4.5f32, 4.5f32]);
+    let found: Vec<i32> = tree_map
+        .iter_query(MbrRectQuery::Overlaps(crossing))
+        .map(|(_, &i)| i)
+        .collect();
+    assert_eq!(vec![0], found);
+
+    // every road is exactly its segments
+    for (geometry, &i) in tree_map.iter() {
+        if let Shapes::LineString(ref road) = *geometry {
+            let overlaps = road
+                .segments()
+                .any(|segment| segment.overlapped_by_mbr(&pocket));
+            assert_eq!(overlaps, geometry.overlapped_by_mbr(&pocket), "road {}", i);
+        }
+    }
+
+    let found: Vec<i32> = tree_map
+        .nearest(&Point::new([6.0f32, 1.0f32]), 2)
+        .iter()
+        .map(|&(_, &i)| i)
+        .collect();
+    assert_eq!(vec![0, -1], found);
+    let found: Vec<i32> = tree_map
+        .iter_query(MbrPointQuery::with_tolerance(
+            Point::new([16.0f32, 3.0f32]),
+            0.5f32,
+        ))
+        .map(|(_, &i)| i)
+        .collect();
+    assert!(found.is_empty());
+    let found: Vec<i32> = tree_map
+        .iter_query(MbrPointQuery::with_tolerance(
+            Point::new([18.0f32, 3.0f32]),
+            0.5f32,
+        ))
+        .map(|(_, &i)| i)
+        .collect();
+    assert_eq!(vec![1], found);
+
+    assert_eq!(
+        Err(GeometryError::TooFewVertices),
+        LineString::try_new(vec![[0.0f32, 0.0f32]])
+    );
+}
+
+#[test]
+fn rstar_scene_primitives() {
+    // a terrain mesh of two triangles per grid cell, sloping up along x
+    let mut terrain = RStar::new_with_max(8);
+    let height = |x: f32| x * 0.5f32;
+    for i in 0..100 {
+        let (x, y) = ((i % 10) as f32, (i / 10) as f32);
+        let corner = |dx: f32, dy: f32| [x + dx, y + dy, height(x + dx)];
+        terrain.insert(
+            Triangle::new(corner(0.0, 0.0), corner(1.0, 0.0), corner(0.0, 1.0)),
+            i * 2,
+        );
+        terrain.insert(
+            Triangle::new(corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)),
+            i * 2 + 1,
+        );
+    }
+    let down = Ray::new([3.25f32, 7.25f32, 20.0f32], [0.0f32, 0.0f32, -1.0f32]);
+    let (t, _, &i) = terrain.raycast_first(&down).unwrap();
+    assert_eq!((7 * 10 + 3) * 2, i);
+    assert!((t - (20.0f32 - height(3.25f32))).abs() < 1e-4);
+    // a box floating above the slope only meets the mbrs of the triangles below it
+    let above = Rect::from_corners([2.1f32, 2.1f32, 2.0f32], [2.9f32, 2.9f32, 3.0f32]);
+    assert_eq!(0, terrain.iter_query(MbrRectQuery::Overlaps(above)).count());
+
+    let mut bubbles = RStar::new_with_max(6);
+    let mut pipes = RStar::new_with_max(6);
+    for i in 0..300 {
+        let center = grid_point(i);
+        let radius = 0.2f32 + (i % 4) as f32 * 0.1f32;
+        bubbles.insert(Sphere::new(center.coords, radius), i);
+        let mut end = center.coords;
+        end[0] += 0.5f32;
+        pipes.insert(Capsule::new(center.coords, end, radius * 0.5f32), i);
+    }
+    let query = Rect::from_corners([2.35f32, 2.45f32, 0.45f32], [4.65f32, 3.55f32, 1.55f32]);
+    let sorted = |mut items: Vec<i32>| {
+        items.sort_unstable();
+        items
+    };
+    for (found, expected) in [
+        (
+            sorted(
+                bubbles
+                    .iter_query(MbrRectQuery::Overlaps(query.clone()))
+                    .map(|(_, &i)| i)
+                    .collect(),
+            ),
+            sorted(
+                bubbles
+                    .iter()
+                    .filter(|(s, _)| s.overlapped_by_mbr(&query))
+                    .map(|(_, &i)| i)
+                    .collect(),
+            ),
+        ),
+        (
+            sorted(
+                pipes
+                    .iter_query(MbrRectQuery::Overlaps(query.clone()))
+                    .map(|(_, &i)| i)
+                    .collect(),
+            ),
+            sorted(
+                pipes
+                    .iter()
+                    .filter(|(c, _)| c.overlapped_by_mbr(&query))
+                    .map(|(_, &i)| i)
+                    .collect(),
+            ),
+        ),
+    ] {
+        assert!(!expected.is_empty());
+        assert_eq!(expected, found);
+    }
+    // the query box's corners are farther than any radius from the grid points they surround
+    let corner_only = Rect::from_corners([2.45f32, 2.45f32, 0.45f32], [2.55f32, 2.55f32, 0.55f32]);
+    assert_eq!(
+        0,
+        bubbles
+            .iter_query(MbrRectQuery::Overlaps(corner_only))
+            .count()
+    );
+
+    let nearest: Vec<i32> = pipes
+        .nearest(&Point::new([5.6f32, 5.0f32, 1.0f32]), 1)
+        .iter()
+        .map(|&(_, &i)| i)
+        .collect();
+    assert_eq!(vec![155], nearest);
+}
+
+#[test]
+fn rstar_custom_shapes() {
+    let mut tree_map = RStar::new_with_max(6);
+    for i in 0..100 {
+        tree_map.insert(Shapes::Point(grid_point(i)), i);
+    }
+    // application shapes mixed in with the built-in ones
+    for i in 0..20 {
+        let center = grid_point(i * 5);
+        let mut coords = center.coords;
+        coords[2] = 3.0f32;
+        if i % 2 == 0 {
+            tree_map.insert(
+                Shapes::Custom(Box::new(Sphere::new(coords, 0.5f32))),
+                100 + i,
+            );
+        } else {
+            let mut far = coords;
+            far[0] += 1.0f32;
+            let mut up = coords;
+            up[1] += 1.0f32;
+            tree_map.insert(
+                Shapes::Custom(Box::new(Triangle::new(coords, far, up))),
+                100 + i,
+            );
+        }
+    }
+    assert_eq!(120, tree_map.len());
+
+    let query = Rect::from_corners([-0.5f32, -0.5f32, 2.5f32], [9.5f32, 9.5f32, 3.5f32]);
+    let mut found: Vec<i32> = tree_map
+        .iter_query(MbrRectQuery::Overlaps(query))
+        .map(|(_, &i)| i)
+        .collect();
+    found.sort_unstable();
+    assert_eq!((100..120).collect::<Vec<_>>(), found);
+
+    // the sphere at (0, 0, 3) is nearer than any grid point
+    let nearest: Vec<i32> = tree_map
+        .nearest(&Point::new([0.0f32, 0.0f32, 2.0f32]), 1)
+        .iter()
+        .map(|&(_, &i)| i)
+        .collect();
+    assert_eq!(vec![100], nearest);
+
+    let triangle = Shapes::Custom(Box::new(Triangle::new(
+        [5.0f32, 0.0f32, 3.0f32],
+        [6.0f32, 0.0f32, 3.0f32],
+        [5.0f32, 1.0f32, 3.0f32],
+    )));
+    match tree_map.entry(triangle.clone()) {
+        Entry::Occupied(entry) => assert_eq!(101, entry.remove()),
+        Entry::Vacant(_) => panic!("the triangle is in the map"),
+    }
+    assert!(matches!(tree_map.entry(triangle), Entry::Vacant(_)));
+    assert_eq!(119, tree_map.len());
+}
+
+#[test]
+fn rstar_integer_coordinates() {
+    let mut tree_map = RStar::new_with_max(8);
+    for i in 0..1000 {
+        let (x, y) = (i % 40 * 16, i / 40 * 16);
+        tree_map.insert(Rect::from_corners([x, y], [x + 15, y + 15]), i);
+    }
+    // tiles that span the whole coordinate range, whose areas and margins don't fit in an i32 or even an i64
+    tree_map.insert(
+        Rect::from_corners([i32::MIN, i32::MIN], [i32::MAX, i32::MAX]),
+        -1,
+    );
+    tree_map.insert(Rect::from_corners([i32::MIN, 0], [i32::MAX, 1]), -2);
+    tree_map.insert(
+        Rect::from_corners([i32::MAX, i32::MAX], [i32::MAX, i32::MAX]),
+        -3,
+    );
+    assert_eq!(1003, tree_map.len());
+
+    let mut found: Vec<i32> = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [0, 0],
+            [31, 31],
+        )))
+        .map(|(_, &i)| i)
+        .collect();
+    found.sort_unstable();
+    assert_eq!(vec![0, 1, 40, 41], found);
+
+    // distances are measured as f64
+    let nearest: Vec<(f64, i32)> = tree_map
+        .iter_nearest(&Point::new([i32::MAX, i32::MAX - 10]))
+        .take(2)
+        .map(|(distance, _, &i)| (distance, i))
+        .collect();
+    assert_eq!(vec![(0.0f64, -1), (10.0f64, -3)], nearest);
+
+    let removed = tree_map.remove(MbrRectQuery::Overlaps(Rect::from_corners([-1, -1], [1, 1])));
+    assert_eq!(3, removed.len());
+    assert_eq!(1000, tree_map.len());
+
+    let mut grid: RStarTree<u16, 3, Point<u16, 3>, usize> = RStar::new_with_max(16);
+    grid.bulk_load((0..4096).map(|i| {
+        (
+            Point::new([i as u16 % 16, i as u16 / 16 % 16, i as u16 / 256]),
+            i,
+        )
+    }));
+    grid.insert(Point::new([u16::MAX; 3]), 4096);
+    let found = grid
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [0, 0, 0],
+            [7, 15, 15],
+        )))
+        .count();
+    assert_eq!(2048, found);
+    assert_eq!(
+        4097,
+        grid.remove(MbrRectQuery::ContainedBy(Rect::max())).len()
+    );
+
+    // 128 bit and pointer sized integers
+    let step = u128::MAX / 128;
+    let mut wide: RStarTree<u128, 2, Point<u128, 2>, usize> = RStar::new_with_max(8);
+    let mut indexes: RStarTree<usize, 2, Point<usize, 2>, usize> = RStar::new_with_max(8);
+    for i in 0..100usize {
+        wide.insert(Point::new([i as u128 * step, 0]), i);
+        indexes.insert(Point::new([i, i]), i);
+    }
+    let found = wide
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [0, 0],
+            [9 * step, 0],
+        )))
+        .count();
+    assert_eq!(10, found);
+    let (_, &nearest) = indexes.nearest(&Point::new([60, 58]), 1)[0];
+    assert_eq!(59, nearest);
+}
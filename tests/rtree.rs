@@ -1,9 +1,13 @@
 extern crate spatial;
 
 use spatial::geometry::{Point, Rect};
-use spatial::tree::mbr::MbrRectQuery;
+use spatial::tree::mbr::{Entry, MbrRectQuery};
 use spatial::RTree;
 
+fn grid_point(i: i32) -> Point<f32, 3> {
+    Point::new([(i % 10) as f32, (i / 10 % 10) as f32, (i / 100) as f32])
+}
+
 #[test]
 fn rlinear_integration() {
     let mut tree_map = RTree::new_linear_with_max(16);
@@ -87,3 +91,160 @@ fn rquadratic_integration() {
     assert_eq!(32, tree_map.len());
     assert_eq!(tree_map.len(), tree_map.iter().count());
 }
+
+#[test]
+fn rlinear_nearest() {
+    let mut tree_map = RTree::new_linear_with_max(16);
+    for i in 0..64 {
+        let i_f32 = i as f32;
+        tree_map.insert(Point::new([i_f32; 3]), i);
+    }
+    let nearest: Vec<i32> = tree_map
+        .nearest(&Point::new([10.2f32; 3]), 3)
+        .into_iter()
+        .map(|(_, &i)| i)
+        .collect();
+    assert_eq!(vec![10, 11, 9], nearest);
+    assert_eq!(64, tree_map.nearest(&Point::new([0.0f32; 3]), 100).len());
+    assert!(tree_map.nearest(&Point::new([0.0f32; 3]), 0).is_empty());
+}
+
+#[test]
+fn rquadratic_nearest() {
+    let mut tree_map = RTree::new_quadratic_with_max(16);
+    for i in 0..64 {
+        let i_f32 = i as f32;
+        tree_map.insert(Point::new([i_f32; 3]), i);
+    }
+    let nearest: Vec<i32> = tree_map
+        .nearest(&Point::new([10.2f32; 3]), 3)
+        .into_iter()
+        .map(|(_, &i)| i)
+        .collect();
+    assert_eq!(vec![10, 11, 9], nearest);
+    assert_eq!(64, tree_map.nearest(&Point::new([0.0f32; 3]), 100).len());
+    assert!(tree_map.nearest(&Point::new([0.0f32; 3]), 0).is_empty());
+}
+
+#[test]
+fn rlinear_bulk_load() {
+    let mut tree_map = RTree::new_linear_with_max(16);
+    tree_map.insert(Point::new([-1.0f32; 3]), -1);
+    tree_map.bulk_load((0..1000).map(|i| (grid_point(i), i)));
+    assert_eq!(1001, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    let found = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [4.5f32, 9.5f32, 9.5f32],
+        )))
+        .count();
+    assert_eq!(500, found);
+
+    // the packed tree keeps working with the regular insert and remove algorithms
+    for i in 1000..1100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    assert_eq!(1101, tree_map.len());
+    let removed = tree_map.remove(MbrRectQuery::ContainedBy(Rect::from_corners(
+        [-0.5f32, -0.5f32, -0.5f32],
+        [9.5f32, 4.5f32, 10.5f32],
+    )));
+    assert_eq!(550, removed.len());
+    assert_eq!(551, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+}
+
+#[test]
+fn rquadratic_bulk_load() {
+    let mut tree_map = RTree::new_quadratic_with_max(16);
+    tree_map.insert(Point::new([-1.0f32; 3]), -1);
+    tree_map.bulk_load((0..1000).map(|i| (grid_point(i), i)));
+    assert_eq!(1001, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    let found = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(Rect::from_corners(
+            [-0.5f32, -0.5f32, -0.5f32],
+            [4.5f32, 9.5f32, 9.5f32],
+        )))
+        .count();
+    assert_eq!(500, found);
+
+    // the packed tree keeps working with the regular insert and remove algorithms
+    for i in 1000..1100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    assert_eq!(1101, tree_map.len());
+    let removed = tree_map.remove(MbrRectQuery::ContainedBy(Rect::from_corners(
+        [-0.5f32, -0.5f32, -0.5f32],
+        [9.5f32, 4.5f32, 10.5f32],
+    )));
+    assert_eq!(550, removed.len());
+    assert_eq!(551, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+}
+
+#[test]
+fn rtree_integer_coordinates() {
+    // the linear seed's separation is negative for overlapping children, which an unsigned subtraction can't hold
+    let mut linear = RTree::new_linear_with_max(8);
+    let mut quadratic = RTree::new_quadratic_with_max(8);
+    for i in 0..1000u16 {
+        let point = Point::new([i % 10 * 1000, i / 10 % 10 * 1000, i / 100 * 1000]);
+        linear.insert(point.clone(), i);
+        quadratic.insert(point, i);
+    }
+    linear.insert(Point::new([u16::MAX, 0, u16::MAX]), 1000);
+    quadratic.insert(Point::new([u16::MAX, 0, u16::MAX]), 1000);
+    let query = Rect::from_corners([0, 0, 0], [4000, 9000, 9000]);
+    assert_eq!(
+        500,
+        linear
+            .iter_query(MbrRectQuery::ContainedBy(query.clone()))
+            .count()
+    );
+    assert_eq!(
+        500,
+        quadratic
+            .iter_query(MbrRectQuery::ContainedBy(query.clone()))
+            .count()
+    );
+    assert_eq!(
+        500,
+        linear
+            .remove(MbrRectQuery::ContainedBy(query.clone()))
+            .len()
+    );
+    assert_eq!(
+        501,
+        quadratic
+            .retain(MbrRectQuery::ContainedBy(Rect::max()), |&i| i < 500)
+            .len()
+    );
+    assert_eq!(501, linear.len());
+    assert_eq!(500, quadratic.len());
+}
+
+#[test]
+fn rtree_entry_follows_splits() {
+    let mut tree_map = RTree::new_quadratic_with_max(8);
+    for i in 0..500 {
+        let item = tree_map.entry(grid_point(i)).or_insert(i);
+        assert_eq!(i, *item);
+        *item = -i;
+    }
+    assert_eq!(500, tree_map.len());
+    assert!(tree_map.iter().all(|(point, &i)| *point == grid_point(-i)));
+
+    for i in (0..500).step_by(2) {
+        match tree_map.entry(grid_point(i)) {
+            Entry::Occupied(entry) => assert_eq!((grid_point(i), -i), entry.remove_entry()),
+            Entry::Vacant(_) => panic!("{} is in the map", i),
+        }
+    }
+    assert_eq!(250, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    assert!(tree_map
+        .iter()
+        .all(|(point, &i)| i % 2 != 0 && *point == grid_point(-i)));
+}
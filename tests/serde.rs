@@ -0,0 +1,178 @@
+#![cfg(feature = "serde")]
+
+extern crate serde_json;
+extern crate spatial;
+
+use spatial::geometry::{LineSegment, LineString, Point, Polygon, Ray, Rect, Shapes, Sphere};
+use spatial::tree::mbr::MbrRectQuery;
+use spatial::{RLinearTree, RStar, RStarTree, RTree, XTree, XTreeMap};
+
+fn grid_point(i: i32) -> Point<f32, 3> {
+    Point::new([(i % 10) as f32, (i / 10 % 10) as f32, (i / 100) as f32])
+}
+
+#[test]
+fn serde_shapes() {
+    let shapes = vec![
+        Shapes::Point(Point::new([1.0f64, 2.0f64])),
+        Shapes::LineSegment(LineSegment::new([0.0f64, 1.0f64], [2.0f64, 3.0f64])),
+        Shapes::Rect(Rect::from_corners([-1.0f64, -2.0f64], [1.0f64, 2.0f64])),
+    ];
+    let json = serde_json::to_string(&shapes).unwrap();
+    let deserialized: Vec<Shapes<f64, 2>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+
+    // custom shapes have no serialized form
+    let custom = Shapes::Custom(Box::new(Point::new([1.0f64, 2.0f64])));
+    assert!(serde_json::to_string(&custom).is_err());
+
+    // wrong number of coordinates
+    assert!(serde_json::from_str::<Point<f64, 3>>(r#"{"coords":[1.0,2.0]}"#).is_err());
+}
+
+#[test]
+fn serde_validates_geometry() {
+    // 1e400 overflows to infinity
+    assert!(serde_json::from_str::<Point<f64, 2>>(r#"{"coords":[1e400,2.0]}"#).is_err());
+    assert!(serde_json::from_str::<Rect<f64, 2>>(r#"{"edges":[[0.0,1e400],[0.0,1.0]]}"#).is_err());
+    // leaf rects are ordered like Rect::new, only level mbrs may be inverted
+    let rect: Rect<f64, 2> = serde_json::from_str(r#"{"edges":[[1.0,0.0],[0.0,1.0]]}"#).unwrap();
+    assert_eq!(Rect::from_corners([0.0, 0.0], [1.0, 1.0]), rect);
+    assert!(serde_json::from_str::<Sphere<f64, 2>>(
+        r#"{"center":{"coords":[0.0,0.0]},"radius":-1.0}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<Ray<f64, 2>>(
+        r#"{"origin":{"coords":[0.0,0.0]},"direction":[0.0,0.0]}"#
+    )
+    .is_err());
+
+    // cached mbrs are recomputed from the vertices
+    let line: LineString<f64, 2> = serde_json::from_str(
+        r#"{"vertices":[{"coords":[0.0,0.0]},{"coords":[2.0,3.0]}],"mbr":{"edges":[[5.0,6.0],[5.0,6.0]]}}"#,
+    )
+    .unwrap();
+    assert_eq!(&Rect::from_corners([0.0, 0.0], [2.0, 3.0]), line.mbr());
+
+    let polygon = Polygon::new(
+        vec![[0.0f64, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+        vec![],
+    );
+    let json = serde_json::to_string(&polygon)
+        .unwrap()
+        .replace(r#"[[0.0,4.0],[0.0,4.0]]"#, r#"[[9.0,9.0],[9.0,9.0]]"#);
+    assert!(json.contains("9.0"));
+    let deserialized: Polygon<f64> = serde_json::from_str(&json).unwrap();
+    assert_eq!(polygon.mbr(), deserialized.mbr());
+}
+
+#[test]
+fn serde_rstar() {
+    let mut tree_map = RStar::new_with_max(16);
+    for i in 0..500 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let json = serde_json::to_string(&tree_map).unwrap();
+    let mut deserialized: RStarTree<f32, 3, Point<f32, 3>, i32> =
+        serde_json::from_str(&json).unwrap();
+    // identical structure, not a rebuild
+    assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+    assert_eq!(500, deserialized.len());
+
+    let query = MbrRectQuery::ContainedBy(Rect::from_corners(
+        [-0.5f32, -0.5f32, -0.5f32],
+        [4.5f32, 9.5f32, 9.5f32],
+    ));
+    assert_eq!(250, deserialized.iter_query(query.clone()).count());
+    for i in 500..600 {
+        deserialized.insert(grid_point(i), i);
+    }
+    assert_eq!(300, deserialized.remove(query).len());
+    assert_eq!(300, deserialized.len());
+}
+
+#[test]
+fn serde_empty() {
+    let tree_map = RStar::<f32, 3, Rect<f32, 3>, i32>::new();
+    let json = serde_json::to_string(&tree_map).unwrap();
+    let mut deserialized: RStarTree<f32, 3, Rect<f32, 3>, i32> =
+        serde_json::from_str(&json).unwrap();
+    assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+    deserialized.insert(Rect::from_corners([0.0f32; 3], [1.0f32; 3]), 0);
+    assert_eq!(
+        1,
+        deserialized
+            .iter_query(MbrRectQuery::Overlaps(Rect::max()))
+            .count()
+    );
+}
+
+#[test]
+fn serde_rlinear() {
+    let mut tree_map = RTree::new_linear_with_max(8);
+    for i in 0..100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let json = serde_json::to_string(&tree_map).unwrap();
+    let deserialized: RLinearTree<f32, 3, Point<f32, 3>, i32> =
+        serde_json::from_str(&json).unwrap();
+    assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+    assert_eq!(100, deserialized.iter().count());
+}
+
+#[test]
+fn serde_xtree() {
+    let mut tree_map = XTree::new_with_max(8);
+    for i in 0..100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let json = serde_json::to_string(&tree_map).unwrap();
+    let deserialized: XTreeMap<f32, 3, Point<f32, 3>, i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+    assert_eq!(100, deserialized.iter().count());
+}
+
+#[test]
+fn serde_validates_tree() {
+    type Tree = RStarTree<f32, 3, Point<f32, 3>, i32>;
+    let mut tree_map = RStar::new_with_max(16);
+    for i in 0..100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let json = serde_json::to_value(&tree_map).unwrap();
+
+    // the length is recounted from the leaves, so removing every entry can't underflow it
+    let mut tampered = json.clone();
+    tampered["len"] = 3.into();
+    let mut deserialized: Tree = serde_json::from_value(tampered).unwrap();
+    assert_eq!(100, deserialized.len());
+    let removed = deserialized.remove(MbrRectQuery::Overlaps(Rect::max()));
+    assert_eq!(100, removed.len());
+    assert_eq!(0, deserialized.len());
+
+    let mut tampered = json.clone();
+    tampered["insert_index"]["max"] = 0.into();
+    assert!(serde_json::from_value::<Tree>(tampered).is_err());
+    let mut tampered = json.clone();
+    tampered["insert_index"]["preferred_min"] = 17.into();
+    assert!(serde_json::from_value::<Tree>(tampered).is_err());
+    let mut tampered = json.clone();
+    tampered["remove_index"]["min"] = 0.into();
+    assert!(serde_json::from_value::<Tree>(tampered).is_err());
+
+    let mut tree_map = XTree::new_with_max(8);
+    for i in 0..100 {
+        tree_map.insert(grid_point(i), i);
+    }
+    let mut tampered = serde_json::to_value(&tree_map).unwrap();
+    tampered["insert_index"]["preferred_min"] = 5.into();
+    assert!(serde_json::from_value::<XTreeMap<f32, 3, Point<f32, 3>, i32>>(tampered).is_err());
+
+    // wrapping one leaf level in another level leaves the tree unbalanced
+    let mut tampered = json.clone();
+    let child = &mut tampered["root"]["Level"]["children"][0];
+    let mbr = child["Leaves"]["mbr"].clone();
+    *child = serde_json::json!({"Level": {"mbr": mbr, "children": [child.take()]}});
+    let error = serde_json::from_value::<Tree>(tampered).err().unwrap();
+    assert!(error.to_string().contains("same depth"), "{}", error);
+}
@@ -0,0 +1,203 @@
+extern crate spatial;
+
+use spatial::geometry::{Point, Rect};
+use spatial::tree::mbr::{Entry, MbrRectQuery};
+use spatial::{XTree, XTreeMap};
+
+/// Deterministic coordinates spread over [0, 1)
+fn scattered_point<const DIM: usize>(i: usize) -> Point<f64, DIM> {
+    let mut coords = [0.0f64; DIM];
+    for (axis, coord) in coords.iter_mut().enumerate() {
+        // splitmix64
+        let mut hash = ((i * DIM + axis) as u64).wrapping_add(0x9E3779B97F4A7C15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D049BB133111EB);
+        hash ^= hash >> 31;
+        *coord = (hash % 1000) as f64 / 1000.0f64;
+    }
+    Point::new(coords)
+}
+
+#[test]
+fn xtree_integration() {
+    let mut tree_map = XTree::new_with_max(16);
+    for i in 0..32 {
+        let i_f32 = i as f32;
+        tree_map.insert(Point::new([i_f32; 3]), i);
+    }
+    assert_eq!(32, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    assert_eq!(tree_map.len(), tree_map.iter_mut().count());
+
+    let removed = tree_map.remove(MbrRectQuery::ContainedBy(Rect::from_corners(
+        [0.0f32, 0.0f32, 0.0f32],
+        [9.0f32, 9.0f32, 9.0f32],
+    )));
+    assert_eq!(10, removed.len());
+    assert_eq!(22, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+
+    let removed_retain = tree_map.retain(MbrRectQuery::ContainedBy(Rect::max()), |x| *x >= 20);
+    assert_eq!(10, removed_retain.len());
+    assert_eq!(12, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+
+    let retain_none = tree_map.remove(MbrRectQuery::ContainedBy(Rect::max()));
+    assert_eq!(12, retain_none.len());
+    assert_eq!(0, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+
+    for i in 0..32 {
+        let i_f32 = i as f32;
+        tree_map.insert(Point::new([i_f32; 3]), i);
+    }
+    assert_eq!(32, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+}
+
+#[test]
+fn xtree_high_dimensional() {
+    let mut tree_map = XTree::new_with_max(16);
+    for i in 0..2000 {
+        tree_map.insert(scattered_point::<16>(i), i);
+    }
+    assert_eq!(2000, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+
+    let query = Rect::from_corners([0.0f64; 16], [0.85f64; 16]);
+    let expected: Vec<usize> = (0..2000)
+        .filter(|&i| scattered_point::<16>(i).iter().all(|&x| x <= 0.85f64))
+        .collect();
+    let mut found: Vec<usize> = tree_map
+        .iter_query(MbrRectQuery::ContainedBy(query.clone()))
+        .map(|(_, &i)| i)
+        .collect();
+    found.sort();
+    assert!(!expected.is_empty());
+    assert_eq!(expected, found);
+
+    for (_, item) in tree_map.iter_query_mut(MbrRectQuery::ContainedBy(query.clone())) {
+        *item += 2000;
+    }
+    let mut removed: Vec<usize> = tree_map
+        .remove(MbrRectQuery::ContainedBy(query))
+        .into_iter()
+        .map(|(_, i)| i - 2000)
+        .collect();
+    removed.sort();
+    assert_eq!(expected, removed);
+    assert_eq!(2000 - expected.len(), tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+
+    // everything still reachable after condensing
+    let removed_all = tree_map.remove(MbrRectQuery::ContainedBy(Rect::max()));
+    assert_eq!(2000 - removed.len(), removed_all.len());
+    assert!(tree_map.is_empty());
+}
+
+#[test]
+fn xtree_nearest() {
+    let mut tree_map = XTree::new_with_max(16);
+    for i in 0..64 {
+        let i_f32 = i as f32;
+        tree_map.insert(Point::new([i_f32; 3]), i);
+    }
+    let nearest: Vec<i32> = tree_map
+        .nearest(&Point::new([10.2f32; 3]), 3)
+        .into_iter()
+        .map(|(_, &i)| i)
+        .collect();
+    assert_eq!(vec![10, 11, 9], nearest);
+}
+
+#[test]
+fn xtree_remove_one() {
+    let mut tree_map = XTree::new_with_max(8);
+    for i in 0..500 {
+        tree_map.insert(scattered_point::<4>(i), i);
+    }
+    for i in (0..500).filter(|i| i % 3 == 0) {
+        let (geometry, item) = tree_map.remove_one(&scattered_point(i), &i).unwrap();
+        assert_eq!(scattered_point::<4>(i), geometry);
+        assert_eq!(i, item);
+    }
+    assert_eq!(333, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    assert!(tree_map.remove_one(&scattered_point(0), &0).is_none());
+    assert!(tree_map.remove_one(&scattered_point(1), &2).is_none());
+    assert!(tree_map
+        .iter()
+        .all(|(point, &i)| i % 3 != 0 && *point == scattered_point(i)));
+}
+
+#[test]
+fn xtree_entry_follows_splits() {
+    let mut tree_map = XTree::new_with_max(8);
+    for i in 0..1000 {
+        let item = tree_map.entry(scattered_point::<16>(i)).or_insert(i);
+        assert_eq!(i, *item);
+        *item += 1000;
+    }
+    assert_eq!(1000, tree_map.len());
+    assert!(tree_map
+        .iter()
+        .all(|(point, &i)| *point == scattered_point(i - 1000)));
+
+    for i in (0..1000).step_by(2) {
+        match tree_map.entry(scattered_point(i)) {
+            Entry::Occupied(entry) => {
+                assert_eq!((scattered_point(i), i + 1000), entry.remove_entry())
+            }
+            Entry::Vacant(_) => panic!("{} is in the map", i),
+        }
+    }
+    assert_eq!(500, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    assert!(tree_map
+        .iter()
+        .all(|(point, &i)| i % 2 != 0 && *point == scattered_point(i - 1000)));
+}
+
+#[test]
+fn xtree_from_iter() {
+    let tree_map: XTreeMap<f64, 16, Point<f64, 16>, usize> =
+        (0..1000).map(|i| (scattered_point(i), i)).collect();
+    assert_eq!(1000, tree_map.len());
+    let mut items: Vec<usize> = tree_map.into_iter().map(|(_, i)| i).collect();
+    items.sort_unstable();
+    assert_eq!((0..1000).collect::<Vec<usize>>(), items);
+}
+
+#[test]
+fn xtree_bulk_load() {
+    let mut tree_map = XTree::new_with_max(16);
+    tree_map.bulk_load((0..2000).map(|i| (scattered_point::<16>(i), i)));
+    assert_eq!(2000, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    for i in 2000..2100 {
+        tree_map.insert(scattered_point::<16>(i), i);
+    }
+    let removed = tree_map.retain(MbrRectQuery::ContainedBy(Rect::max()), |&i| i % 2 == 0);
+    assert_eq!(1050, removed.len());
+    assert_eq!(1050, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+}
+
+#[test]
+fn xtree_integer_coordinates() {
+    let mut tree_map = XTree::new_with_max(8);
+    for i in 0..2000i64 {
+        let mut coords = [0i64; 8];
+        for (axis, coord) in coords.iter_mut().enumerate() {
+            *coord = (i * 7919 + axis as i64 * 104_729) % 2003 * (i64::MAX / 4096);
+        }
+        tree_map.insert(Rect::from_corners(coords, coords), i);
+    }
+    // spans every axis, so the volumes of splits that include it overflow any integer
+    tree_map.insert(Rect::from_corners([i64::MIN; 8], [i64::MAX; 8]), -1);
+    assert_eq!(2001, tree_map.len());
+    assert_eq!(tree_map.len(), tree_map.iter().count());
+    let removed = tree_map.retain(MbrRectQuery::ContainedBy(Rect::max()), |&i| i % 2 == 0);
+    assert_eq!(1001, removed.len());
+    assert_eq!(1000, tree_map.len());
+}